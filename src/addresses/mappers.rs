@@ -1,4 +1,6 @@
 pub const CPU_MIN_ADDRESS: u16 = 0x8000;
 pub const CPU_MAX_ADDRESS: u16 = 0xFFFF;
 pub const PPU_MIN_ADDRESS: u16 = 0x0000;
-pub const PPU_MAX_ADDRESS: u16 = 0x1FFF;
\ No newline at end of file
+pub const PPU_MAX_ADDRESS: u16 = 0x1FFF;
+pub const PRG_RAM_MIN_ADDRESS: u16 = 0x6000;
+pub const PRG_RAM_MAX_ADDRESS: u16 = 0x7FFF;
@@ -0,0 +1,110 @@
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+pub const ROM_EXTENSIONS: [&str; 4] = ["nes", "fds", "nsf", "nsfe"];
+
+pub fn is_archive(file_path: &str) -> bool {
+    let extension = get_extension(file_path);
+    extension == "zip" || extension == "gz" || (cfg!(feature = "sevenz") && extension == "7z")
+}
+
+/// Names of the ROMs inside the archive, in the order they're stored
+pub fn list_roms(file_path: &str) -> Vec<String> {
+    let names = match get_extension(file_path).as_str() {
+        "zip" => {
+            let archive = zip::ZipArchive::new(open(file_path)).expect("Error reading zip archive");
+            archive.file_names().map(|name| name.to_owned()).collect()
+        },
+        "gz" => vec![get_gzip_name(file_path)],
+        #[cfg(feature = "sevenz")]
+        "7z" => {
+            let reader = sevenz_rust::SevenZReader::open(file_path, sevenz_rust::Password::empty()).expect("Error reading 7z archive");
+            reader.archive().files.iter()
+                .filter(|entry| entry.has_stream() && !entry.is_directory())
+                .map(|entry| entry.name().to_owned())
+                .collect()
+        },
+        _ => panic!("Unrecognized archive extension")
+    };
+
+    names.into_iter().filter(|name| ROM_EXTENSIONS.contains(&get_extension(name).as_str())).collect()
+}
+
+/// Extracts a ROM into a folder named after the archive, next to it, and returns where it was written.
+/// The loaders keep saves, patches and quick saves next to the ROM so it has to stay somewhere permanent.
+pub fn extract(file_path: &str, rom_name: &str) -> String {
+    let data = match get_extension(file_path).as_str() {
+        "zip" => {
+            let mut archive = zip::ZipArchive::new(open(file_path)).expect("Error reading zip archive");
+            read_all(archive.by_name(rom_name).expect("ROM is missing from zip archive"))
+        },
+        "gz" => read_all(GzDecoder::new(open(file_path))),
+        #[cfg(feature = "sevenz")]
+        "7z" => {
+            let mut reader = sevenz_rust::SevenZReader::open(file_path, sevenz_rust::Password::empty()).expect("Error reading 7z archive");
+            let mut data = None;
+            reader.for_each_entries(|entry, entry_reader| {
+                if entry.name() == rom_name {
+                    data = Some(read_all(entry_reader));
+                } else {
+                    std::io::copy(entry_reader, &mut std::io::sink())?;
+                }
+
+                Ok(data.is_none())
+            }).expect("Error extracting 7z archive");
+
+            data.expect("ROM is missing from 7z archive")
+        },
+        _ => panic!("Unrecognized archive extension")
+    };
+
+    let mut rom_path = get_folder(file_path);
+    fs::create_dir_all(&rom_path).expect("Error creating folder for extracted ROM");
+
+    // Only keep the file name so entries in subfolders, or with .. in them, can't end up outside the folder
+    rom_path.push(Path::new(rom_name).file_name().expect("ROM in archive has no file name"));
+    fs::write(&rom_path, data).expect("Error writing extracted ROM");
+    rom_path.to_str().expect("Error converting extracted ROM path to string").to_owned()
+}
+
+/// game.zip and game.nes.gz are both extracted into a game folder
+fn get_folder(file_path: &str) -> PathBuf {
+    let folder = Path::new(file_path).with_extension("");
+    if ROM_EXTENSIONS.contains(&get_extension(folder.to_str().unwrap_or_default()).as_str()) {
+        folder.with_extension("")
+    } else {
+        folder
+    }
+}
+
+/// Gzip can store the original file name, otherwise game.nes.gz is assumed to hold game.nes
+fn get_gzip_name(file_path: &str) -> String {
+    let decoder = GzDecoder::new(open(file_path));
+    let stored_name = decoder.header()
+        .and_then(|header| header.filename())
+        .map(|name| String::from_utf8_lossy(name).into_owned());
+
+    stored_name.unwrap_or_else(|| {
+        let stem = Path::new(file_path).file_stem().expect("Error getting name of archive");
+        stem.to_str().expect("Error converting archive name to string").to_owned()
+    })
+}
+
+fn get_extension(file_path: &str) -> String {
+    Path::new(file_path).extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn open(file_path: &str) -> File {
+    File::open(file_path).expect("Cannot find archive")
+}
+
+fn read_all(mut reader: impl Read) -> Vec<u8> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).expect("Error reading ROM from archive");
+    data
+}
@@ -0,0 +1,107 @@
+use std::env;
+use crate::audio;
+use crate::palette::ntsc::NtscSettings;
+use crate::region::Region;
+
+/// rust-nes [options] [file]
+/// --database file: A JSON database of header corrections to use alongside the built in one
+/// --headless: Runs the game without a window or sound, reading REPL commands from stdin until it closes
+/// --gif-skip count: Keeps one frame out of every count when recording a GIF, 1 by default
+/// --float-wav: Records audio with F5 as 32 bit float rather than 16 bit
+/// --hue degrees, --saturation amount, --contrast amount, --brightness amount, --gamma amount:
+///     Settings for the generated NTSC palette, 0, 1, 1, 0 and 2.2 by default
+/// --movie file: Plays back a movie recorded with F8, or an FCEUX .fm2 movie
+/// --palette name|file: Starts with a built in palette, 2C02, NTSC, FCEUX, NestopiaYUV, Smooth or PVM, or a .pal file.
+///     F11 cycles through them
+/// --patch file: An IPS, BPS or UPS patch to apply to the ROM, can be given more than once to apply several in order
+/// --record-gif file: Records an animated GIF from power on
+/// --record-video file: Records a Y4M video from power on, with the sound in file.wav
+/// --region ntsc|pal|dendy: Overrides the region detected from the header
+/// --sample-rate rate: The audio output rate, from 22050 to 96000, 44100 by default
+/// --stereo: Plays sound in stereo, 0 cycles through where each channel is panned
+/// --repl: Reads RAM search and watch commands from stdin while the game runs
+pub struct Arguments {
+    pub database: Option<String>,
+    pub file_path: Option<String>,
+    pub float_wav: bool,
+    pub gif_skip: u32,
+    pub headless: bool,
+    pub movie: Option<String>,
+    pub ntsc: NtscSettings,
+    pub palette: Option<String>,
+    pub patches: Vec<String>,
+    pub record_gif: Option<String>,
+    pub record_video: Option<String>,
+    pub region: Option<Region>,
+    pub repl: bool,
+    pub sample_rate: i32,
+    pub stereo: bool
+}
+
+impl Arguments {
+    pub fn new() -> Self {
+        let mut arguments = Arguments {
+            database: None,
+            file_path: None,
+            float_wav: false,
+            gif_skip: 1,
+            headless: false,
+            movie: None,
+            ntsc: Default::default(),
+            palette: None,
+            patches: vec![],
+            record_gif: None,
+            record_video: None,
+            region: None,
+            repl: false,
+            sample_rate: audio::DEFAULT_SAMPLE_RATE,
+            stereo: false
+        };
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--database" => arguments.database = Some(args.next().expect("--database needs a file")),
+                "--float-wav" => arguments.float_wav = true,
+                "--gif-skip" => {
+                    let value = args.next().expect("--gif-skip needs a value");
+                    arguments.gif_skip = value.parse().expect("GIF frame skip should be a number");
+                },
+                "--headless" => arguments.headless = true,
+                "--hue" => arguments.ntsc.hue = parse_setting(&mut args, "--hue"),
+                "--saturation" => arguments.ntsc.saturation = parse_setting(&mut args, "--saturation"),
+                "--contrast" => arguments.ntsc.contrast = parse_setting(&mut args, "--contrast"),
+                "--brightness" => arguments.ntsc.brightness = parse_setting(&mut args, "--brightness"),
+                "--gamma" => arguments.ntsc.gamma = parse_setting(&mut args, "--gamma"),
+                "--palette" => arguments.palette = Some(args.next().expect("--palette needs a name or file")),
+                "--movie" => arguments.movie = Some(args.next().expect("--movie needs a file")),
+                "--patch" => arguments.patches.push(args.next().expect("--patch needs a file")),
+                "--record-gif" => arguments.record_gif = Some(args.next().expect("--record-gif needs a file")),
+                "--record-video" => arguments.record_video = Some(args.next().expect("--record-video needs a file")),
+                "--region" => {
+                    let value = args.next().expect("--region needs a value");
+                    arguments.region = Some(Region::from(&value).expect("Region should be ntsc, pal or dendy"));
+                },
+                "--repl" => arguments.repl = true,
+                "--stereo" => arguments.stereo = true,
+                "--sample-rate" => {
+                    let value = args.next().expect("--sample-rate needs a value");
+                    let sample_rate = value.parse().expect("Sample rate should be a number");
+                    if !(audio::MIN_SAMPLE_RATE..=audio::MAX_SAMPLE_RATE).contains(&sample_rate) {
+                        panic!("Sample rate should be between {} and {}", audio::MIN_SAMPLE_RATE, audio::MAX_SAMPLE_RATE);
+                    }
+
+                    arguments.sample_rate = sample_rate;
+                },
+                _ => arguments.file_path = Some(arg)
+            }
+        }
+
+        arguments
+    }
+}
+
+fn parse_setting(args: &mut impl Iterator<Item = String>, name: &str) -> f32 {
+    let value = args.next().unwrap_or_else(|| panic!("{} needs a value", name));
+    value.parse().unwrap_or_else(|_| panic!("{} should be a number", name))
+}
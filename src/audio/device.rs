@@ -1,61 +1,86 @@
+use std::io;
 use std::sync::{Arc, Mutex};
 use sdl2::audio::{AudioCallback, AudioSpecDesired};
-use super::filter::{Filter, Coefficient};
-
-const CPU_SAMPLE_RATE: f32 = 1_789_773.0;
-const APU_SAMPLE_RATE: f32 = CPU_SAMPLE_RATE / 2.0;
-const SAMPLES: u16 = (super::SAMPLE_RATE as u16) / 60;
-const SAMPLE_RATIO: f32 = APU_SAMPLE_RATE / (super::SAMPLE_RATE as f32);
+use super::filter::FilterChain;
+use super::ring_buffer::RingBuffer;
+use super::wav::{SampleFormat, WavRecorder};
 
 pub struct AudioDevice {
-    pub buffer: Arc<Mutex<Vec<f32>>>,
-    filter_90: Filter,
-    filter_440: Filter,
-    filter_14000: Filter
+    pub buffer: Arc<Mutex<RingBuffer>>,
+    buffering: bool,
+    filters: Vec<FilterChain>,
+    recorder: Option<WavRecorder>,
+    sample_rate: i32
 }
 
 impl AudioDevice {
-    pub fn new(sdl_context: &sdl2::Sdl, buffer: Arc<Mutex<Vec<f32>>>) -> sdl2::audio::AudioDevice<AudioDevice> {
+    pub fn new(sdl_context: &sdl2::Sdl, buffer: Arc<Mutex<RingBuffer>>) -> sdl2::audio::AudioDevice<AudioDevice> {
         let audio_subsystem = sdl_context.audio().expect("Error loading audio subsystem");
+        let (sample_rate, channels) = {
+            let lock = buffer.lock().expect("Error retrieving buffer");
+            (lock.sample_rate, lock.channels)
+        };
+
         let desired_spec = AudioSpecDesired {
-            freq: Some(super::SAMPLE_RATE),
-            channels: Some(1),  // mono
-            samples: Some(SAMPLES)
+            freq: Some(sample_rate),
+            channels: Some(channels as u8),
+            samples: Some((sample_rate / 60) as u16)
         };
 
         audio_subsystem.open_playback(None, &desired_spec, |_spec| {
             AudioDevice {
                 buffer,
-                filter_90: Filter::new(90.0, Coefficient::High),
-                filter_440: Filter::new(440.0, Coefficient::High),
-                filter_14000: Filter::new(14_000.0, Coefficient::Low)
+                buffering: true,
+                filters: (0..channels).map(|_| FilterChain::new(sample_rate as f32)).collect(),
+                recorder: None,
+                sample_rate
             }
         }).expect("Error opening device")
     }
+
+    /// Records exactly what's played, after it's been filtered
+    pub fn start_recording(&mut self, file_path: &str, format: SampleFormat) -> io::Result<()> {
+        self.recorder = Some(WavRecorder::new(file_path, self.sample_rate as u32, self.filters.len() as u16, format)?);
+        Ok(())
+    }
+
+    /// Dropping the recorder finishes the file
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
 }
 
 impl AudioCallback for AudioDevice {
     type Channel = f32;
 
+    /// When the buffer runs dry, silence is played until it's half full again rather than playing each sample as it trickles in
     fn callback(&mut self, out: &mut [Self::Channel]) {
-        let mut lock = self.buffer.lock().expect("Error retrieving buffer");
-        if lock.len() > 0 {
-            for (i, x) in out.iter_mut().enumerate() {
-                let sample_index = ((i as f32) * SAMPLE_RATIO) as usize;
-                if sample_index < lock.len() {
-                    let sample = lock[sample_index];
-                    let filtered_90 = self.filter_90.high_pass(sample);
-                    let filtered_440 = self.filter_440.high_pass(filtered_90);
-                    let filtered_14_000 = self.filter_14000.low_pass(filtered_440);
-
-                    *x = filtered_14_000;
-                }
-            }
+        let mut buffer = self.buffer.lock().expect("Error retrieving buffer");
+        if self.buffering && buffer.len() < buffer.capacity() / 2 {
+            out.iter_mut().for_each(|x| *x = 0.0);
+            return;
+        }
 
-            let target_index = (SAMPLES as f32 * SAMPLE_RATIO) as usize;
-            if lock.len() > target_index {
-                *lock = lock.split_off(target_index);
+        self.buffering = false;
+        for frame in out.chunks_mut(self.filters.len()) {
+            for (x, filter) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                *x = match buffer.pop() {
+                    Some(sample) => filter.filter(sample),
+                    None => {
+                        self.buffering = true;
+                        0.0
+                    }
+                };
             }
         }
+
+        drop(buffer);
+        if let Some(ref r) = self.recorder {
+            r.write(out);
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,14 @@
 use serde::{Serialize, Deserialize};
+use crate::region::Region;
 
 const RATE_TABLE: [u16; 16] = [
     428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54
 ];
 
+const PAL_RATE_TABLE: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50
+];
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct DeltaModulationChannel {
     pub remaining_bytes: u8,
@@ -23,12 +28,17 @@ impl DeltaModulationChannel {
         0
     }
 
-    pub fn set_rate(&mut self, data: u8) {
+    /// Resetting clears all but the lowest bit of the output level
+    pub fn reset(&mut self) {
+        self.output_level &= 1;
+    }
+
+    pub fn set_rate(&mut self, data: u8, region: Region) {
         self.irq_enabled = (data & 0b10000000) > 0;
         self.loop_flag   = (data & 0b01000000) > 0;
 
         let rate_index = data & 0b00001111;
-        self.rate = RATE_TABLE[rate_index as usize];
+        self.rate = if region.has_pal_apu() { PAL_RATE_TABLE[rate_index as usize] } else { RATE_TABLE[rate_index as usize] };
     }
 
     pub fn set_direct_load(&mut self, data: u8) {
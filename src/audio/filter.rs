@@ -7,10 +7,10 @@ pub struct Filter {
 }
 
 impl Filter {
-    pub fn new(cutoff_frequency: f32, coefficient: Coefficient) -> Self {
+    pub fn new(cutoff_frequency: f32, coefficient: Coefficient, sample_rate: f32) -> Self {
         let gamma = match coefficient {
-            Coefficient::High => high_pass_coefficient(cutoff_frequency),
-            Coefficient::Low => low_pass_coefficient(cutoff_frequency)
+            Coefficient::High => high_pass_coefficient(cutoff_frequency, sample_rate),
+            Coefficient::Low => low_pass_coefficient(cutoff_frequency, sample_rate)
         };
 
         Filter {
@@ -43,15 +43,38 @@ pub enum Coefficient {
     Low
 }
 
-fn high_pass_coefficient(cutoff_frequency: f32) -> f32 {
-    1.0 / (calculate_frequency(cutoff_frequency) + 1.0)
+/// The NES filters its output with two high pass filters and a low pass filter, each channel needs its own
+pub struct FilterChain {
+    filter_90: Filter,
+    filter_440: Filter,
+    filter_14000: Filter
 }
 
-fn low_pass_coefficient(cutoff_frequency: f32) -> f32 {
-    let frequency = calculate_frequency(cutoff_frequency);
+impl FilterChain {
+    pub fn new(sample_rate: f32) -> Self {
+        FilterChain {
+            filter_90: Filter::new(90.0, Coefficient::High, sample_rate),
+            filter_440: Filter::new(440.0, Coefficient::High, sample_rate),
+            filter_14000: Filter::new(14_000.0, Coefficient::Low, sample_rate)
+        }
+    }
+
+    pub fn filter(&mut self, sample: f32) -> f32 {
+        let filtered_90 = self.filter_90.high_pass(sample);
+        let filtered_440 = self.filter_440.high_pass(filtered_90);
+        self.filter_14000.low_pass(filtered_440)
+    }
+}
+
+fn high_pass_coefficient(cutoff_frequency: f32, sample_rate: f32) -> f32 {
+    1.0 / (calculate_frequency(cutoff_frequency, sample_rate) + 1.0)
+}
+
+fn low_pass_coefficient(cutoff_frequency: f32, sample_rate: f32) -> f32 {
+    let frequency = calculate_frequency(cutoff_frequency, sample_rate);
     frequency / (frequency + 1.0)
 }
 
-fn calculate_frequency(cutoff_frequency: f32) -> f32 {
-    2.0 * PI * cutoff_frequency / super::SAMPLE_RATE as f32
+fn calculate_frequency(cutoff_frequency: f32, sample_rate: f32) -> f32 {
+    2.0 * PI * cutoff_frequency / sample_rate
 }
\ No newline at end of file
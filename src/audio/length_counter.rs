@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+const LENGTH_COUNTER_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12,
+    16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30
+];
+
+/// Silences a channel once it runs out, clocked by the frame counter's half frames.
+/// Writes to it only land after the frame counter has been clocked for that cycle, see apply_writes.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LengthCounter {
+    pub counter: u8,
+    enabled: bool,
+    halt: bool,
+    new_halt: bool,
+    previous_counter: u8,
+    reload_value: u8
+}
+
+impl LengthCounter {
+    pub fn clock(&mut self) {
+        if self.counter > 0 && !self.halt {
+            self.counter -= 1;
+        }
+    }
+
+    /// Takes the top 5 bits of the register, which index the length table
+    pub fn load(&mut self, data: u8) {
+        if self.enabled {
+            self.reload_value = LENGTH_COUNTER_TABLE[(data >> 3) as usize];
+            self.previous_counter = self.counter;
+        }
+    }
+
+    pub fn set_halt(&mut self, halt: bool) {
+        self.new_halt = halt;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    /// Called every CPU cycle after the frame counter.
+    /// A reload is ignored if the counter was clocked on the same cycle while it was above 0,
+    /// and a change to halt doesn't affect a clock on the same cycle.
+    pub fn apply_writes(&mut self) {
+        if self.reload_value != 0 {
+            if self.counter == self.previous_counter {
+                self.counter = self.reload_value;
+            }
+
+            self.reload_value = 0;
+        }
+
+        self.halt = self.new_halt;
+    }
+}
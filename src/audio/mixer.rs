@@ -0,0 +1,134 @@
+/// The channels that can be muted, soloed or turned down, expansion is whatever sound chip the cartridge has
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    Dmc,
+    Expansion
+}
+
+impl Channel {
+    pub fn from(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pulse1" => Some(Channel::Pulse1),
+            "pulse2" => Some(Channel::Pulse2),
+            "triangle" => Some(Channel::Triangle),
+            "noise" => Some(Channel::Noise),
+            "dmc" => Some(Channel::Dmc),
+            "expansion" => Some(Channel::Expansion),
+            _ => None
+        }
+    }
+}
+
+/// In the order of their keys, 1 to 6
+pub const CHANNELS: [Channel; 6] = [Channel::Pulse1, Channel::Pulse2, Channel::Triangle, Channel::Noise, Channel::Dmc, Channel::Expansion];
+
+/// The volumes Ctrl and a channel's key step through
+const VOLUME_STEPS: [f32; 4] = [1.0, 0.75, 0.5, 0.25];
+
+/// Where each channel sits from -1 for left to 1 for right, in the same order as CHANNELS
+pub const PAN_PRESETS: [(&str, [f32; 6]); 4] = [
+    ("Centered", [0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("Pulse 1 left, pulse 2 right", [-1.0, 1.0, 0.0, 0.0, 0.0, 0.0]),
+    ("Wide", [-0.6, 0.6, 0.0, -0.3, 0.3, 0.0]),
+    ("Expansion right", [-0.5, -0.5, -0.5, -0.5, -0.5, 1.0])
+];
+
+/// Scales each channel before it's mixed, so one can be listened to on its own while tracking down a sound bug.
+/// In stereo each channel can also be panned, both sides are mixed separately.
+#[derive(Debug)]
+pub struct Mixer {
+    pub stereo: bool,
+    muted: [bool; 6],
+    pan_preset: usize,
+    pans: [f32; 6],
+    solo: Option<Channel>,
+    volumes: [f32; 6]
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Mixer {
+            stereo: false,
+            muted: [false; 6],
+            pan_preset: 0,
+            pans: PAN_PRESETS[0].1,
+            solo: None,
+            volumes: [1.0; 6]
+        }
+    }
+}
+
+impl Mixer {
+    /// How much of the channel makes it into the mix, nothing if it's muted or another channel is soloed
+    pub fn get_gain(&self, channel: Channel) -> f32 {
+        match self.solo {
+            Some(solo) if solo != channel => 0.0,
+            _ if self.muted[channel as usize] => 0.0,
+            _ => self.volumes[channel as usize]
+        }
+    }
+
+    /// A centered channel is at full volume on both sides, panning it turns down the other side
+    pub fn get_side_gain(&self, channel: Channel, right: bool) -> f32 {
+        let pan = if right { self.pans[channel as usize] } else { -self.pans[channel as usize] };
+        self.get_gain(channel) * (1.0 + pan).min(1.0)
+    }
+
+    pub fn get_pan(&self, channel: Channel) -> f32 {
+        self.pans[channel as usize]
+    }
+
+    /// Pans go from -1 for left to 1 for right, anything outside of that is clamped
+    pub fn set_pan(&mut self, channel: Channel, pan: f32) {
+        self.pans[channel as usize] = pan.clamp(-1.0, 1.0);
+    }
+
+    /// Moves on to the next preset and gives back its name
+    pub fn next_pan_preset(&mut self) -> &'static str {
+        self.pan_preset = (self.pan_preset + 1) % PAN_PRESETS.len();
+        let (name, pans) = PAN_PRESETS[self.pan_preset];
+        self.pans = pans;
+        name
+    }
+
+    pub fn get_volume(&self, channel: Channel) -> f32 {
+        self.volumes[channel as usize]
+    }
+
+    /// Volumes go from 0 to 1, anything outside of that is clamped
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.volumes[channel as usize] = volume.clamp(0.0, 1.0);
+    }
+
+    /// Moves on to the next step down in volume, going back to full after the quietest
+    pub fn step_volume(&mut self, channel: Channel) {
+        let volume = self.volumes[channel as usize];
+        let next = VOLUME_STEPS.iter().position(|v| *v < volume).map(|i| VOLUME_STEPS[i]).unwrap_or(VOLUME_STEPS[0]);
+        self.volumes[channel as usize] = next;
+    }
+
+    pub fn is_muted(&self, channel: Channel) -> bool {
+        self.muted[channel as usize]
+    }
+
+    pub fn set_muted(&mut self, channel: Channel, muted: bool) {
+        self.muted[channel as usize] = muted;
+    }
+
+    pub fn toggle_mute(&mut self, channel: Channel) {
+        self.muted[channel as usize] = !self.muted[channel as usize];
+    }
+
+    pub fn get_solo(&self) -> Option<Channel> {
+        self.solo
+    }
+
+    /// Soloing the channel that's already soloed brings all of the channels back
+    pub fn toggle_solo(&mut self, channel: Channel) {
+        self.solo = if self.solo == Some(channel) { None } else { Some(channel) };
+    }
+}
@@ -2,35 +2,73 @@ pub mod device;
 pub mod dmc;
 pub mod envelope;
 pub mod filter;
+pub mod length_counter;
+pub mod mixer;
 pub mod noise;
 pub mod pulse;
+pub mod rate_control;
+pub mod resampler;
+pub mod ring_buffer;
 pub mod sweep;
 pub mod timer;
 pub mod triangle;
+pub mod wav;
 
 use serde::{Serialize, Deserialize};
 use crate::addresses::apu::*;
+use crate::region::Region;
 
-const LENGTH_COUNTER_TABLE: [u8; 32] = [
-    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12,
-    16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30
+pub const DEFAULT_SAMPLE_RATE: i32 = 44_100;
+pub const MIN_SAMPLE_RATE: i32 = 22_050;
+pub const MAX_SAMPLE_RATE: i32 = 96_000;
+
+/// The CPU cycles each step of the frame counter lands on, the last step of each mode starts the sequence again.
+/// In 4 step mode the last 3 steps set the interrupt flag, in 5 step mode step 3 does nothing.
+const FRAME_COUNTER_STEPS: [[u32; 6]; 2] = [
+    [7457, 14913, 22371, 29828, 29829, 29830],
+    [7457, 14913, 22371, 29829, 37281, 37282]
+];
+const PAL_FRAME_COUNTER_STEPS: [[u32; 6]; 2] = [
+    [8313, 16627, 24939, 33252, 33253, 33254],
+    [8313, 16627, 24939, 33253, 41565, 41566]
+];
+const FRAME_COUNTER_CLOCKS: [FrameClock; 6] = [
+    FrameClock::Quarter,
+    FrameClock::Half,
+    FrameClock::Quarter,
+    FrameClock::None,
+    FrameClock::Half,
+    FrameClock::None
 ];
 
-const SAMPLE_RATE: i32 = 44_100;
-const FRAME_COUNTER_STEPS: [usize; 5] = [3728, 7456, 11185, 14914, 18640];
+#[derive(Clone, Copy, PartialEq)]
+enum FrameClock {
+    None,
+    Quarter,
+    Half
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Apu2A03 {
-    pub buffer: Vec<f32>,
-    pub trigger_interrupt: bool,
+    pub buffer: Vec<[f32; 2]>,
+    pub region: Region,
     clock_counter: u32,
     dmc: dmc::DeltaModulationChannel,
-    frame_clock_counter: usize, // Maintains musical timing of the apu
+    frame_block_clock: u8,
+    frame_clock_counter: u32, // Maintains musical timing of the apu, in CPU cycles
     frame_interrupt: bool,
+    frame_step: usize,
+    frame_write: Option<u8>,
+    frame_write_delay: u8,
     interrupt_inhibit: bool,
+    #[serde(skip)]
+    pub mixer: mixer::Mixer,
+    #[serde(skip)]
+    pub multitrack: Option<wav::Multitrack>,
     noise: noise::Noise,
     pulse_1: pulse::Pulse,
     pulse_2: pulse::Pulse,
+    odd_cycle: bool,
     square_table: Vec<f32>,
     step_mode: u8,
     tnd_table: Vec<f32>,
@@ -40,39 +78,91 @@ pub struct Apu2A03 {
 impl Apu2A03 {
     pub fn initialize() -> Self {
         Apu2A03 {
-            buffer: Vec::<f32>::new(),
-            trigger_interrupt: false,
+            buffer: Vec::<[f32; 2]>::new(),
+            region: Region::Ntsc,
             clock_counter: 0,
             dmc: Default::default(),
+            frame_block_clock: 0,
             frame_clock_counter: 0,
             frame_interrupt: false,
+            frame_step: 0,
+            frame_write: None,
+            frame_write_delay: 0,
             interrupt_inhibit: false,
+            mixer: Default::default(),
+            multitrack: None,
             noise: noise::Noise::new(),
             pulse_1: pulse::Pulse::new(true),
             pulse_2: pulse::Pulse::new(false),
+            odd_cycle: false,
             square_table: (0..31).map(|x| 95.52/((8128.0 / x as f32) + 100.0)).collect(),
-            step_mode: 0,
+            step_mode: 4,
             triangle: Default::default(),
             tnd_table: (0..203).map(|x| 163.67/((24329.0 / x as f32) + 100.0)).collect()
         }
     }
 
+    /// Silences every channel and restarts the frame counter in the mode it was in, as if $4017 was written again.
+    /// The interrupt inhibit flag isn't kept, and power on is the same as a reset in 4 step mode.
     pub fn reset(&mut self) {
+        self.write_status(0);
+        self.triangle.reset();
+        self.dmc.reset();
+        self.frame_interrupt = false;
+        self.interrupt_inhibit = false;
+        self.frame_write = Some(if self.step_mode == 5 { 0b10000000 } else { 0 });
+        self.frame_write_delay = 3;
+    }
 
+    /// Clocks the channels, called every other CPU cycle
+    pub fn clock(&mut self, expansion_sample: f32) {
+        let sample = self.mix_samples(expansion_sample);
+        self.buffer.push(sample);
     }
 
-    pub fn clock(&mut self) {    
-        let sample = self.mix_samples();
+    /// Clocks the frame counter, called every CPU cycle after the CPU
+    pub fn clock_frame_counter(&mut self) {
+        self.odd_cycle = !self.odd_cycle;
+        self.frame_clock_counter += 1;
 
-        if FRAME_COUNTER_STEPS.contains(&self.frame_clock_counter) {
-            self.clock_frame_counter();
+        let steps = self.get_frame_counter_steps();
+        if self.frame_clock_counter == steps[self.frame_step] {
+            if self.step_mode == 4 && self.frame_step >= 3 && !self.interrupt_inhibit {
+                self.frame_interrupt = true;
+            }
+
+            let frame_clock = FRAME_COUNTER_CLOCKS[self.frame_step];
+            if frame_clock != FrameClock::None && self.frame_block_clock == 0 {
+                self.clock_frame(frame_clock);
+            }
+
+            self.frame_step += 1;
+            if self.frame_step == steps.len() {
+                self.frame_step = 0;
+                self.frame_clock_counter = 0;
+            }
         }
-        
-        self.frame_clock_counter += 1;
-        if self.is_max_step_counter() {
-            self.frame_clock_counter = 0;
+
+        if let Some(data) = self.frame_write {
+            self.frame_write_delay -= 1;
+            if self.frame_write_delay == 0 {
+                self.apply_frame_counter_write(data);
+            }
         }
-        self.buffer.push(sample);
+
+        if self.frame_block_clock > 0 {
+            self.frame_block_clock -= 1;
+        }
+
+        self.pulse_1.length_counter.apply_writes();
+        self.pulse_2.length_counter.apply_writes();
+        self.triangle.length_counter.apply_writes();
+        self.noise.length_counter.apply_writes();
+    }
+
+    /// The frame counter's interrupt stays asserted until $4015 is read or it's inhibited
+    pub fn irq_active(&self) -> bool {
+        self.frame_interrupt || self.dmc.interrupt
     }
 
     pub fn read(&mut self, address: u16) -> u8 {
@@ -96,9 +186,9 @@ impl Apu2A03 {
             APU_TRIANGLE_TIMER_LOW => self.triangle.set_timer_low(data),
             APU_TRIANGLE_TIMER_HIGH => self.triangle.set_timer_high(data),
             APU_NOISE_VOLUME => self.noise.set_volume(data),
-            APU_NOISE_PERIOD => self.noise.set_period(data),
+            APU_NOISE_PERIOD => self.noise.set_period(data, self.region),
             APU_NOISE_COUNTER_LOAD => self.noise.set_length_counter(data),
-            APU_DMC_FLAGS_RATE => self.dmc.set_rate(data),
+            APU_DMC_FLAGS_RATE => self.dmc.set_rate(data, self.region),
             APU_DMC_DIRECT_LOAD => self.dmc.set_direct_load(data),
             APU_DMC_SAMPLE_ADDRESS => self.dmc.set_sample_address(data),
             APU_DMC_SAMPLE_LENGTH => self.dmc.set_sample_length(data),
@@ -108,33 +198,21 @@ impl Apu2A03 {
         }
     }
 
-    fn clock_frame_counter(&mut self) {
-        match self.step_mode {
-            4 => {
-                self.clock_4_step_frame_counter();
-            },
-            5 => {
-                self.clock_5_step_frame_counter();
-            },
-            _ => ()
-        }
-    }
-
     fn read_status(&mut self) -> u8 {
         let mut status = 0;
-        if self.pulse_1.length_counter != 0 {
+        if self.pulse_1.length_counter.counter != 0 {
             status |= 1 << 0;
         }
 
-        if self.pulse_2.length_counter != 0 {
+        if self.pulse_2.length_counter.counter != 0 {
             status |= 1 << 1;
         }
 
-        if self.triangle.length_counter != 0 {
+        if self.triangle.length_counter.counter != 0 {
             status |= 1 << 2;
         }
 
-        if self.noise.length_counter != 0 {
+        if self.noise.length_counter.counter != 0 {
             status |= 1 << 3;
         }
 
@@ -156,102 +234,83 @@ impl Apu2A03 {
     }
 
     fn write_status(&mut self, data: u8) {
-        self.pulse_1.set_enabled((data & 0b01) > 0);
-        self.pulse_2.set_enabled((data & 0b10) > 0);
-        self.triangle.set_enabled((data & 0b100) > 0);
-        self.noise.set_enabled((data & 0b1000) > 0);
+        self.pulse_1.length_counter.set_enabled((data & 0b01) > 0);
+        self.pulse_2.length_counter.set_enabled((data & 0b10) > 0);
+        self.triangle.length_counter.set_enabled((data & 0b100) > 0);
+        self.noise.length_counter.set_enabled((data & 0b1000) > 0);
+        self.dmc.interrupt = false;
     }
 
+    /// Setting interrupt inhibit clears the flag straight away, but the new mode only takes effect 3 or 4 CPU cycles later,
+    /// depending on whether it was written on an APU cycle or between two
     fn write_frame_counter(&mut self, data: u8) {
-        self.step_mode = match data >> 7 {
-            0 => {
-                4
-            },
-            1 => {
-                5
-            },
-            _ => panic!("Invalid step mode")
-        };
-
-        self.interrupt_inhibit = (data >> 6) & 0b1 > 0;
-        if self.step_mode == 5 {
-            self.clock_envelopes();
-            self.clock_sweeps();
-            self.triangle.clock_linear_counter();
-            self.clock_length_counters();
+        self.interrupt_inhibit = (data & 0b01000000) > 0;
+        if self.interrupt_inhibit {
+            self.frame_interrupt = false;
+        }
+
+        // The frame counter is clocked after the CPU, so the cycle it was written on counts down as well
+        self.frame_write = Some(data);
+        self.frame_write_delay = if self.odd_cycle { 5 } else { 4 };
+    }
+
+    /// Restarts the sequence, 5 step mode also clocks everything straight away.
+    /// A step landing in the next couple of cycles doesn't clock anything so it's not clocked twice.
+    fn apply_frame_counter_write(&mut self, data: u8) {
+        self.frame_write = None;
+        self.step_mode = if (data & 0b10000000) > 0 { 5 } else { 4 };
+        self.frame_clock_counter = 0;
+        self.frame_step = 0;
+        if self.step_mode == 5 && self.frame_block_clock == 0 {
+            self.clock_frame(FrameClock::Half);
+            self.frame_block_clock = 2;
         }
     }
 
-    fn mix_samples(&mut self) -> f32 {
-        let pulse_1 = self.pulse_1.clock();
-        let pulse_2 = self.pulse_2.clock();
-        let triangle = self.triangle.clock();
-        let noise = self.noise.clock();
-        let dmc = self.dmc.clock();
+    /// Gives back the left and right samples, in mono they're both the same
+    fn mix_samples(&mut self, expansion_sample: f32) -> [f32; 2] {
+        let outputs = [
+            self.pulse_1.clock() as f32,
+            self.pulse_2.clock() as f32,
+            self.triangle.clock() as f32,
+            self.noise.clock() as f32,
+            self.dmc.clock() as f32,
+            expansion_sample
+        ];
+
+        if let Some(ref mut m) = self.multitrack {
+            m.add_samples(&outputs);
+        }
 
-        let pulse_index = pulse_1 + pulse_2;
-        let pulse_out = self.square_table[pulse_index as usize];
-        let tnd_index = (3 * triangle) + (2 * noise) + dmc;
-        let tnd_out = self.tnd_table[tnd_index as usize];
+        if !self.mixer.stereo {
+            let mono = self.mix_side(&outputs, |channel| self.mixer.get_gain(channel));
+            return [mono, mono];
+        }
 
-        pulse_out + tnd_out
+        [
+            self.mix_side(&outputs, |channel| self.mixer.get_side_gain(channel, false)),
+            self.mix_side(&outputs, |channel| self.mixer.get_side_gain(channel, true))
+        ]
     }
 
-    fn clock_4_step_frame_counter(&mut self) {
-        match self.frame_clock_counter {
-            3728 => {
-                self.clock_envelopes();
-                self.triangle.clock_linear_counter();
-            },
-            7456 => {
-                self.clock_envelopes();
-                self.clock_sweeps();
-                self.clock_length_counters();
-                self.triangle.clock_linear_counter();
-            },
-            11185 => {
-                self.clock_envelopes();
-                self.triangle.clock_linear_counter();
-            },
-            14914 => {
-                self.clock_envelopes();
-                self.triangle.clock_linear_counter();
-                self.clock_sweeps();
-                self.clock_length_counters();
-
-                if !self.interrupt_inhibit {
-                    self.trigger_interrupt = true;
-                } 
-            },
-            _ => ()
-        }
+    /// Runs the channels through the same non linear tables as the console, with each one scaled by its gain.
+    /// Turning a channel down gives an index between two entries, so the tables are interpolated.
+    fn mix_side(&self, outputs: &[f32; 6], gain: impl Fn(mixer::Channel) -> f32) -> f32 {
+        let output = |channel: mixer::Channel| outputs[channel as usize] * gain(channel);
+        let pulse_index = output(mixer::Channel::Pulse1) + output(mixer::Channel::Pulse2);
+        let pulse_out = interpolate(&self.square_table, pulse_index);
+        let tnd_index = (3.0 * output(mixer::Channel::Triangle)) + (2.0 * output(mixer::Channel::Noise)) + output(mixer::Channel::Dmc);
+        let tnd_out = interpolate(&self.tnd_table, tnd_index);
+
+        pulse_out + tnd_out + output(mixer::Channel::Expansion)
     }
 
-    fn clock_5_step_frame_counter(&mut self) {
-        match self.frame_clock_counter {
-            3728 => {
-                self.clock_envelopes();
-                self.triangle.clock_linear_counter();
-            },
-            7456 => {
-                self.clock_envelopes();
-                self.clock_sweeps();
-                self.triangle.clock_linear_counter();
-                self.clock_length_counters();
-            },
-            11185 => {
-                self.clock_envelopes();
-                self.triangle.clock_linear_counter();
-            },
-            14914 => {
-            },
-            18640 => {
-                self.clock_envelopes();
-                self.clock_sweeps();
-                self.triangle.clock_linear_counter();
-                self.clock_length_counters();
-            },
-            _ => ()
+    fn clock_frame(&mut self, frame_clock: FrameClock) {
+        self.clock_envelopes();
+        self.triangle.clock_linear_counter();
+        if frame_clock == FrameClock::Half {
+            self.clock_sweeps();
+            self.clock_length_counters();
         }
     }
 
@@ -267,17 +326,21 @@ impl Apu2A03 {
     }
 
     fn clock_length_counters(&mut self) {
-        self.pulse_1.clock_length_counter();
-        self.pulse_2.clock_length_counter();
-        self.triangle.clock_length_counter();
-        self.noise.clock_length_counter();
+        self.pulse_1.length_counter.clock();
+        self.pulse_2.length_counter.clock();
+        self.triangle.length_counter.clock();
+        self.noise.length_counter.clock();
     }
 
-    fn is_max_step_counter(&self) -> bool {
-        if self.step_mode == 4 {
-            self.frame_clock_counter == 14915
-        } else {
-            self.frame_clock_counter == 18641
-        }
+    fn get_frame_counter_steps(&self) -> &'static [u32; 6] {
+        let mode = if self.step_mode == 4 { 0 } else { 1 };
+        if self.region.has_pal_apu() { &PAL_FRAME_COUNTER_STEPS[mode] } else { &FRAME_COUNTER_STEPS[mode] }
     }
-}
\ No newline at end of file
+}
+
+fn interpolate(table: &[f32], index: f32) -> f32 {
+    let lower = (index as usize).min(table.len() - 1);
+    let upper = (lower + 1).min(table.len() - 1);
+    let fraction = index - (lower as f32);
+    table[lower] + ((table[upper] - table[lower]) * fraction)
+}
@@ -1,16 +1,21 @@
 use serde::{Serialize, Deserialize};
 use super::envelope;
+use super::length_counter::LengthCounter;
+use crate::region::Region;
 
 const PERIOD_TABLE: [u16; 16] = [
     4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068
 ];
 
+const PAL_PERIOD_TABLE: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778
+];
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Noise {
     pub envelope: envelope::Envelope,
-    pub length_counter: u8,
+    pub length_counter: LengthCounter,
     constant_volume: bool,
-    enabled: bool,
     feedback_shift: u16,
     mode: bool,
     period: u16,
@@ -21,9 +26,8 @@ impl Noise {
     pub fn new() -> Self {
         Noise {
             envelope: Default::default(),
-            length_counter: 0,
+            length_counter: Default::default(),
             constant_volume: false,
-            enabled: false,
             feedback_shift: 1, // On power-up, the shift register is loaded with the value 1.
             mode: false,
             period: 0,
@@ -47,15 +51,10 @@ impl Noise {
         }
     }
 
-    pub fn clock_length_counter(&mut self) {
-        if self.length_counter > 0 && !self.envelope.loop_flag {
-            self.length_counter -= 1;
-        }
-    }
-
     /// 0x400C
     pub fn set_volume(&mut self, data: u8) {
         self.envelope.loop_flag = (data & 0b100000) > 0;
+        self.length_counter.set_halt(self.envelope.loop_flag);
         self.constant_volume = (data & 0b10000) > 0;
 
         let volume = data & 0b1111;
@@ -63,24 +62,18 @@ impl Noise {
     }
 
     /// 0x400E
-    pub fn set_period(&mut self, data: u8) {
+    pub fn set_period(&mut self, data: u8, region: Region) {
         self.mode = data & 0b10000000 > 0;
         let period_index = (data & 0b1111) as usize;
-        self.period = PERIOD_TABLE[period_index];
+        self.period = if region.has_pal_apu() { PAL_PERIOD_TABLE[period_index] } else { PERIOD_TABLE[period_index] };
     }
 
+    /// 0x400F
     pub fn set_length_counter(&mut self, data: u8) {
-        self.length_counter = (data & 0b11111000) >> 3;
+        self.length_counter.load(data);
         self.envelope.start = true;
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
-        if !enabled {
-            self.length_counter = 0;
-        }
-    }
-
     fn clock_shift_register(&mut self) {
         let first_bit = self.feedback_shift & 0b1;
         let bit_shift = if self.mode {
@@ -98,6 +91,6 @@ impl Noise {
     }
 
     fn is_silenced(&mut self) -> bool {
-         (self.feedback_shift & 0b1) == 1 || self.length_counter == 0
+         (self.feedback_shift & 0b1) == 1 || self.length_counter.counter == 0
     }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
 use super::envelope;
+use super::length_counter::LengthCounter;
 use super::sweep;
 use super::timer;
 
@@ -13,11 +14,10 @@ const DUTY_CYCLE_WAVEFORMS: [u8; 4] = [
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Pulse {
     pub envelope: envelope::Envelope,
-    pub length_counter: u8,
+    pub length_counter: LengthCounter,
     constant_volume: bool,
     duty_cycle: u8,
     duty_shifter: u8,
-    enabled: bool,
     is_first: bool,
     sweep: sweep::Sweep,
     target_period: u16,
@@ -28,11 +28,10 @@ impl Pulse {
     pub fn new(is_first: bool) -> Self {
         Pulse {
             envelope: Default::default(),
-            length_counter: 0,
+            length_counter: Default::default(),
             constant_volume: false,
             duty_cycle: DUTY_CYCLE_WAVEFORMS[0],
             duty_shifter: 0,
-            enabled: false,
             is_first,
             sweep: Default::default(),
             target_period: 0,
@@ -73,17 +72,12 @@ impl Pulse {
         }
     }
 
-    pub fn clock_length_counter(&mut self) {
-        if self.length_counter > 0 && !self.envelope.loop_flag {
-            self.length_counter -= 1;
-        }
-    }
-
     pub fn set_duty_cycle(&mut self, data: u8) {
         let duty = (data & 0b11000000) >> 6;
         self.duty_cycle = DUTY_CYCLE_WAVEFORMS[duty as usize];
 
         self.envelope.loop_flag = (data & 0b100000) > 0;
+        self.length_counter.set_halt(self.envelope.loop_flag);
         self.constant_volume = (data & 0b10000) > 0;
 
         let volume = data & 0b1111;
@@ -103,24 +97,13 @@ impl Pulse {
     }
 
     pub fn set_timer_high(&mut self, data: u8) {
-        if self.enabled {
-            let index = (data & 0b11111000) >> 3;
-            self.length_counter = super::LENGTH_COUNTER_TABLE[index as usize];
-        }
-
+        self.length_counter.load(data);
         self.timer.set_high(data);
         self.envelope.start = true;
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
-        if !enabled {
-            self.length_counter = 0;
-        }
-    }
-
     fn is_silenced(&self, sample: u16) -> bool {
-        sample == 0 || self.length_counter == 0 || self.is_muting_channel()
+        sample == 0 || self.length_counter.counter == 0 || self.is_muting_channel()
     }
 
     fn is_muting_channel(&self) -> bool {
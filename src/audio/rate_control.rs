@@ -0,0 +1,52 @@
+use super::resampler::Resampler;
+use super::ring_buffer::RingBuffer;
+
+/// How far the output rate can be pushed either way to keep the buffer half full, small enough that the pitch change can't be heard
+const MAX_RATE_ADJUSTMENT: f64 = 0.005;
+
+/// Video runs at exactly the console's frame rate and the sound card runs at its own rate, and the two clocks never quite agree.
+/// Rather than letting the buffer between them slowly empty or fill up, the APU's samples are resampled at a rate
+/// that's nudged up when the buffer is running low and down when it's getting full.
+#[derive(Debug)]
+pub struct RateControl {
+    input_rate: f64,
+    output_rate: f64,
+    resamplers: Vec<Resampler>
+}
+
+impl RateControl {
+    /// Each channel gets its own resampler, they all run at the same rate so they always have the same number of samples ready
+    pub fn new(input_rate: f64, output_rate: f64, channels: usize) -> Self {
+        RateControl {
+            input_rate,
+            output_rate,
+            resamplers: (0..channels).map(|_| Resampler::new(input_rate, output_rate)).collect()
+        }
+    }
+
+    /// Takes left and right samples from the APU, in mono only the left is used
+    pub fn push(&mut self, samples: &[[f32; 2]], buffer: &mut RingBuffer) {
+        let adjustment = 1.0 + MAX_RATE_ADJUSTMENT * (1.0 - 2.0 * buffer.get_fill());
+        for (channel, resampler) in self.resamplers.iter_mut().enumerate() {
+            resampler.set_rates(self.input_rate, self.output_rate * adjustment);
+            for sample in samples {
+                resampler.add_sample(sample[channel]);
+            }
+        }
+
+        let outputs: Vec<Vec<f32>> = self.resamplers.iter_mut().map(|resampler| {
+            let mut output = vec![];
+            resampler.read_samples(|sample| output.push(sample));
+            output
+        }).collect();
+
+        let mut frame = vec![0.0; outputs.len()];
+        for index in 0..outputs.iter().map(|o| o.len()).min().unwrap_or_default() {
+            for (channel, output) in outputs.iter().enumerate() {
+                frame[channel] = output[index];
+            }
+
+            buffer.push(&frame);
+        }
+    }
+}
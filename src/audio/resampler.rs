@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+
+/// How many output samples each step is spread across
+const KERNEL_WIDTH: usize = 16;
+/// How many positions between two output samples a step can start at
+const PHASES: usize = 64;
+/// Where the cutoff sits compared to the output's Nyquist frequency, leaving room for the filter to roll off before it
+const CUTOFF: f64 = 0.9;
+
+/// Band limited step synthesis, like blip_buf.
+/// The APU's output only changes every so often, so instead of filtering every one of its ~894,000 samples a second,
+/// each change is added to the output as a step that's been band limited to the output rate.
+/// The output is the running total of those steps, so anything the output rate can't represent never makes it in to alias.
+#[derive(Debug)]
+pub struct Resampler {
+    kernels: Vec<[f32; KERNEL_WIDTH]>,
+    deltas: Vec<f32>,
+    previous_sample: f32,
+    step: f64,
+    time: f64,
+    total: f32
+}
+
+impl Resampler {
+    pub fn new(input_rate: f64, output_rate: f64) -> Self {
+        Resampler {
+            kernels: create_kernels(),
+            deltas: vec![0.0; KERNEL_WIDTH],
+            previous_sample: 0.0,
+            step: output_rate / input_rate,
+            time: 0.0,
+            total: 0.0
+        }
+    }
+
+    /// Changes how many output samples each input sample is worth
+    pub fn set_rates(&mut self, input_rate: f64, output_rate: f64) {
+        self.step = output_rate / input_rate;
+    }
+
+    pub fn add_sample(&mut self, sample: f32) {
+        let delta = sample - self.previous_sample;
+        if delta != 0.0 {
+            self.previous_sample = sample;
+            self.add_delta(delta);
+        }
+
+        self.time += self.step;
+    }
+
+    /// Hands over every output sample that's complete, no more steps can land on them
+    pub fn read_samples(&mut self, mut output: impl FnMut(f32)) {
+        let complete = self.time as usize;
+        if self.deltas.len() < complete {
+            self.deltas.resize(complete, 0.0);
+        }
+
+        for delta in self.deltas.drain(..complete) {
+            self.total += delta;
+            output(self.total);
+        }
+
+        self.time -= complete as f64;
+    }
+
+    fn add_delta(&mut self, delta: f32) {
+        let whole = self.time as usize;
+        let phase = (((self.time - (whole as f64)) * (PHASES as f64)) as usize).min(PHASES - 1);
+        if self.deltas.len() < whole + KERNEL_WIDTH {
+            self.deltas.resize(whole + KERNEL_WIDTH, 0.0);
+        }
+
+        for (i, k) in self.kernels[phase].iter().enumerate() {
+            self.deltas[whole + i] += delta * k;
+        }
+    }
+}
+
+/// A windowed sinc for each phase, shifted by that fraction of a sample.
+/// Each one adds up to 1 so a step of 1 in the input moves the output by exactly 1.
+fn create_kernels() -> Vec<[f32; KERNEL_WIDTH]> {
+    (0..PHASES).map(|phase| {
+        let offset = (phase as f64) / (PHASES as f64);
+        let mut kernel = [0.0; KERNEL_WIDTH];
+        for (i, k) in kernel.iter_mut().enumerate() {
+            let x = (i as f64) - ((KERNEL_WIDTH / 2) as f64) - offset;
+            let sinc = if x == 0.0 { 1.0 } else { (PI * CUTOFF * x).sin() / (PI * CUTOFF * x) };
+            let window = 0.5 + 0.5 * (PI * x / ((KERNEL_WIDTH / 2) as f64)).cos();
+            *k = sinc * if x.abs() < (KERNEL_WIDTH / 2) as f64 { window } else { 0.0 };
+        }
+
+        let sum: f64 = kernel.iter().sum();
+        let mut normalized = [0.0; KERNEL_WIDTH];
+        for (n, k) in normalized.iter_mut().zip(kernel.iter()) {
+            *n = (k / sum) as f32;
+        }
+
+        normalized
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(resampler: &mut Resampler, samples: &[f32]) -> Vec<f32> {
+        let mut output = vec![];
+        for sample in samples {
+            resampler.add_sample(*sample);
+            resampler.read_samples(|s| output.push(s));
+        }
+
+        output
+    }
+
+    #[test]
+    fn kernels_add_up_to_one() {
+        for kernel in create_kernels() {
+            let sum: f32 = kernel.iter().sum();
+            assert!((sum - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn outputs_at_the_output_rate() {
+        let mut resampler = Resampler::new(48_000.0, 4_000.0);
+        let output = resample(&mut resampler, &[0.0; 48_000]);
+        assert!((3_999..=4_000).contains(&output.len()));
+        assert!(output.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn step_settles_at_its_level() {
+        let mut resampler = Resampler::new(8_000.0, 1_000.0);
+        let output = resample(&mut resampler, &[0.5; 800]);
+        assert!(output.iter().skip(KERNEL_WIDTH).all(|sample| (sample - 0.5).abs() < 1e-4));
+    }
+
+    #[test]
+    fn set_rates_changes_the_output_rate() {
+        let mut resampler = Resampler::new(1_000.0, 100.0);
+        resampler.set_rates(1_000.0, 200.0);
+        let output = resample(&mut resampler, &[0.0; 1_000]);
+        assert!((199..=200).contains(&output.len()));
+    }
+}
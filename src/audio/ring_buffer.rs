@@ -0,0 +1,114 @@
+/// A fixed size queue of samples shared between the emulator, which fills it, and the audio device, which empties it.
+/// Stereo samples are stored left then right.
+#[derive(Debug, Default)]
+pub struct RingBuffer {
+    pub channels: usize,
+    pub sample_rate: i32,
+    samples: Vec<f32>,
+    read_index: usize,
+    length: usize
+}
+
+impl RingBuffer {
+    /// Holds a tenth of a second of samples at the output rate, the rate control keeps it about half full
+    pub fn new(sample_rate: i32, channels: usize) -> Self {
+        RingBuffer {
+            channels,
+            sample_rate,
+            samples: vec![0.0; (sample_rate / 10) as usize * channels],
+            read_index: 0,
+            length: 0
+        }
+    }
+
+    /// Pushes a sample for each channel, or none of them if they don't all fit so left and right never get out of step.
+    /// The rate control keeps the buffer from filling up in practice.
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.length + samples.len() > self.samples.len() {
+            return;
+        }
+
+        for sample in samples {
+            let write_index = (self.read_index + self.length) % self.samples.len();
+            self.samples[write_index] = *sample;
+            self.length += 1;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<f32> {
+        if self.length == 0 {
+            return None;
+        }
+
+        let sample = self.samples[self.read_index];
+        self.read_index = (self.read_index + 1) % self.samples.len();
+        self.length -= 1;
+        Some(sample)
+    }
+
+    pub fn clear(&mut self) {
+        self.read_index = 0;
+        self.length = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// How full the buffer is, from 0 to 1
+    pub fn get_fill(&self) -> f64 {
+        if self.samples.is_empty() { 0.0 } else { (self.length as f64) / (self.samples.len() as f64) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_order() {
+        let mut buffer = RingBuffer::new(100, 1);
+        buffer.push(&[1.0, 2.0, 3.0]);
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.pop(), Some(1.0));
+        assert_eq!(buffer.pop(), Some(2.0));
+        assert_eq!(buffer.pop(), Some(3.0));
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut buffer = RingBuffer::new(100, 1);
+        buffer.push(&[0.0; 8]);
+        for _ in 0..8 {
+            buffer.pop();
+        }
+
+        buffer.push(&[1.0, 2.0, 3.0, 4.0]);
+        let samples: Vec<f32> = std::iter::from_fn(|| buffer.pop()).collect();
+        assert_eq!(samples, [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn drops_samples_that_do_not_fit() {
+        let mut buffer = RingBuffer::new(100, 2);
+        assert_eq!(buffer.capacity(), 20);
+        buffer.push(&[1.0; 16]);
+        buffer.push(&[2.0; 8]);
+        assert_eq!(buffer.len(), 16);
+        assert_eq!(buffer.get_fill(), 0.8);
+    }
+
+    #[test]
+    fn clear_empties_it() {
+        let mut buffer = RingBuffer::new(100, 1);
+        buffer.push(&[1.0; 5]);
+        buffer.clear();
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.pop(), None);
+    }
+}
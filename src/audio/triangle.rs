@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use super::length_counter::LengthCounter;
 use super::timer;
 
 const SEQUENCER_LENGTH: usize = 32;
@@ -9,10 +10,9 @@ const SEQUENCER: [u8; SEQUENCER_LENGTH] = [
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Triangle {
-    pub length_counter: u8,
+    pub length_counter: LengthCounter,
+    control_flag: bool,
     counter_reload: u8,
-    enabled: bool,
-    length_counter_halt: bool,
     linear_counter: u8,
     linear_counter_reload: bool,
     sequencer_counter: usize,
@@ -24,12 +24,6 @@ impl Triangle {
         self.get_sample();
         self.get_sample()
     }
-    
-    pub fn clock_length_counter(&mut self) {
-        if self.length_counter > 0 && !self.length_counter_halt {
-            self.length_counter -= 1;
-        }
-    }
 
     pub fn clock_linear_counter(&mut self) {
         if self.linear_counter_reload {
@@ -38,13 +32,14 @@ impl Triangle {
             self.linear_counter -= 1;
         }
 
-        if !self.length_counter_halt {
+        if !self.control_flag {
             self.linear_counter_reload = false;
         }
     }
 
     pub fn set_counter_reload(&mut self, data: u8) {
-        self.length_counter_halt = (data & 0b10000000) > 0;
+        self.control_flag = (data & 0b10000000) > 0;
+        self.length_counter.set_halt(self.control_flag);
         self.counter_reload = data & 0b01111111;
     }
 
@@ -53,26 +48,20 @@ impl Triangle {
     }
 
     pub fn set_timer_high(&mut self, data: u8) {
-        if self.enabled {
-            let index = ((data & 0b11111000) >> 3) as usize;
-            self.length_counter = super::LENGTH_COUNTER_TABLE[index];
-        }
-
+        self.length_counter.load(data);
         self.timer.set_high(data);
         self.linear_counter_reload = true;
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
-        if !enabled {
-            self.length_counter = 0;
-        }
+    /// Resetting puts the waveform back at its first step
+    pub fn reset(&mut self) {
+        self.sequencer_counter = 0;
     }
 
     fn get_sample(&mut self) -> u8 {
         if self.timer.counter == 0 {
             self.timer.reset();
-            if self.length_counter != 0 && self.linear_counter != 0 {
+            if self.length_counter.counter != 0 && self.linear_counter != 0 {
                 self.sequencer_counter = (self.sequencer_counter + 1) % SEQUENCER_LENGTH;
             }
         } else {
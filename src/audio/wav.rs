@@ -0,0 +1,184 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use super::mixer::CHANNELS;
+use super::resampler::Resampler;
+
+const HEADER_SIZE: u32 = 44;
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+
+/// The highest each channel's raw output goes, expansion audio is already a sample
+const CHANNEL_MAXIMUMS: [f32; 6] = [15.0, 15.0, 15.0, 15.0, 127.0, 1.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleFormat {
+    Pcm16,
+    Float
+}
+
+impl SampleFormat {
+    fn get_bytes(&self) -> u16 {
+        match self {
+            SampleFormat::Pcm16 => 2,
+            SampleFormat::Float => 4
+        }
+    }
+}
+
+/// Writes interleaved samples to a WAV file.
+/// The sizes in the header aren't known until the end, so they're filled in when the writer is dropped.
+#[derive(Debug)]
+pub struct WavWriter {
+    channels: u16,
+    data_size: u32,
+    file: BufWriter<File>,
+    format: SampleFormat
+}
+
+impl WavWriter {
+    pub fn new(file_path: &str, sample_rate: u32, channels: u16, format: SampleFormat) -> io::Result<Self> {
+        let file = File::create(file_path)?;
+        let mut writer = WavWriter {
+            channels,
+            data_size: 0,
+            file: BufWriter::new(file),
+            format
+        };
+
+        writer.write_header(sample_rate)?;
+        Ok(writer)
+    }
+
+    /// Samples go from -1 to 1, anything outside of that is clipped when written as 16 bit
+    pub fn write(&mut self, sample: f32) {
+        let result = match self.format {
+            SampleFormat::Pcm16 => {
+                let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                self.file.write_all(&sample.to_le_bytes())
+            },
+            SampleFormat::Float => self.file.write_all(&sample.to_le_bytes())
+        };
+
+        result.expect("Error writing to WAV file");
+        self.data_size += self.format.get_bytes() as u32;
+    }
+
+    fn write_header(&mut self, sample_rate: u32) -> io::Result<()> {
+        let bytes = self.format.get_bytes();
+        let format = match self.format {
+            SampleFormat::Pcm16 => FORMAT_PCM,
+            SampleFormat::Float => FORMAT_FLOAT
+        };
+
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(HEADER_SIZE - 8).to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&format.to_le_bytes());
+        header.extend_from_slice(&self.channels.to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * (self.channels * bytes) as u32).to_le_bytes());
+        header.extend_from_slice(&(self.channels * bytes).to_le_bytes());
+        header.extend_from_slice(&(bytes * 8).to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        self.file.write_all(&header)
+    }
+
+    fn write_sizes(&mut self) -> io::Result<()> {
+        // A sample cut off halfway through a frame would shift every channel after it
+        let frame_size = (self.channels * self.format.get_bytes()) as u32;
+        let data_size = self.data_size - (self.data_size % frame_size);
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&data_size.to_le_bytes())?;
+        self.file.flush()
+    }
+}
+
+/// Panicking in drop would abort if it happened while unwinding, so a file that can't be finished is only reported
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        if let Err(error) = self.write_sizes() {
+            eprintln!("Error finishing WAV file: {}", error);
+        }
+    }
+}
+
+/// Writes a WAV file on a thread of its own so the audio callback never waits on the disk.
+/// Samples are sent over a channel and the file is finished when the recorder is dropped.
+#[derive(Debug)]
+pub struct WavRecorder {
+    sender: Option<Sender<Vec<f32>>>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl WavRecorder {
+    /// The file is created straight away so a bad path is reported to the caller rather than on the writer thread
+    pub fn new(file_path: &str, sample_rate: u32, channels: u16, format: SampleFormat) -> io::Result<Self> {
+        let mut writer = WavWriter::new(file_path, sample_rate, channels, format)?;
+        let (sender, receiver) = mpsc::channel::<Vec<f32>>();
+        let thread = thread::spawn(move || {
+            for samples in receiver {
+                samples.iter().for_each(|sample| writer.write(*sample));
+            }
+        });
+
+        Ok(WavRecorder {
+            sender: Some(sender),
+            thread: Some(thread)
+        })
+    }
+
+    pub fn write(&self, samples: &[f32]) {
+        if let Some(ref sender) = self.sender {
+            // Only fails if the writer thread has stopped, which it reports itself
+            let _ = sender.send(samples.to_vec());
+        }
+    }
+}
+
+/// Closing the channel lets the writer thread finish the file, waiting for it means the file is complete once this returns
+impl Drop for WavRecorder {
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Dumps each channel straight from the APU into its own file, before it's mixed or filtered.
+/// They're resampled so they line up with each other and open in anything that plays a WAV.
+#[derive(Debug)]
+pub struct Multitrack {
+    tracks: Vec<(Resampler, WavWriter)>
+}
+
+impl Multitrack {
+    /// Each channel goes into a file named after it, game.nes.pulse1.wav for game.nes.wav.
+    /// If any of them can't be created the ones that were are still left behind, empty.
+    pub fn new(file_path: &str, input_rate: f64, sample_rate: u32, format: SampleFormat) -> io::Result<Self> {
+        let tracks = CHANNELS.iter().map(|channel| {
+            let name = format!("{:?}", channel).to_lowercase();
+            let track_path = std::path::Path::new(file_path).with_extension(format!("{}.wav", name));
+            let track_path = track_path.to_str().expect("Error converting track path to string");
+            Ok((Resampler::new(input_rate, sample_rate as f64), WavWriter::new(track_path, sample_rate, 1, format)?))
+        }).collect::<io::Result<_>>()?;
+
+        Ok(Multitrack { tracks })
+    }
+
+    /// Takes the raw output of each channel, in the same order as CHANNELS
+    pub fn add_samples(&mut self, outputs: &[f32; 6]) {
+        for (index, (resampler, writer)) in self.tracks.iter_mut().enumerate() {
+            resampler.add_sample(outputs[index] / CHANNEL_MAXIMUMS[index]);
+            resampler.read_samples(|sample| writer.write(sample));
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::addresses::{AddressRange, get_address_range};
 use crate::ppu;
 use crate::ppu::sprites;
 use crate::cartridge;
+use crate::cheats::Cheats;
 use crate::audio;
 use crate::controller;
 
@@ -20,6 +21,8 @@ pub struct Bus {
     pub ppu: ppu::Ppu2C02,
     pub apu: audio::Apu2A03,
     pub cartridge: Option<Rc<RefCell<cartridge::Cartridge>>>,
+    #[serde(skip)]
+    pub cheats: Cheats,
     pub controllers: [controller::Controller; 2],
     pub dma: sprites::DirectMemoryAccess,
     pub dma_transfer: bool,
@@ -35,6 +38,7 @@ impl Bus {
             ppu: ppu::Ppu2C02::new(),
             apu: audio::Apu2A03::initialize(),
             cartridge: None,
+            cheats: Default::default(),
             controllers: Default::default(),
             dma: Default::default(),
             dma_transfer: false,
@@ -55,7 +59,7 @@ impl Bus {
         let mut data: u8 = 0;
         if let Some(ref mut c) = self.cartridge {
             if c.borrow_mut().cpu_read(address, &mut data) {
-                return data;
+                return self.cheats.apply_read(address, data);
             }
         }
 
@@ -71,6 +75,26 @@ impl Bus {
         data
     }
 
+    /// Reads RAM or the cartridge's PRG RAM without any side effects, anything else is a register or ROM and gives None
+    pub fn peek(&self, address: u16) -> Option<u8> {
+        match get_address_range(address) {
+            AddressRange::Cpu => Some(self.ram[(address & CPU_MIRROR) as usize]),
+            _ => match self.cartridge {
+                Some(ref c) => c.borrow_mut().peek(address),
+                None => None
+            }
+        }
+    }
+
+    /// Changes RAM or PRG RAM directly, the write never reaches the PPU, APU or mapper registers
+    pub fn poke(&mut self, address: u16, data: u8) {
+        if let AddressRange::Cpu = get_address_range(address) {
+            self.ram[(address & CPU_MIRROR) as usize] = data;
+        } else if let Some(ref c) = self.cartridge {
+            c.borrow_mut().poke(address, data);
+        }
+    }
+
     pub fn write(&mut self, address: u16, data: u8) {
         if let Some(ref mut c) = self.cartridge {
             if c.borrow_mut().cpu_write(address, data) {
@@ -88,6 +112,14 @@ impl Bus {
         }
     }
 
+    /// Writes the frozen values back to RAM, called once a frame
+    pub fn apply_freeze_codes(&mut self) {
+        let freeze_codes = self.cheats.get_freeze_codes().to_vec();
+        for code in freeze_codes {
+            self.write(code.address, code.value);
+        }
+    }
+
     pub fn reset(&mut self) {
         self.apu.reset();
         if let Some(ref mut c) = self.cartridge {
@@ -99,6 +131,13 @@ impl Bus {
         }
     }
 
+    pub fn get_expansion_audio_sample(&self) -> f32 {
+        match self.cartridge {
+            Some(ref c) => c.borrow().get_audio_sample(),
+            None => 0.0
+        }
+    }
+
     fn read_controllers(&mut self, address: u16) -> u8 {
         let masked_address = address & 0x0001;
         self.controllers[masked_address as usize].read(&self.strobe_pulse)
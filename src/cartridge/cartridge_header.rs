@@ -1,3 +1,9 @@
+use crate::database::{Entry, Mirroring};
+use crate::region::Region;
+
+const MIN_SIZE_SHIFT: u32 = 1;
+const MAX_SIZE_SHIFT: u32 = 15;
+
 pub struct CartridgeHeader {
     pub name: [u8; 4],
     pub prg_rom_chunks: u8,
@@ -30,4 +36,181 @@ impl CartridgeHeader {
             unused
         }
     }
-}
\ No newline at end of file
+
+    /// NES 2.0 headers are identified by bits 2-3 of byte 7 being 0b10
+    pub fn is_nes_2(&self) -> bool {
+        self.mapper_2 & 0b1100 == 0b1000
+    }
+
+    /// In NES 2.0 headers the upper nibble of byte 8 is the submapper, iNES headers don't have one
+    pub fn get_submapper(&self) -> u8 {
+        if self.is_nes_2() { self.prg_ram_size >> 4 } else { 0 }
+    }
+
+    /// NES 2.0 headers have the timing in bits 0-1 of byte 12, 2 is for games that work on both so those get NTSC.
+    /// iNES headers only have a PAL flag in bit 0 of byte 9, which hardly any dumps set.
+    pub fn get_region(&self) -> Region {
+        if self.is_nes_2() {
+            return match self.unused[1] & 0b11 {
+                1 => Region::Pal,
+                3 => Region::Dendy,
+                _ => Region::Ntsc
+            };
+        }
+
+        if self.tv_system_1 & 1 != 0 { Region::Pal } else { Region::Ntsc }
+    }
+
+    /// NES 2.0 headers have the CHR RAM size in the lower nibble of byte 11 as a shift count, 64 << n bytes
+    pub fn get_chr_ram_size(&self) -> Option<usize> {
+        let shift = self.unused[0] & 0x0F;
+        if !self.is_nes_2() || shift == 0 {
+            return None;
+        }
+
+        Some(64 << shift)
+    }
+
+    /// NES 2.0 headers have the PRG RAM size in byte 10 as shift counts, 64 << n bytes.
+    /// The lower nibble is volatile RAM and the upper nibble is battery backed RAM, a board has one or the other.
+    pub fn get_prg_ram_size(&self) -> Option<usize> {
+        if !self.is_nes_2() {
+            return None;
+        }
+
+        let size = [self.tv_system_2 & 0x0F, self.tv_system_2 >> 4].iter()
+            .filter(|shift| **shift != 0)
+            .map(|shift| 64 << shift)
+            .sum();
+
+        if size == 0 { None } else { Some(size) }
+    }
+
+    /// Rewrites the header as NES 2.0 with the database's corrections applied, so the rest of the loading code
+    /// doesn't need to know where the values came from
+    pub fn apply_database_entry(&mut self, entry: &Entry) {
+        let mapper_id = ((self.mapper_2 >> 4) << 4) | (self.mapper_1 >> 4);
+        let submapper = self.get_submapper();
+        let region = self.get_region();
+        if !self.is_nes_2() {
+            self.prg_ram_size = 0;
+            self.tv_system_1 = 0;
+            self.tv_system_2 = 0;
+            self.unused = [0; 5];
+        }
+
+        let mapper_id = entry.mapper.unwrap_or(mapper_id);
+        let submapper = entry.submapper.unwrap_or(submapper);
+        let region = entry.region.unwrap_or(region);
+
+        self.mapper_1 = (mapper_id << 4) | (self.mapper_1 & 0x0F);
+        self.mapper_2 = (mapper_id & 0xF0) | (self.mapper_2 & 0x03) | 0x08;
+        self.prg_ram_size = submapper << 4;
+
+        if let Some(mirroring) = entry.mirroring {
+            self.mapper_1 &= !0b1001;
+            self.mapper_1 |= match mirroring {
+                Mirroring::Horizontal => 0,
+                Mirroring::Vertical => 0b0001,
+                Mirroring::FourScreen => 0b1000
+            };
+        }
+
+        if let Some(battery) = entry.battery {
+            self.mapper_1 = if battery { self.mapper_1 | 0b10 } else { self.mapper_1 & !0b10 };
+        }
+
+        if let Some(size) = entry.prg_ram_size {
+            // Battery backed RAM goes in the upper nibble
+            let shift = get_size_shift(size);
+            self.tv_system_2 = if self.mapper_1 & 0b10 != 0 { shift << 4 } else { shift };
+        }
+
+        if let Some(size) = entry.chr_ram_size {
+            self.unused[0] = get_size_shift(size);
+        }
+
+        self.unused[1] = match region {
+            Region::Ntsc => 0,
+            Region::Pal => 1,
+            Region::Dendy => 3
+        };
+    }
+}
+
+/// RAM sizes are stored as a shift count, 64 << n bytes with 0 meaning no RAM.
+/// Anything that isn't a size the header can hold is rounded up, so the game never gets less RAM than it asked for.
+fn get_size_shift(size: u32) -> u8 {
+    if size == 0 {
+        return 0;
+    }
+
+    let size = size.clamp(64 << MIN_SIZE_SHIFT, 64 << MAX_SIZE_SHIFT).next_power_of_two();
+    (size / 64).trailing_zeros() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_entry() -> Entry {
+        Entry {
+            title: None,
+            mapper: None,
+            submapper: None,
+            mirroring: None,
+            battery: None,
+            region: None,
+            prg_ram_size: None,
+            chr_ram_size: None
+        }
+    }
+
+    /// An iNES header for mapper 1 with horizontal mirroring and no battery
+    fn create_header() -> CartridgeHeader {
+        CartridgeHeader::new(&[b'N', b'E', b'S', 0x1A, 2, 1, 0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+    }
+
+    #[test]
+    fn empty_entry_keeps_header() {
+        let mut header = create_header();
+        header.apply_database_entry(&create_entry());
+        assert!(header.is_nes_2());
+        assert_eq!(header.mapper_1, 0x10);
+        assert_eq!(header.get_region(), Region::Ntsc);
+        assert_eq!(header.get_prg_ram_size(), None);
+    }
+
+    #[test]
+    fn entry_corrects_header() {
+        let mut header = create_header();
+        header.apply_database_entry(&Entry {
+            mapper: Some(0x45),
+            submapper: Some(3),
+            mirroring: Some(Mirroring::Vertical),
+            battery: Some(true),
+            region: Some(Region::Dendy),
+            prg_ram_size: Some(8192),
+            chr_ram_size: Some(32768),
+            ..create_entry()
+        });
+
+        assert_eq!(header.mapper_1, 0x53);
+        assert_eq!(header.mapper_2 & 0xF0, 0x40);
+        assert_eq!(header.get_submapper(), 3);
+        assert_eq!(header.get_region(), Region::Dendy);
+        assert_eq!(header.tv_system_2, 0x70);
+        assert_eq!(header.get_prg_ram_size(), Some(8192));
+        assert_eq!(header.get_chr_ram_size(), Some(32768));
+    }
+
+    #[test]
+    fn sizes_round_up() {
+        assert_eq!(get_size_shift(0), 0);
+        assert_eq!(get_size_shift(1), 1);
+        assert_eq!(get_size_shift(128), 1);
+        assert_eq!(get_size_shift(8192), 7);
+        assert_eq!(get_size_shift(8193), 8);
+        assert_eq!(get_size_shift(u32::MAX), 15);
+    }
+}
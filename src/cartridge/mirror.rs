@@ -6,5 +6,9 @@ pub enum Mirror {
     Horizontal,
     Vertical,
     OneScreenLow,
-    OneScreenHigh
+    OneScreenHigh,
+
+    /// Each 1KB name table is mapped by the cartridge to a CIRAM page (0 or 1).
+    /// Any other page means the name table isn't backed by CIRAM.
+    Mapped([u8; 4])
 }
\ No newline at end of file
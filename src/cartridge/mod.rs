@@ -3,9 +3,24 @@ pub mod mirror;
 
 use serde::{Serialize, Deserialize};
 use std::fs;
-use crate::memory_sizes::KILOBYTES_16;
+use std::path::Path;
+use crate::addresses::mappers::{CPU_MIN_ADDRESS, PRG_RAM_MIN_ADDRESS, PRG_RAM_MAX_ADDRESS};
+use crate::database::Database;
+use crate::memory_sizes::{KILOBYTES_8, KILOBYTES_16, KILOBYTES_32};
 use crate::mappers;
+use crate::nsf;
+use crate::patches;
+use crate::region::Region;
 use crate::mappers::mapper_save_data::*;
+use crate::mappers::mapper004::interrupt_request::Revision;
+use crate::mappers::mapper004::variant::Variant;
+
+const MMC6_SUBMAPPER: u8 = 1;
+const MMC3A_SUBMAPPER: u8 = 4;
+const TQROM_MAPPER_ID: u8 = 119;
+const DISK_SYSTEM_MAPPER_ID: u8 = 20;
+const DISK_SYSTEM_BIOS: &str = "disksys.rom";
+const NSF_MAPPER_ID: u8 = 31;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Cartridge {
@@ -18,7 +33,9 @@ pub struct Cartridge {
     mapper_id: u8,
     mirror: mirror::Mirror,
     prg_banks: u8,
-    prg_memory: Vec<u8>
+    prg_memory: Vec<u8>,
+    pub region: Region,
+    pub title: Option<String>
 }
 
 impl Cartridge {
@@ -30,23 +47,38 @@ impl Cartridge {
     /// CHR ROM data, if present (8192 * y bytes)
     /// PlayChoice INST-ROM, if present (0 or 8192 bytes)
     /// PlayChoice PROM, if present (16 bytes Data, 16 bytes CounterOut) (this is often missing, see PC10 ROM-Images for details)
-    pub fn new(file_path: &str) -> Self {
-        let bytes = fs::read(file_path).expect("Cannot find file");
-        let header = cartridge_header::CartridgeHeader::new(&bytes);
-        let mapper_id = ((header.mapper_2 >> 4) << 4) | (header.mapper_1 >> 4);
-        let mirror = if (header.mapper_1 & 0x01) > 0 { mirror::Mirror::Vertical } else { mirror::Mirror::Horizontal };
-
+    ///
+    /// Any patches are applied to the whole file before the header is read.
+    /// The header is then checked against the database, using the CRC-32 of the PRG and CHR ROM.
+    pub fn new(file_path: &str, database: &Database, patch_paths: &[String]) -> Self {
+        let mut bytes = fs::read(file_path).expect("Cannot find file");
+        patches::apply_all(&mut bytes, patch_paths);
+        let mut header = cartridge_header::CartridgeHeader::new(&bytes);
         let prg_memory_size = ((header.prg_rom_chunks as u32) * (KILOBYTES_16 as u32)) as usize;
+        let chr_memory_size = ((header.chr_rom_chunks as u32) * 8192) as usize;
         let post_header_index = if (header.mapper_1 & 0x04) > 0 { 16 + 512 } else { 16 };
 
+        let rom_end = (post_header_index + prg_memory_size + chr_memory_size).min(bytes.len());
+        let entry = database.find(&bytes[post_header_index..rom_end]);
+        if let Some(entry) = entry {
+            header.apply_database_entry(entry);
+        }
+
+        let mapper_id = ((header.mapper_2 >> 4) << 4) | (header.mapper_1 >> 4);
+        let mirror = if (header.mapper_1 & 0x01) > 0 { mirror::Mirror::Vertical } else { mirror::Mirror::Horizontal };
+
         let chr_memory_start = (post_header_index + prg_memory_size) as usize;
-        let chr_memory = if header.chr_rom_chunks == 0 { 
-            vec![0; 8192]
+        let mut chr_memory = if header.chr_rom_chunks == 0 { 
+            vec![0; header.get_chr_ram_size().unwrap_or(8192)]
         } else { 
-            let chr_memory_size = ((header.chr_rom_chunks as u32) * 8192) as usize;
             bytes[chr_memory_start..(chr_memory_start + chr_memory_size)].to_vec()
         };
 
+        // TQROM has 8KB of CHR RAM alongside its CHR ROM
+        if mapper_id == TQROM_MAPPER_ID {
+            chr_memory.extend(vec![0; KILOBYTES_8 as usize]);
+        }
+
         let prg_memory = bytes[post_header_index..(post_header_index + prg_memory_size)].to_vec();
 
         Cartridge {
@@ -58,7 +90,52 @@ impl Cartridge {
             mapper: Cartridge::get_mapper(mapper_id, &header, file_path, mirror),
             mapper_save_data: MapperSaveData::None,
             mapper_id,
-            mirror
+            mirror,
+            region: header.get_region(),
+            title: entry.and_then(|e| e.title.to_owned())
+        }
+    }
+
+    /// A Famicom Disk System image runs from the RAM adapter with the BIOS mapped in at $E000.
+    /// The BIOS isn't included with the images so disksys.rom has to sit next to the image or in the working directory.
+    pub fn from_disk_system(file_path: &str) -> Self {
+        let sides = mappers::fds::disk::to_raw_sides(&mappers::fds::disk::load_image(file_path));
+        if sides.is_empty() {
+            panic!("Disk image doesn't contain any sides");
+        }
+
+        let mut prg_memory = vec![0; mappers::fds::RAM_SIZE];
+        prg_memory.extend(Cartridge::load_disk_system_bios(file_path));
+
+        Cartridge {
+            chr_banks: 0,
+            chr_memory: vec![0; KILOBYTES_8 as usize],
+            file_path: file_path.to_owned(),
+            prg_banks: 0,
+            prg_memory,
+            mapper: Some(Box::new(mappers::fds::Fds::new(sides))),
+            mapper_save_data: MapperSaveData::None,
+            mapper_id: DISK_SYSTEM_MAPPER_ID,
+            mirror: mirror::Mirror::Vertical,
+            region: Region::Ntsc,
+            title: None
+        }
+    }
+
+    /// An NSF has no PRG or CHR ROM of its own, the mapper holds the music data and the driver that plays it
+    pub fn from_nsf(file_path: &str, file: &nsf::NsfFile) -> Self {
+        Cartridge {
+            chr_banks: 0,
+            chr_memory: vec![0; KILOBYTES_8 as usize],
+            file_path: file_path.to_owned(),
+            prg_banks: 0,
+            prg_memory: vec![],
+            mapper: Some(Box::new(mappers::nsf::Nsf::new(file))),
+            mapper_save_data: MapperSaveData::None,
+            mapper_id: NSF_MAPPER_ID,
+            mirror: mirror::Mirror::Vertical,
+            region: file.region,
+            title: None
         }
     }
 
@@ -84,9 +161,48 @@ impl Cartridge {
         false
     }
 
+    /// Reads PRG RAM at $6000-$7FFF for debugging, none of the mappers change their state when it's read
+    pub fn peek(&mut self, address: u16) -> Option<u8> {
+        if !(PRG_RAM_MIN_ADDRESS..=PRG_RAM_MAX_ADDRESS).contains(&address) {
+            return None;
+        }
+
+        let mut data = 0;
+        if self.cpu_read(address, &mut data) { Some(data) } else { None }
+    }
+
+    /// Changes PRG RAM at $6000-$7FFF for debugging, without any writes to the mapper's registers.
+    /// Returns false when there's no RAM at the address.
+    pub fn poke(&mut self, address: u16, data: u8) -> bool {
+        if !(PRG_RAM_MIN_ADDRESS..=PRG_RAM_MAX_ADDRESS).contains(&address) {
+            return false;
+        }
+
+        if let Some(ref mut m) = self.mapper {
+            let result = m.cpu_map_read(address);
+            if result.read_from_cart_ram {
+                self.prg_memory[result.mapped_address as usize] = data;
+                return true;
+            } else if result.read_from_mapper_ram {
+                m.poke_ram(address, data);
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// Write to the Main Bus
     pub fn cpu_write(&mut self, address: u16, data: u8) -> bool {
         if let Some(ref mut m) = self.mapper {
+            let mut data = data;
+            if m.has_bus_conflicts() && address >= CPU_MIN_ADDRESS {
+                let read_result = m.cpu_map_read(address);
+                if read_result.read_from_cart_ram {
+                    data &= self.prg_memory[read_result.mapped_address as usize];
+                }
+            }
+
             let result = m.cpu_map_write(address, data);
             if result.write_to_cart_ram {
                 self.prg_memory[result.mapped_address as usize] = data;
@@ -129,6 +245,59 @@ impl Cartridge {
         false
     }
 
+    pub fn get_pattern_ciram_page(&self, address: u16) -> Option<u8> {
+        match self.mapper {
+            Some(ref m) => m.get_pattern_ciram_page(address),
+            None => None
+        }
+    }
+
+    pub fn ppu_address(&mut self, address: u16) {
+        if let Some(ref mut m) = self.mapper {
+            m.ppu_address(address);
+        }
+    }
+
+    pub fn insert_or_eject_disk(&mut self) {
+        if let Some(ref mut m) = self.mapper {
+            m.insert_or_eject_disk();
+        }
+    }
+
+    pub fn switch_disk_side(&mut self) {
+        if let Some(ref mut m) = self.mapper {
+            m.switch_disk_side();
+        }
+    }
+
+    pub fn select_song(&mut self, song: u8) {
+        if let Some(ref mut m) = self.mapper {
+            m.select_song(song);
+        }
+    }
+
+    pub fn cpu_clock(&mut self) {
+        if let Some(ref mut m) = self.mapper {
+            m.cpu_clock();
+        }
+    }
+
+    pub fn get_audio_sample(&self) -> f32 {
+        match self.mapper {
+            Some(ref m) => m.get_audio_sample(),
+            None => 0.0
+        }
+    }
+
+    /// The MD5 of the PRG and CHR ROM, which is how FCEUX movies identify the game
+    pub fn get_rom_md5(&self) -> [u8; 16] {
+        let chr_rom_size = (self.chr_banks as usize) * (KILOBYTES_8 as usize);
+        let mut context = md5::Context::new();
+        context.consume(&self.prg_memory);
+        context.consume(&self.chr_memory[..chr_rom_size.min(self.chr_memory.len())]);
+        context.compute().0
+    }
+
     pub fn get_mirror(&self) -> mirror::Mirror {
         match self.mapper {
             Some(ref mapper) => {
@@ -156,7 +325,18 @@ impl Cartridge {
             MapperSaveData::Mapper002(ref m) => Some(Box::new(mappers::mapper002::Mapper002::from(m))),
             MapperSaveData::Mapper003(ref m) => Some(Box::new(mappers::mapper003::Mapper003::from(m))),
             MapperSaveData::Mapper004(ref m) => Some(Box::new(mappers::mapper004::Mapper004::from(m))),
-            MapperSaveData::Mapper066(ref m) => Some(Box::new(mappers::mapper066::Mapper066::from(m)))
+            MapperSaveData::Mapper007(ref m) => Some(Box::new(mappers::mapper007::Mapper007::from(m))),
+            MapperSaveData::Mapper011(ref m) => Some(Box::new(mappers::mapper011::Mapper011::from(m))),
+            MapperSaveData::Mapper019(ref m) => Some(Box::new(mappers::mapper019::Mapper019::from(m))),
+            MapperSaveData::Mapper034(ref m) => Some(Box::new(mappers::mapper034::Mapper034::from(m))),
+            MapperSaveData::Mapper066(ref m) => Some(Box::new(mappers::mapper066::Mapper066::from(m))),
+            MapperSaveData::Mapper071(ref m) => Some(Box::new(mappers::mapper071::Mapper071::from(m))),
+            MapperSaveData::Mapper079(ref m) => Some(Box::new(mappers::mapper079::Mapper079::from(m))),
+            MapperSaveData::Mapper085(ref m) => Some(Box::new(mappers::mapper085::Mapper085::from(m))),
+            MapperSaveData::Mapper140(ref m) => Some(Box::new(mappers::mapper140::Mapper140::from(m))),
+            MapperSaveData::Mapper180(ref m) => Some(Box::new(mappers::mapper180::Mapper180::from(m))),
+            MapperSaveData::Fds(ref m) => Some(Box::new(mappers::fds::Fds::from(m))),
+            MapperSaveData::Nsf(ref m) => Some(Box::new(mappers::nsf::Nsf::from(m)))
         }
     }
 
@@ -170,13 +350,35 @@ impl Cartridge {
         let prg_banks = header.prg_rom_chunks;
         let chr_banks = header.chr_rom_chunks;
         let has_battery_backed_ram = (header.mapper_1 >> 1) & 1 != 0;
+        let submapper = header.get_submapper();
+        let prg_ram_size = header.get_prg_ram_size();
+        let ram_size = prg_ram_size.unwrap_or(KILOBYTES_8 as usize);
         let mut mapper: Option<Box<dyn mappers::mapper::Mapper>> =  match mapper_id {
             0 => Some(Box::new(mappers::mapper000::Mapper000::new(prg_banks, chr_banks, has_battery_backed_ram))),
-            1 => Some(Box::new(mappers::mapper001::Mapper001::new(prg_banks, chr_banks, has_battery_backed_ram, mirror))),
+            1 => Some(Box::new(mappers::mapper001::Mapper001::new(prg_banks, chr_banks, has_battery_backed_ram, mirror, prg_ram_size.unwrap_or(KILOBYTES_32 as usize)))),
             2 => Some(Box::new(mappers::mapper002::Mapper002::new(prg_banks, chr_banks, has_battery_backed_ram))),
             3 => Some(Box::new(mappers::mapper003::Mapper003::new(prg_banks, chr_banks, has_battery_backed_ram))),
-            4 => Some(Box::new(mappers::mapper004::Mapper004::new(prg_banks, chr_banks, has_battery_backed_ram, mirror))),
+            4 => {
+                let (variant, revision) = match submapper {
+                    MMC6_SUBMAPPER => (Variant::Mmc6, Revision::A),
+                    MMC3A_SUBMAPPER => (Variant::Standard, Revision::A),
+                    _ => (Variant::Standard, Revision::B)
+                };
+
+                Some(Box::new(mappers::mapper004::Mapper004::new(prg_banks, chr_banks, has_battery_backed_ram, mirror, variant, revision, ram_size)))
+            },
+            7 => Some(Box::new(mappers::mapper007::Mapper007::new(prg_banks, chr_banks, has_battery_backed_ram, submapper))),
+           11 => Some(Box::new(mappers::mapper011::Mapper011::new(prg_banks, chr_banks, has_battery_backed_ram))),
+           19 => Some(Box::new(mappers::mapper019::Mapper019::new(prg_banks, chr_banks, has_battery_backed_ram, ram_size))),
+           34 => Some(Box::new(mappers::mapper034::Mapper034::new(prg_banks, chr_banks, has_battery_backed_ram, submapper, ram_size))),
            66 => Some(Box::new(mappers::mapper066::Mapper066::new(prg_banks, chr_banks, has_battery_backed_ram))),
+           71 => Some(Box::new(mappers::mapper071::Mapper071::new(prg_banks, chr_banks, has_battery_backed_ram, submapper))),
+           79 => Some(Box::new(mappers::mapper079::Mapper079::new(prg_banks, chr_banks, has_battery_backed_ram))),
+           85 => Some(Box::new(mappers::mapper085::Mapper085::new(prg_banks, chr_banks, has_battery_backed_ram, ram_size))),
+          118 => Some(Box::new(mappers::mapper004::Mapper004::new(prg_banks, chr_banks, has_battery_backed_ram, mirror, Variant::TxSrom, Revision::B, ram_size))),
+          119 => Some(Box::new(mappers::mapper004::Mapper004::new(prg_banks, chr_banks, has_battery_backed_ram, mirror, Variant::Tqrom, Revision::B, ram_size))),
+          140 => Some(Box::new(mappers::mapper140::Mapper140::new(prg_banks, chr_banks, has_battery_backed_ram))),
+          180 => Some(Box::new(mappers::mapper180::Mapper180::new(prg_banks, chr_banks, has_battery_backed_ram))),
             _ => None
         };
 
@@ -186,4 +388,15 @@ impl Cartridge {
 
         mapper
     }
+
+    fn load_disk_system_bios(file_path: &str) -> Vec<u8> {
+        let next_to_image = Path::new(file_path).with_file_name(DISK_SYSTEM_BIOS);
+        let bios_path = if next_to_image.exists() { next_to_image } else { Path::new(DISK_SYSTEM_BIOS).to_path_buf() };
+        let bios = fs::read(bios_path).expect("Cannot find disksys.rom, the Famicom Disk System BIOS");
+        if bios.len() != KILOBYTES_8 as usize {
+            panic!("disksys.rom should be 8KB");
+        }
+
+        bios
+    }
 }
\ No newline at end of file
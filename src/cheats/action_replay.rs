@@ -0,0 +1,60 @@
+use crate::addresses::mappers::{PRG_RAM_MIN_ADDRESS, PRG_RAM_MAX_ADDRESS};
+
+const RAM_END: u16 = 0x07FF;
+
+/// A Pro Action Replay code keeps a byte of RAM at the same value by writing it again every frame.
+/// Codes are the address followed by the value in hex, like 0075:09 or 007509.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FreezeCode {
+    pub address: u16,
+    pub value: u8
+}
+
+impl FreezeCode {
+    pub fn decode(code: &str) -> Option<Self> {
+        let digits: String = code.chars().filter(|c| *c != ':').collect();
+        if digits.len() != 6 || !digits.is_ascii() {
+            return None;
+        }
+
+        let address = u16::from_str_radix(&digits[0..4], 16).ok()?;
+        let value = u8::from_str_radix(&digits[4..6], 16).ok()?;
+
+        // Only RAM can be frozen, writing registers every frame would have side effects
+        if address > RAM_END && !(PRG_RAM_MIN_ADDRESS..=PRG_RAM_MAX_ADDRESS).contains(&address) {
+            return None;
+        }
+
+        Some(FreezeCode { address, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_with_or_without_colon() {
+        let code = FreezeCode { address: 0x0075, value: 0x09 };
+        assert_eq!(FreezeCode::decode("0075:09"), Some(code));
+        assert_eq!(FreezeCode::decode("007509"), Some(code));
+    }
+
+    #[test]
+    fn decodes_prg_ram() {
+        assert_eq!(FreezeCode::decode("6000:FF"), Some(FreezeCode { address: 0x6000, value: 0xFF }));
+    }
+
+    #[test]
+    fn rejects_registers_and_rom() {
+        assert_eq!(FreezeCode::decode("2000:00"), None);
+        assert_eq!(FreezeCode::decode("8000:00"), None);
+    }
+
+    #[test]
+    fn rejects_bad_codes() {
+        assert_eq!(FreezeCode::decode("0075:9"), None);
+        assert_eq!(FreezeCode::decode("0075:0G"), None);
+        assert_eq!(FreezeCode::decode("00é509"), None);
+    }
+}
@@ -0,0 +1,89 @@
+const LETTERS: &str = "APZLGITYEOXUKSVN";
+
+/// A Game Genie code replaces what the CPU reads from an address in PRG ROM,
+/// 8 letter codes only replace it when the ROM has the compare value there so they can target one bank.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameGenieCode {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>
+}
+
+impl GameGenieCode {
+    /// Each letter is 4 bits, and the address, value and compare bits are shuffled across them
+    pub fn decode(code: &str) -> Option<Self> {
+        let n: Vec<u16> = code.chars()
+            .map(|letter| LETTERS.find(letter.to_ascii_uppercase()).map(|index| index as u16))
+            .collect::<Option<Vec<u16>>>()?;
+
+        if n.len() != 6 && n.len() != 8 {
+            return None;
+        }
+
+        let address = 0x8000 +
+            (((n[3] & 7) << 12) | ((n[5] & 7) << 8) | ((n[4] & 8) << 8) | ((n[2] & 7) << 4) | ((n[1] & 8) << 4) | (n[4] & 7) | (n[3] & 8));
+
+        let last = if n.len() == 6 { n[5] } else { n[7] };
+        let value = (((n[1] & 7) << 4) | ((n[0] & 8) << 4) | (n[0] & 7) | (last & 8)) as u8;
+        let compare = if n.len() == 8 {
+            Some((((n[7] & 7) << 4) | ((n[6] & 8) << 4) | (n[6] & 7) | (n[5] & 8)) as u8)
+        } else {
+            None
+        };
+
+        Some(GameGenieCode { address, value, compare })
+    }
+
+    pub fn is_game_genie(code: &str) -> bool {
+        code.chars().all(|letter| LETTERS.contains(letter.to_ascii_uppercase()))
+    }
+
+    pub fn apply(&self, address: u16, data: u8) -> u8 {
+        if address != self.address {
+            return data;
+        }
+
+        match self.compare {
+            Some(compare) if compare != data => data,
+            _ => self.value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_six_letter_code() {
+        let code = GameGenieCode::decode("GOSSIP").unwrap();
+        assert_eq!(code, GameGenieCode { address: 0xD1DD, value: 0x14, compare: None });
+    }
+
+    #[test]
+    fn decodes_eight_letter_code() {
+        let code = GameGenieCode::decode("ZEXPYGLA").unwrap();
+        assert_eq!(code, GameGenieCode { address: 0x94A7, value: 0x02, compare: Some(0x03) });
+    }
+
+    #[test]
+    fn letters_are_case_insensitive() {
+        assert_eq!(GameGenieCode::decode("gossip"), GameGenieCode::decode("GOSSIP"));
+    }
+
+    #[test]
+    fn rejects_bad_codes() {
+        assert_eq!(GameGenieCode::decode("GOSSI"), None);
+        assert_eq!(GameGenieCode::decode("GOSSIPP"), None);
+        assert_eq!(GameGenieCode::decode("GOSSIB"), None);
+        assert!(!GameGenieCode::is_game_genie("0075:09"));
+    }
+
+    #[test]
+    fn compare_value_has_to_match() {
+        let code = GameGenieCode::decode("ZEXPYGLA").unwrap();
+        assert_eq!(code.apply(0x94A7, 0x03), 0x02);
+        assert_eq!(code.apply(0x94A7, 0x04), 0x04);
+        assert_eq!(code.apply(0x94A8, 0x03), 0x03);
+    }
+}
@@ -0,0 +1,96 @@
+pub mod action_replay;
+pub mod game_genie;
+
+use std::fs;
+use std::path::Path;
+use crate::mappers::battery_backed_ram::get_save_data_path;
+use action_replay::FreezeCode;
+use game_genie::GameGenieCode;
+
+/// Cheats for a game live next to its saves in a .cht file, one code per line with an optional description after it:
+/// SXIOPO Infinite lives
+/// 0075:09 Freeze lives at 9
+/// Lines starting with # are comments and codes starting with ! are turned off.
+#[derive(Default)]
+pub struct Cheats {
+    pub enabled: bool,
+    file_path: String,
+    freeze_codes: Vec<FreezeCode>,
+    game_genie_codes: Vec<GameGenieCode>,
+    invalid_codes: Vec<String>
+}
+
+impl Cheats {
+    /// Loads the cheat file for the ROM, cheats start turned on if there are any
+    pub fn new(file_path: &str) -> Self {
+        let mut cheats = Cheats {
+            enabled: false,
+            file_path: get_save_data_path(file_path, ".cht"),
+            freeze_codes: vec![],
+            game_genie_codes: vec![],
+            invalid_codes: vec![]
+        };
+
+        cheats.load();
+        cheats.enabled = !cheats.is_empty();
+        cheats
+    }
+
+    /// The file is read again when turning cheats on so codes can be edited while the game is running
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if self.enabled {
+            self.load();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.freeze_codes.is_empty() && self.game_genie_codes.is_empty()
+    }
+
+    pub fn apply_read(&self, address: u16, data: u8) -> u8 {
+        if !self.enabled {
+            return data;
+        }
+
+        self.game_genie_codes.iter().fold(data, |data, code| code.apply(address, data))
+    }
+
+    pub fn get_freeze_codes(&self) -> &[FreezeCode] {
+        if self.enabled { &self.freeze_codes } else { &[] }
+    }
+
+    /// Codes from the last time the file was read that couldn't be decoded, they're skipped
+    pub fn get_invalid_codes(&self) -> &[String] {
+        &self.invalid_codes
+    }
+
+    fn load(&mut self) {
+        self.freeze_codes.clear();
+        self.game_genie_codes.clear();
+        self.invalid_codes.clear();
+        if !Path::new(&self.file_path).exists() {
+            return;
+        }
+
+        let contents = fs::read_to_string(&self.file_path).expect("Error reading cheat file");
+        for line in contents.lines() {
+            let code = match line.split_whitespace().next() {
+                Some(code) if !code.starts_with('#') && !code.starts_with('!') => code,
+                _ => continue
+            };
+
+            if GameGenieCode::is_game_genie(code) {
+                match GameGenieCode::decode(code) {
+                    Some(game_genie_code) => self.game_genie_codes.push(game_genie_code),
+                    None => self.invalid_codes.push(code.to_owned())
+                }
+            } else {
+                match FreezeCode::decode(code) {
+                    Some(freeze_code) => self.freeze_codes.push(freeze_code),
+                    None => self.invalid_codes.push(code.to_owned())
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Serialize, Deserialize};
 
 bitfield! {
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
     pub struct ControllerState(u8);
     impl Debug;
 
@@ -1,7 +1,7 @@
 pub mod controller_state;
 
 use serde::{Serialize, Deserialize};
-use std::collections::HashSet;
+use sdl2::EventPump;
 use sdl2::keyboard::{Scancode};
 
 const CONTROLLER_OPEN_BUS: u8 = 0x40;
@@ -40,15 +40,8 @@ impl Controller {
         bit | CONTROLLER_OPEN_BUS
     }
 
-    pub fn set_controller_state(&mut self, pressed_scancodes: HashSet<Scancode>) {
-        self.controller_state.set_a(pressed_scancodes.contains(&Scancode::X));
-        self.controller_state.set_b(pressed_scancodes.contains(&Scancode::Z));
-        self.controller_state.set_select(pressed_scancodes.contains(&Scancode::RShift));
-        self.controller_state.set_start(pressed_scancodes.contains(&Scancode::Return));
-        self.controller_state.set_up(pressed_scancodes.contains(&Scancode::Up));
-        self.controller_state.set_down(pressed_scancodes.contains(&Scancode::Down));
-        self.controller_state.set_left(pressed_scancodes.contains(&Scancode::Left));
-        self.controller_state.set_right(pressed_scancodes.contains(&Scancode::Right));
+    pub fn set_controller_state(&mut self, controller_state: controller_state::ControllerState) {
+        self.controller_state = controller_state;
     }
 
     pub fn write(&mut self, poll_input: &u8) {
@@ -60,6 +53,21 @@ impl Controller {
     }
 }
 
+/// The buttons held on the keyboard for the first controller
+pub fn read_keyboard(event_pump: &EventPump) -> controller_state::ControllerState {
+    let keyboard_state = event_pump.keyboard_state();
+    let mut controller_state = controller_state::ControllerState(0);
+    controller_state.set_a(keyboard_state.is_scancode_pressed(Scancode::X));
+    controller_state.set_b(keyboard_state.is_scancode_pressed(Scancode::Z));
+    controller_state.set_select(keyboard_state.is_scancode_pressed(Scancode::RShift));
+    controller_state.set_start(keyboard_state.is_scancode_pressed(Scancode::Return));
+    controller_state.set_up(keyboard_state.is_scancode_pressed(Scancode::Up));
+    controller_state.set_down(keyboard_state.is_scancode_pressed(Scancode::Down));
+    controller_state.set_left(keyboard_state.is_scancode_pressed(Scancode::Left));
+    controller_state.set_right(keyboard_state.is_scancode_pressed(Scancode::Right));
+    controller_state
+}
+
 impl Default for Controller {
     fn default() -> Self { 
         Controller {
@@ -0,0 +1,35 @@
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// The CRC-32 used by zip files, No-Intro and the patch formats
+pub fn checksum(data: &[u8]) -> u32 {
+    update(0, data)
+}
+
+/// Carries on a checksum from where a previous call left off
+pub fn update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ POLYNOMIAL } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_check_value() {
+        assert_eq!(checksum(b"123456789"), 0xCBF4_3926);
+        assert_eq!(checksum(&[]), 0);
+    }
+
+    #[test]
+    fn update_carries_on() {
+        assert_eq!(update(checksum(b"1234"), b"56789"), checksum(b"123456789"));
+    }
+}
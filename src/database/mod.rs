@@ -0,0 +1,236 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use crate::crc32;
+use crate::region::Region;
+
+const EMBEDDED_DATABASE: &str = include_str!("database.json");
+const MAX_SUBMAPPER: u8 = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    FourScreen
+}
+
+/// A game in the database, anything left out is taken from the header as is
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub title: Option<String>,
+    pub mapper: Option<u8>,
+    pub submapper: Option<u8>,
+    pub mirroring: Option<Mirroring>,
+    pub battery: Option<bool>,
+    pub region: Option<Region>,
+    pub prg_ram_size: Option<u32>,
+    pub chr_ram_size: Option<u32>
+}
+
+/// An entry as it's written in the JSON, crc32 is the CRC-32 of the PRG ROM followed by the CHR ROM, written in hex
+#[derive(Deserialize)]
+struct JsonEntry {
+    crc32: String,
+    title: Option<String>,
+    mapper: Option<u8>,
+    submapper: Option<u8>,
+    mirroring: Option<String>,
+    battery: Option<bool>,
+    region: Option<String>,
+    prg_ram_size: Option<u32>,
+    chr_ram_size: Option<u32>
+}
+
+/// Corrections for dumps with bad headers, looked up by the CRC-32 of the ROM data.
+/// The database built into the emulator is src/database/database.json, a user database with the same
+/// layout can be passed with --database and its entries take priority.
+/// Entries that can't be read are skipped and described in errors so the frontend can show them once.
+pub struct Database {
+    pub errors: Vec<String>,
+    entries: HashMap<u32, Entry>
+}
+
+impl Database {
+    pub fn new(user_database: Option<&str>) -> Self {
+        let mut database = Database {
+            errors: vec![],
+            entries: HashMap::new()
+        };
+
+        database.add_entries("built in database", EMBEDDED_DATABASE);
+        if let Some(file_path) = user_database {
+            match fs::read_to_string(file_path) {
+                Ok(json) => database.add_entries(file_path, &json),
+                Err(error) => database.errors.push(format!("Cannot read {}: {}", file_path, error))
+            }
+        }
+
+        database
+    }
+
+    /// Dumps with no ROM data at all are never looked up, the CRC-32 of nothing is 0
+    pub fn find(&self, rom: &[u8]) -> Option<&Entry> {
+        if rom.is_empty() {
+            return None;
+        }
+
+        self.entries.get(&crc32::checksum(rom))
+    }
+
+    fn add_entries(&mut self, source: &str, json: &str) {
+        let values: Vec<serde_json::Value> = match serde_json::from_str(json) {
+            Ok(values) => values,
+            Err(error) => {
+                self.errors.push(format!("Error reading {}: {}", source, error));
+                return;
+            }
+        };
+
+        for (index, value) in values.into_iter().enumerate() {
+            let result = serde_json::from_value::<JsonEntry>(value)
+                .map_err(|error| error.to_string())
+                .and_then(parse_entry);
+
+            match result {
+                Ok((crc, entry)) => {
+                    self.entries.insert(crc, entry);
+                },
+                Err(error) => self.errors.push(format!("Skipped entry {} in {}: {}", index + 1, source, error))
+            }
+        }
+    }
+}
+
+fn parse_entry(json: JsonEntry) -> Result<(u32, Entry), String> {
+    let crc = u32::from_str_radix(json.crc32.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("crc32 {} should be in hex", json.crc32))?;
+
+    if let Some(submapper) = json.submapper {
+        if submapper > MAX_SUBMAPPER {
+            return Err(format!("submapper {} should be from 0 to {}", submapper, MAX_SUBMAPPER));
+        }
+    }
+
+    let mirroring = match json.mirroring.as_deref() {
+        None => None,
+        Some("horizontal") => Some(Mirroring::Horizontal),
+        Some("vertical") => Some(Mirroring::Vertical),
+        Some("four_screen") => Some(Mirroring::FourScreen),
+        Some(other) => return Err(format!("mirroring {} should be horizontal, vertical or four_screen", other))
+    };
+
+    let region = match json.region {
+        None => None,
+        Some(ref name) => Some(Region::from(name).ok_or_else(|| format!("region {} should be ntsc, pal or dendy", name))?)
+    };
+
+    let entry = Entry {
+        title: json.title,
+        mapper: json.mapper,
+        submapper: json.submapper,
+        mirroring,
+        battery: json.battery,
+        region,
+        prg_ram_size: json.prg_ram_size,
+        chr_ram_size: json.chr_ram_size
+    };
+
+    Ok((crc, entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The CRC-32 of 123456789 is CBF43926, so it stands in for a ROM
+    const ROM: &[u8] = b"123456789";
+
+    const EXAMPLE: &str = r#"[
+        {
+            "crc32": "CBF43926",
+            "title": "Game title",
+            "mapper": 4,
+            "submapper": 1,
+            "mirroring": "four_screen",
+            "battery": true,
+            "region": "pal",
+            "prg_ram_size": 8192,
+            "chr_ram_size": 8192
+        }
+    ]"#;
+
+    fn create_database(json: &str) -> Database {
+        let mut database = Database::new(None);
+        database.add_entries("test", json);
+        database
+    }
+
+    #[test]
+    fn reads_every_field() {
+        let database = create_database(EXAMPLE);
+        assert!(database.errors.is_empty());
+
+        let entry = database.find(ROM).unwrap();
+        assert_eq!(entry.title.as_deref(), Some("Game title"));
+        assert_eq!(entry.mapper, Some(4));
+        assert_eq!(entry.submapper, Some(1));
+        assert_eq!(entry.mirroring, Some(Mirroring::FourScreen));
+        assert_eq!(entry.battery, Some(true));
+        assert_eq!(entry.region, Some(Region::Pal));
+        assert_eq!(entry.prg_ram_size, Some(8192));
+        assert_eq!(entry.chr_ram_size, Some(8192));
+    }
+
+    #[test]
+    fn fields_are_optional() {
+        let database = create_database(r#"[{ "crc32": "0xcbf43926" }]"#);
+        let entry = database.find(ROM).unwrap();
+        assert_eq!(entry.mapper, None);
+        assert_eq!(entry.mirroring, None);
+        assert_eq!(entry.region, None);
+    }
+
+    #[test]
+    fn only_finds_matching_rom() {
+        let database = create_database(EXAMPLE);
+        assert!(database.find(b"12345678").is_none());
+        assert!(database.find(&[]).is_none());
+    }
+
+    #[test]
+    fn later_entries_take_priority() {
+        let mut database = create_database(EXAMPLE);
+        database.add_entries("user", r#"[{ "crc32": "CBF43926", "mapper": 1 }]"#);
+        assert_eq!(database.find(ROM).unwrap().mapper, Some(1));
+    }
+
+    #[test]
+    fn skips_bad_entries() {
+        let database = create_database(r#"[
+            { "crc32": "XYZ" },
+            { "crc32": "00000001", "mirroring": "diagonal" },
+            { "crc32": "00000002", "region": "secam" },
+            { "crc32": "00000003", "submapper": 16 },
+            { "mapper": 4 },
+            { "crc32": "CBF43926", "mapper": 4 }
+        ]"#);
+
+        assert_eq!(database.errors.len(), 5);
+        assert!(database.errors[0].starts_with("Skipped entry 1 in test"));
+        assert_eq!(database.find(ROM).unwrap().mapper, Some(4));
+    }
+
+    #[test]
+    fn reports_bad_json() {
+        let database = create_database("[{");
+        assert_eq!(database.errors.len(), 1);
+        assert!(database.find(ROM).is_none());
+    }
+
+    #[test]
+    fn reports_missing_file() {
+        let database = Database::new(Some("missing-database.json"));
+        assert_eq!(database.errors.len(), 1);
+        assert!(database.errors[0].starts_with("Cannot read missing-database.json"));
+    }
+}
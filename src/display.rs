@@ -2,6 +2,8 @@ use sdl2::Sdl;
 use sdl2::pixels::Color;
 use sdl2::render::{Canvas, Texture, TextureCreator};
 use sdl2::video::{Window, WindowContext};
+use crate::palette::Palette;
+use crate::ppu::frame::Frame;
 
 pub const PIXEL_SIZE: usize = 3;
 pub const SCREEN_WIDTH: usize = 256;
@@ -24,8 +26,20 @@ pub fn initialize_window(context: &Sdl) -> (Canvas<Window>, TextureCreator<Windo
     (canvas, texture_creator)
 }
 
-pub fn draw_frame(texture: &mut Texture, canvas: &mut Canvas<Window>, buffer: &[u8]) {
-    texture.update(None, buffer, SCREEN_WIDTH * BYTES_PER_COLOR * PIXEL_SIZE).expect("Error updating texture");
+/// The texture is the console's resolution, copying it to the canvas scales it up to the window
+pub fn draw_frame(texture: &mut Texture, canvas: &mut Canvas<Window>, frame: &Frame, palette: &Palette) {
+    texture.update(None, &to_rgb(frame, palette), SCREEN_WIDTH * BYTES_PER_COLOR).expect("Error updating texture");
     canvas.copy(texture, None, None).expect("Error copying to canvas");
     canvas.present();
-}
\ No newline at end of file
+}
+
+/// 3 bytes of RGB for each pixel of the frame
+pub fn to_rgb(frame: &Frame, palette: &Palette) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(SCREEN_WIDTH * SCREEN_HEIGHT * BYTES_PER_COLOR);
+    for pixel in frame.get_pixels() {
+        let (red, green, blue) = palette.get_color(*pixel);
+        pixels.extend_from_slice(&[red, green, blue]);
+    }
+
+    pixels
+}
@@ -6,57 +6,224 @@ extern crate serde_big_array;
 
 use sdl2::Sdl;
 use sdl2::event::Event;
-use sdl2::keyboard::{Keycode};
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::messagebox::*;
 use sdl2::pixels::PixelFormatEnum;
-use std::env;
+use sdl2::video::Window;
 use std::path::Path;
 use std::string::String;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 mod addresses;
+mod archive;
+mod arguments;
 mod audio;
 mod bus;
 mod cartridge;
+mod cheats;
 mod controller;
 mod cpu;
+mod crc32;
+mod database;
 mod display;
 mod instant;
 mod mappers;
 mod memory_sizes;
+mod movie;
 mod nes;
+mod nsf;
+mod palette;
+mod patches;
 mod ppu;
+mod ram_search;
+mod region;
+mod repl;
 mod save_state;
+mod screenshot;
+mod video;
 
 use audio::device::AudioDevice;
+use audio::ring_buffer::RingBuffer;
+use audio::wav::{Multitrack, SampleFormat};
+use ppu::fps_limiter::Speed;
+use video::animated_gif::GifRecorder;
+use video::y4m::Y4mRecorder;
+
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 fn main() {
-    let buffer = Arc::new(Mutex::new(Vec::<f32>::new()));
-    let sdl_buffer = Arc::clone(&buffer);
+    let arguments = arguments::Arguments::new();
+    let channels = if arguments.stereo { 2 } else { 1 };
+    let buffer = Arc::new(Mutex::new(RingBuffer::new(arguments.sample_rate, channels)));
+    if arguments.headless {
+        run_headless(&arguments, buffer);
+        return;
+    }
+
     let sdl_context = sdl2::init().expect("Error initializing sdl");
-    let audio_device = AudioDevice::new(&sdl_context, sdl_buffer);
-    run_game(&sdl_context, &audio_device, buffer);
+    run_game(&sdl_context, &arguments, buffer);
 }
 
-fn run_game(sdl_context: &Sdl, audio_device: &sdl2::audio::AudioDevice<AudioDevice>, buffer: Arc<Mutex<Vec<f32>>>) {
+fn run_game(sdl_context: &Sdl, arguments: &arguments::Arguments, buffer: Arc<Mutex<RingBuffer>>) {
     let (mut canvas, texture_creator) = display::initialize_window(sdl_context);
     let mut texture = texture_creator.create_texture_streaming(
         PixelFormatEnum::RGB24,
-        (display::SCREEN_WIDTH * display::PIXEL_SIZE) as u32,
-        (display::SCREEN_HEIGHT * display::PIXEL_SIZE) as u32
+        display::SCREEN_WIDTH as u32,
+        display::SCREEN_HEIGHT as u32
     ).expect("Error creating texture streaming");
 
     let mut event_pump = sdl_context.event_pump().expect("Error loading event pump");
     let mut audio_started = false;
-    let file_path = get_file(&mut event_pump);
-    let mut nes = get_nes(&file_path, buffer);
+    let mut file_path = get_file(arguments, &mut event_pump);
+    if archive::is_archive(&file_path) {
+        file_path = extract_rom(&file_path, canvas.window());
+    }
+
+    // The database is only read once, so any entries it had to skip are only reported once
+    let database = database::Database::new(arguments.database.as_deref());
+    if !database.errors.is_empty() {
+        show_simple_message_box(
+            MessageBoxFlag::WARNING,
+            "Database",
+            &database.errors.join("\n"),
+            canvas.window()).expect("Error showing simple message");
+    }
+
+    let mut player = match get_extension(&file_path).as_str() {
+        "nsf" | "nsfe" => Some(nsf::player::Player::new(&file_path, arguments.region)),
+        _ => None
+    };
+
+    let sdl_buffer = Arc::clone(&buffer);
+    let nes_buffer = Arc::clone(&buffer);
+    let mut nes = match player {
+        Some(ref p) => {
+            canvas.window_mut().set_title(&p.get_title()).expect("Error setting window title");
+            let sound_chips = p.get_unsupported_sound_chips();
+            if !sound_chips.is_empty() {
+                show_simple_message_box(
+                    MessageBoxFlag::WARNING,
+                    "Unsupported expansion audio",
+                    &format!("This NSF uses {} sound, which isn't emulated so those parts won't play", sound_chips.join(" and ")),
+                    canvas.window()).expect("Error showing simple message");
+            }
+
+            p.create_nes(&file_path, buffer)
+        },
+        None => get_nes(&file_path, arguments, &database, buffer)
+    };
+
+    let mut recording: Option<movie::Movie> = None;
+    let mut playing = arguments.movie.as_ref().map(|m| movie::Movie::load(m));
+    if let Some(ref m) = playing {
+        nes = start_movie(nes, m, Arc::clone(&nes_buffer), canvas.window());
+    }
+
+    let mut palettes = palette::get_presets(&arguments.ntsc);
+    let mut palette_index = get_palette_index(arguments, &mut palettes);
+    nes.palette = palettes[palette_index].clone();
+
+    let cartridge_file_path = match nes.bus().cartridge {
+        Some(ref c) => {
+            if let Some(ref title) = c.borrow().title {
+                canvas.window_mut().set_title(title).expect("Error setting window title");
+            }
+
+            Some(String::from(&c.borrow().file_path))
+        },
+        None => None
+    };
+
+    // Cheats aren't part of quick saves, so they're always loaded from the game's cheat file.
+    // Movies don't record which cheats were on, so they're kept off while one is playing or recording.
+    if let Some(ref f) = cartridge_file_path {
+        nes.bus().cheats = cheats::Cheats::new(f);
+        nes.bus().cheats.enabled = playing.is_none();
+        if !nes.bus().cheats.get_invalid_codes().is_empty() {
+            show_simple_message_box(
+                MessageBoxFlag::WARNING,
+                "Cheats",
+                &get_invalid_codes_message(&nes.bus().cheats),
+                canvas.window()).expect("Error showing simple message");
+        }
+    }
+
+    if let Some(region) = arguments.region {
+        nes.set_region(region);
+    }
+
+    // Movies always play back in the region they were recorded in
+    if let Some(ref m) = playing {
+        nes.set_region(m.region);
+    }
 
+    if let Some(ref f) = arguments.record_video {
+        match create_video_recorder(&nes, f, arguments) {
+            Ok(r) => nes.video_recorder = Some(r),
+            Err(error) => show_simple_message_box(
+                MessageBoxFlag::WARNING,
+                "Video",
+                &format!("Error recording video to {}: {}", f, error),
+                canvas.window()).expect("Error showing simple message")
+        }
+    }
+
+    if let Some(ref f) = arguments.record_gif {
+        match GifRecorder::new(f, nes.region, arguments.gif_skip) {
+            Ok(r) => nes.gif_recorder = Some(r),
+            Err(error) => show_simple_message_box(
+                MessageBoxFlag::WARNING,
+                "Video",
+                &format!("Error recording GIF to {}: {}", f, error),
+                canvas.window()).expect("Error showing simple message")
+        }
+    }
+
+    let mut repl = if arguments.repl { Some(repl::Repl::new()) } else { None };
+
+    let mut audio_device = AudioDevice::new(sdl_context, sdl_buffer);
+
+    let mut normal_speed = Speed::default();
+    let mut paused = false;
+    let mut frame_advance = false;
+    let mut needs_input = true;
     'running: loop {
-        let frame_complete = nes.clock(&mut texture, &mut canvas, &event_pump);
+        // While paused the window still has to respond, so events are polled as if a frame had finished
+        let running = !paused || frame_advance;
+        if running && needs_input {
+            needs_input = false;
+            let was_playing = playing.is_some();
+            update_input(&mut nes, &event_pump, &mut playing, &mut recording);
+            if was_playing && playing.is_none() {
+                show_simple_message_box(
+                    MessageBoxFlag::INFORMATION,
+                    "Movie",
+                    "Movie has finished",
+                    canvas.window()).expect("Error showing simple message");
+            }
+        }
+
+        let frame_complete = if running {
+            nes.clock()
+        } else {
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+            true
+        };
+
         if frame_complete {
-            if !audio_started {
-                audio_started = true;
-                audio_device.resume();
+            if running {
+                nes.draw(&mut texture, &mut canvas);
+                frame_advance = false;
+                needs_input = true;
+                if !audio_started {
+                    audio_started = true;
+                    audio_device.resume();
+                }
+
+                if let Some(ref mut r) = repl {
+                    r.update(nes.bus());
+                }
             }
 
             for event in event_pump.poll_iter() {
@@ -68,6 +235,170 @@ fn run_game(sdl_context: &Sdl, audio_device: &sdl2::audio::AudioDevice<AudioDevi
 
                         break 'running
                     },
+                    Event::KeyDown { keycode: Some(Keycode::F2), .. } => {
+                        let screenshot_path = nes.screenshot().save(&file_path);
+                        show_simple_message_box(
+                            MessageBoxFlag::INFORMATION,
+                            "Screenshot",
+                            &format!("Screenshot saved to {}", screenshot_path),
+                            canvas.window()).expect("Error showing simple message");
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F3), .. } => {
+                        if let Some(ref c) = nes.bus().cartridge {
+                            c.borrow_mut().insert_or_eject_disk();
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F4), .. } => {
+                        if let Some(ref c) = nes.bus().cartridge {
+                            c.borrow_mut().switch_disk_side();
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Left), .. } => {
+                        if let Some(ref mut p) = player {
+                            p.previous_song(&mut nes);
+                            canvas.window_mut().set_title(&p.get_title()).expect("Error setting window title");
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Right), .. } => {
+                        if let Some(ref mut p) = player {
+                            p.next_song(&mut nes);
+                            canvas.window_mut().set_title(&p.get_title()).expect("Error setting window title");
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F8), keymod, .. } => {
+                        if let (Some(ref f), None) = (&cartridge_file_path, &player) {
+                            match recording.take() {
+                                Some(m) => {
+                                    save_movie(&m, f);
+                                    show_simple_message_box(
+                                        MessageBoxFlag::INFORMATION,
+                                        "Movie",
+                                        "Movie has been saved",
+                                        canvas.window()).expect("Error showing simple message");
+                                },
+                                None => {
+                                    // Shift records from power on, otherwise the movie starts from a save state of right now
+                                    let save_state = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                        nes = get_nes(f, arguments, &database, Arc::clone(&nes_buffer));
+                                        nes.palette = palettes[palette_index].clone();
+                                        nes.bus().cheats = cheats::Cheats::new(f);
+                                        if let Some(region) = arguments.region {
+                                            nes.set_region(region);
+                                        }
+
+                                        None
+                                    } else {
+                                        Some(save_state::save(&mut nes))
+                                    };
+
+                                    let rom_md5 = get_rom_md5(&mut nes);
+                                    nes.bus().cheats.enabled = false;
+                                    playing = None;
+                                    recording = Some(movie::Movie::new(nes.region, rom_md5, save_state));
+                                }
+                            }
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F5), keymod, .. } => {
+                        let (flag, message) = {
+                            let mut device = audio_device.lock();
+                            if device.is_recording() {
+                                device.stop_recording();
+                                nes.apu().multitrack = None;
+                                (MessageBoxFlag::INFORMATION, String::from("Audio has been saved"))
+                            } else {
+                                // Shift also dumps each channel on its own, straight from the APU
+                                let format = if arguments.float_wav { SampleFormat::Float } else { SampleFormat::Pcm16 };
+                                let wav_path = mappers::battery_backed_ram::get_save_data_path(&file_path, ".wav");
+                                let result = device.start_recording(&wav_path, format).and_then(|_| {
+                                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                        let input_rate = (nes.region.get_cpu_frequency() as f64) / 2.0;
+                                        nes.apu().multitrack = Some(Multitrack::new(&wav_path, input_rate, arguments.sample_rate as u32, format)?);
+                                    }
+
+                                    Ok(())
+                                });
+
+                                match result {
+                                    Ok(()) => (MessageBoxFlag::INFORMATION, String::from("Recording audio")),
+                                    Err(error) => {
+                                        device.stop_recording();
+                                        (MessageBoxFlag::WARNING, format!("Error recording audio to {}: {}", wav_path, error))
+                                    }
+                                }
+                            }
+                        };
+
+                        show_simple_message_box(
+                            flag,
+                            "Audio",
+                            &message,
+                            canvas.window()).expect("Error showing simple message");
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F6), keymod, .. } => {
+                        // Shift records a GIF, otherwise it's a Y4M video with its sound
+                        let (flag, message) = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            if nes.gif_recorder.take().is_some() {
+                                (MessageBoxFlag::INFORMATION, String::from("GIF has been saved"))
+                            } else {
+                                let gif_path = mappers::battery_backed_ram::get_save_data_path(&file_path, ".gif");
+                                match GifRecorder::new(&gif_path, nes.region, arguments.gif_skip) {
+                                    Ok(r) => {
+                                        nes.gif_recorder = Some(r);
+                                        (MessageBoxFlag::INFORMATION, String::from("Recording GIF"))
+                                    },
+                                    Err(error) => (MessageBoxFlag::WARNING, format!("Error recording GIF to {}: {}", gif_path, error))
+                                }
+                            }
+                        } else if nes.video_recorder.take().is_some() {
+                            (MessageBoxFlag::INFORMATION, String::from("Video has been saved"))
+                        } else {
+                            let video_path = mappers::battery_backed_ram::get_save_data_path(&file_path, ".y4m");
+                            match create_video_recorder(&nes, &video_path, arguments) {
+                                Ok(r) => {
+                                    nes.video_recorder = Some(r);
+                                    (MessageBoxFlag::INFORMATION, String::from("Recording video"))
+                                },
+                                Err(error) => (MessageBoxFlag::WARNING, format!("Error recording video to {}: {}", video_path, error))
+                            }
+                        };
+
+                        show_simple_message_box(
+                            flag,
+                            "Video",
+                            &message,
+                            canvas.window()).expect("Error showing simple message");
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F11), .. } => {
+                        palette_index = (palette_index + 1) % palettes.len();
+                        nes.palette = palettes[palette_index].clone();
+                        show_simple_message_box(
+                            MessageBoxFlag::INFORMATION,
+                            "Palette",
+                            &nes.palette.name,
+                            canvas.window()).expect("Error showing simple message");
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::F9), .. } => {
+                        let message = if recording.is_some() || playing.is_some() {
+                            String::from("Cheats can't be turned on while a movie is recording or playing")
+                        } else {
+                            nes.bus().cheats.toggle();
+                            let cheats = &nes.bus().cheats;
+                            if !cheats.enabled {
+                                String::from("Cheats are off")
+                            } else if cheats.get_invalid_codes().is_empty() {
+                                String::from("Cheats are on")
+                            } else {
+                                format!("Cheats are on\n\n{}", get_invalid_codes_message(cheats))
+                            }
+                        };
+
+                        show_simple_message_box(
+                            MessageBoxFlag::INFORMATION,
+                            "Cheats",
+                            &message,
+                            canvas.window()).expect("Error showing simple message");
+                    },
                     Event::KeyDown { keycode: Some(Keycode::F7), .. } => {
                         let file_path: Option<String> = match nes.bus().cartridge {
                             Some(ref c) => Some(String::from(&c.borrow().file_path)),
@@ -83,6 +414,49 @@ fn run_game(sdl_context: &Sdl, audio_device: &sdl2::audio::AudioDevice<AudioDevi
                                 canvas.window()).expect("Error showing simple message");
                         }
                     },
+                    Event::KeyDown { keycode: Some(keycode), keymod, .. } if get_channel(keycode).is_some() => {
+                        // Shift solos the channel, Ctrl turns it down a step and on its own the key mutes it
+                        let channel = get_channel(keycode).unwrap();
+                        let mixer = &mut nes.apu().mixer;
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            mixer.toggle_solo(channel);
+                        } else if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                            mixer.step_volume(channel);
+                        } else {
+                            mixer.toggle_mute(channel);
+                        }
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Num0), .. } if nes.apu().mixer.stereo => {
+                        let preset = nes.apu().mixer.next_pan_preset();
+                        show_simple_message_box(
+                            MessageBoxFlag::INFORMATION,
+                            "Stereo",
+                            preset,
+                            canvas.window()).expect("Error showing simple message");
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::P), .. } => {
+                        paused = !paused;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Backslash), .. } => {
+                        // Frame advance pauses the game first if it's running
+                        frame_advance = paused;
+                        paused = true;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Tab), repeat: false, .. } => {
+                        normal_speed = nes.speed;
+                        nes.speed = Speed::Turbo;
+                    },
+                    Event::KeyUp { keycode: Some(Keycode::Tab), .. } => {
+                        nes.speed = normal_speed;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Minus), .. } => {
+                        nes.speed = nes.speed.step(false);
+                        normal_speed = nes.speed;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Equals), .. } => {
+                        nes.speed = nes.speed.step(true);
+                        normal_speed = nes.speed;
+                    },
                     _ => {}
                 }
             }
@@ -90,10 +464,208 @@ fn run_game(sdl_context: &Sdl, audio_device: &sdl2::audio::AudioDevice<AudioDevi
     }
 }
 
-fn get_file(event_pump: &mut sdl2::EventPump) -> String {
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        args[1].to_owned()
+/// Runs the game with only the REPL, nothing is drawn or played so it works without a display.
+/// Input comes from the movie if there is one, and it stops once stdin closes and every command has run.
+fn run_headless(arguments: &arguments::Arguments, buffer: Arc<Mutex<RingBuffer>>) {
+    let mut file_path = arguments.file_path.clone().expect("--headless needs a file");
+    if archive::is_archive(&file_path) {
+        let roms = archive::list_roms(&file_path);
+        if roms.len() != 1 {
+            panic!("--headless needs an archive with exactly one .nes, .fds, .nsf or .nsfe file");
+        }
+
+        file_path = archive::extract(&file_path, &roms[0]);
+    }
+
+    let database = database::Database::new(arguments.database.as_deref());
+    for error in &database.errors {
+        eprintln!("{}", error);
+    }
+
+    let movie_buffer = Arc::clone(&buffer);
+    let mut nes = match get_extension(&file_path).as_str() {
+        "nsf" | "nsfe" => {
+            let player = nsf::player::Player::new(&file_path, arguments.region);
+            player.create_nes(&file_path, buffer)
+        },
+        _ => get_nes(&file_path, arguments, &database, buffer)
+    };
+
+    let mut playing = arguments.movie.as_ref().map(|m| movie::Movie::load(m));
+    if let Some(ref m) = playing {
+        if let Some(ref state) = m.save_state {
+            nes = save_state::load(state, movie_buffer);
+        }
+
+        if m.rom_md5 != [0; 16] && get_rom_md5(&mut nes) != m.rom_md5 {
+            eprintln!("This movie was recorded with a different ROM and might not play back correctly");
+        }
+    }
+
+    nes.palette = palette::get_presets(&arguments.ntsc)[0].clone();
+    if let Some(ref c) = nes.bus().cartridge {
+        let file_path = String::from(&c.borrow().file_path);
+        nes.bus().cheats = cheats::Cheats::new(&file_path);
+        nes.bus().cheats.enabled = playing.is_none();
+    }
+
+    if let Some(region) = arguments.region {
+        nes.set_region(region);
+    }
+
+    if let Some(ref m) = playing {
+        nes.set_region(m.region);
+    }
+
+    if let Some(ref f) = arguments.record_video {
+        match create_video_recorder(&nes, f, arguments) {
+            Ok(r) => nes.video_recorder = Some(r),
+            Err(error) => eprintln!("Error recording video to {}: {}", f, error)
+        }
+    }
+
+    if let Some(ref f) = arguments.record_gif {
+        match GifRecorder::new(f, nes.region, arguments.gif_skip) {
+            Ok(r) => nes.gif_recorder = Some(r),
+            Err(error) => eprintln!("Error recording GIF to {}: {}", f, error)
+        }
+    }
+
+    let mut repl = repl::Repl::new();
+    loop {
+        let mut frame = movie::Frame::default();
+        if let Some(ref mut m) = playing {
+            match m.next_frame() {
+                Some(f) => frame = f,
+                None => {
+                    eprintln!("Movie has finished");
+                    playing = None;
+                }
+            }
+        }
+
+        if frame.reset {
+            nes.reset();
+        }
+
+        nes.set_input(frame.get_input());
+        while !nes.clock() {}
+
+        repl.update(nes.bus());
+        if repl.is_finished() {
+            if let Some(ref c) = nes.bus().cartridge {
+                c.borrow_mut().save_data();
+            }
+
+            break;
+        }
+    }
+}
+
+/// Keys 1 to 6 control the sound channels
+fn get_channel(keycode: Keycode) -> Option<audio::mixer::Channel> {
+    let index = match keycode {
+        Keycode::Num1 => 0,
+        Keycode::Num2 => 1,
+        Keycode::Num3 => 2,
+        Keycode::Num4 => 3,
+        Keycode::Num5 => 4,
+        Keycode::Num6 => 5,
+        _ => return None
+    };
+
+    Some(audio::mixer::CHANNELS[index])
+}
+
+/// Input is picked once before each frame, from the movie when one is playing and from the keyboard otherwise
+fn update_input(nes: &mut nes::Nes, event_pump: &sdl2::EventPump, playing: &mut Option<movie::Movie>, recording: &mut Option<movie::Movie>) {
+    let mut frame = movie::Frame {
+        controllers: [controller::read_keyboard(event_pump).get(), 0],
+        reset: false
+    };
+
+    if let Some(ref mut m) = playing {
+        match m.next_frame() {
+            Some(f) => frame = f,
+            None => *playing = None
+        }
+    }
+
+    if let Some(ref mut m) = recording {
+        m.record(frame);
+    }
+
+    if frame.reset {
+        nes.reset();
+    }
+
+    nes.set_input(frame.get_input());
+}
+
+/// Puts the NES in the state the movie starts from
+fn start_movie(nes: nes::Nes, movie: &movie::Movie, buffer: Arc<Mutex<RingBuffer>>, window: &Window) -> nes::Nes {
+    let mut nes = match movie.save_state {
+        Some(ref state) => save_state::load(state, buffer),
+        None => nes
+    };
+
+    if movie.rom_md5 != [0; 16] && get_rom_md5(&mut nes) != movie.rom_md5 {
+        show_simple_message_box(
+            MessageBoxFlag::WARNING,
+            "Movie",
+            "This movie was recorded with a different ROM and might not play back correctly",
+            window).expect("Error showing simple message");
+    }
+
+    nes
+}
+
+/// Movies are saved next to the game, and ones recorded from power on are also saved as FCEUX movies
+fn save_movie(movie: &movie::Movie, file_path: &str) {
+    movie.save(&mappers::battery_backed_ram::get_save_data_path(file_path, ".movie"), file_path);
+    if movie.save_state.is_none() {
+        movie.save(&mappers::battery_backed_ram::get_save_data_path(file_path, ".fm2"), file_path);
+    }
+}
+
+fn create_video_recorder(nes: &nes::Nes, file_path: &str, arguments: &arguments::Arguments) -> std::io::Result<Y4mRecorder> {
+    let channels = if arguments.stereo { 2 } else { 1 };
+    Y4mRecorder::new(file_path, nes.region, arguments.sample_rate as u32, channels)
+}
+
+/// --palette picks a built in palette by name, or loads a .pal file and adds it to the ones F11 cycles through
+fn get_palette_index(arguments: &arguments::Arguments, palettes: &mut Vec<palette::Palette>) -> usize {
+    let name = match arguments.palette {
+        Some(ref name) => name,
+        None => return 0
+    };
+
+    if name.to_lowercase().ends_with(".pal") {
+        palettes.push(palette::Palette::load(name));
+        return palettes.len() - 1;
+    }
+
+    // Spaces are optional so names like Nestopia YUV don't need quoting
+    let name = name.replace(' ', "");
+    palettes.iter()
+        .position(|p| p.name.replace(' ', "").eq_ignore_ascii_case(&name))
+        .unwrap_or_else(|| panic!("Unknown palette {}, it should be 2C02, NTSC, FCEUX, NestopiaYUV, Smooth, PVM or a .pal file", name))
+}
+
+fn get_invalid_codes_message(cheats: &cheats::Cheats) -> String {
+    format!("These codes in the cheat file aren't valid and were skipped: {}", cheats.get_invalid_codes().join(", "))
+}
+
+fn get_rom_md5(nes: &mut nes::Nes) -> [u8; 16] {
+    match nes.bus().cartridge {
+        Some(ref c) => c.borrow().get_rom_md5(),
+        None => [0; 16]
+    }
+}
+
+fn get_file(arguments: &arguments::Arguments, event_pump: &mut sdl2::EventPump) -> String {
+    if let Some(ref file_path) = arguments.file_path {
+        file_path.to_owned()
     } else {
         loop {
             for event in event_pump.poll_iter() {
@@ -105,15 +677,51 @@ fn get_file(event_pump: &mut sdl2::EventPump) -> String {
     }
 }
 
-fn get_nes(file_path: &str, buffer: Arc<Mutex<Vec<f32>>>) -> nes::Nes {
-    let path = Path::new(file_path);
-    let os_extension = path.extension().expect("Error getting file extension");
-    let extension = os_extension.to_str().expect("Error converting to string");
-    
-    match extension {
+/// Extracts the ROM from an archive, asking which one to play when there's more than one
+fn extract_rom(file_path: &str, window: &Window) -> String {
+    let roms = archive::list_roms(file_path);
+    let rom_name = match roms.len() {
+        0 => panic!("No .nes, .fds, .nsf or .nsfe file in archive"),
+        1 => &roms[0],
+        _ => {
+            let buttons: Vec<ButtonData> = roms.iter().enumerate().map(|(index, rom)| ButtonData {
+                flags: MessageBoxButtonFlag::NOTHING,
+                button_id: index as i32,
+                text: rom
+            }).collect();
+
+            match show_message_box(MessageBoxFlag::INFORMATION, &buttons, "Choose a ROM", "This archive has more than one ROM, which one should be played?", window, None)
+                .expect("Error showing ROM chooser") {
+                ClickedButton::CustomButton(button) => &roms[button.button_id as usize],
+                ClickedButton::CloseButton => std::process::exit(0)
+            }
+        }
+    };
+
+    archive::extract(file_path, rom_name)
+}
+
+fn get_nes(file_path: &str, arguments: &arguments::Arguments, database: &database::Database, buffer: Arc<Mutex<RingBuffer>>) -> nes::Nes {
+    match get_extension(file_path).as_str() {
         "nes" => {
             let mut nes = nes::Nes::new(buffer);
-            let cartridge = cartridge::Cartridge::new(file_path);
+            let mut patch_paths = patches::find_patches(file_path);
+            for patch_path in &arguments.patches {
+                if !patch_paths.contains(patch_path) {
+                    patch_paths.push(patch_path.to_owned());
+                }
+            }
+
+            let cartridge = cartridge::Cartridge::new(file_path, database, &patch_paths);
+            let region = cartridge.region;
+            nes.bus().load_cartridge(cartridge);
+            nes.set_region(region);
+            nes.reset();
+            nes
+        },
+        "fds" => {
+            let mut nes = nes::Nes::new(buffer);
+            let cartridge = cartridge::Cartridge::from_disk_system(file_path);
             nes.bus().load_cartridge(cartridge);
             nes.reset();
             nes
@@ -123,4 +731,10 @@ fn get_nes(file_path: &str, buffer: Arc<Mutex<Vec<f32>>>) -> nes::Nes {
         },
         _ => panic!("Unrecognized file extension")
     }
+}
+
+fn get_extension(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let os_extension = path.extension().expect("Error getting file extension");
+    os_extension.to_str().expect("Error converting to string").to_lowercase()
 }
\ No newline at end of file
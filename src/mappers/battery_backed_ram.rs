@@ -3,12 +3,12 @@ use std::path::Path;
 use super::mapper::Mapper;
 
 pub fn save_battery_backed_ram(file_path: &str, data: &[u8]) {
-    let save_data = get_save_data_path(file_path);
+    let save_data = get_save_data_path(file_path, ".sav");
     fs::write(save_data, data).expect("Error writing save data to path");
 }
 
 pub fn load_battery_backed_ram(mapper: &mut Box<dyn Mapper>, file_path: &str) {
-    let save_data = get_save_data_path(file_path);
+    let save_data = get_save_data_path(file_path, ".sav");
     let save_data_path = Path::new(&save_data);
     if !save_data_path.exists() {
         return;
@@ -18,13 +18,13 @@ pub fn load_battery_backed_ram(mapper: &mut Box<dyn Mapper>, file_path: &str) {
     mapper.load_battery_backed_ram(bytes);
 }
 
-fn get_save_data_path(file_path: &str) -> String {
+pub fn get_save_data_path(file_path: &str, extension: &str) -> String {
     let path = Path::new(file_path);
     let mut save_data = path.parent().expect("ROM must be in a directory").to_str().expect("Error converting directory to string").to_owned();
     let file_name = path.file_name().expect("Error getting name of file").to_str().expect("Error file name to string");
     save_data.push_str(r"\");
     save_data.push_str(file_name);
-    save_data.push_str(".sav");
+    save_data.push_str(extension);
 
     save_data
 }
\ No newline at end of file
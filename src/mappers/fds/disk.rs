@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::Path;
+use crate::mappers::battery_backed_ram;
+use crate::patches::ips;
+
+pub const SIDE_SIZE: usize = 65_500;
+
+const HEADER: &[u8] = b"FDS\x1A";
+const HEADER_SIZE: usize = 16;
+const DISK_INFO_BLOCK: u8 = 1;
+const FILE_AMOUNT_BLOCK: u8 = 2;
+const FILE_HEADER_BLOCK: u8 = 3;
+const FILE_DATA_BLOCK: u8 = 4;
+const DISK_INFO_BLOCK_SIZE: usize = 56;
+const FILE_AMOUNT_BLOCK_SIZE: usize = 2;
+const FILE_HEADER_BLOCK_SIZE: usize = 16;
+const BLOCK_START_MARK: u8 = 0x80;
+const LEADING_GAP_SIZE: usize = 28_300 / 8;
+const BLOCK_GAP_SIZE: usize = 976 / 8;
+const CRC_SIZE: usize = 2;
+
+/// The drive never reports CRC errors so every block gets the same made up CRC
+const FAKE_CRC: [u8; CRC_SIZE] = [0x4D, 0x62];
+
+/// Room left at the end of each side for files the game writes
+const RAW_SIDE_SIZE: usize = LEADING_GAP_SIZE + SIDE_SIZE + 4096;
+
+/// Reads the disk image, along with any changes the game made to it last time
+pub fn load_image(file_path: &str) -> Vec<u8> {
+    let mut bytes = fs::read(file_path).expect("Cannot find file");
+    let diff_path = battery_backed_ram::get_save_data_path(file_path, ".ips");
+    if Path::new(&diff_path).exists() {
+        let diff = fs::read(&diff_path).expect("Error reading disk changes");
+        ips::apply(&mut bytes, &diff);
+    }
+
+    bytes
+}
+
+/// Saves the changes made to the disk as an IPS patch of the original image, so the image itself is never touched
+pub fn save_image(file_path: &str, sides: &[Vec<u8>]) {
+    let original = fs::read(file_path).expect("Cannot find file");
+    let mut modified = if original.starts_with(HEADER) { original[..HEADER_SIZE].to_vec() } else { vec![] };
+    for side in sides {
+        modified.extend(to_fds_side(side));
+    }
+
+    let diff = ips::create(&original, &modified);
+    let diff_path = battery_backed_ram::get_save_data_path(file_path, ".ips");
+    if ips::is_empty(&diff) && !Path::new(&diff_path).exists() {
+        return;
+    }
+
+    fs::write(diff_path, diff).expect("Error writing disk changes");
+}
+
+/// .fds images only hold the contents of each block, the drive needs the gaps and the start mark and CRC around each block.
+/// The fwNES header is optional so the number of sides comes from the size of the image.
+pub fn to_raw_sides(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let data = if bytes.starts_with(HEADER) { &bytes[HEADER_SIZE..] } else { bytes };
+    data.chunks(SIDE_SIZE)
+        .filter(|side| side.len() == SIDE_SIZE)
+        .map(to_raw_side)
+        .collect()
+}
+
+fn to_raw_side(side: &[u8]) -> Vec<u8> {
+    let mut raw = vec![0; LEADING_GAP_SIZE];
+    let mut file_size = 0;
+    let mut index = 0;
+    while let Some(block_size) = get_block_size(side, index, &mut file_size) {
+        raw.push(BLOCK_START_MARK);
+        raw.extend_from_slice(&side[index..(index + block_size)]);
+        raw.extend_from_slice(&FAKE_CRC);
+        raw.extend(vec![0; BLOCK_GAP_SIZE]);
+        index += block_size;
+    }
+
+    raw.resize(RAW_SIDE_SIZE.max(raw.len()), 0);
+    raw
+}
+
+/// Strips the gaps, start marks and CRCs back out of a side the drive has been using
+fn to_fds_side(raw: &[u8]) -> Vec<u8> {
+    let mut side = Vec::with_capacity(SIDE_SIZE);
+    let mut file_size = 0;
+    let mut index = 0;
+    loop {
+        while index < raw.len() && raw[index] != BLOCK_START_MARK {
+            index += 1;
+        }
+
+        index += 1;
+        let block_size = match get_block_size(raw, index, &mut file_size) {
+            Some(size) => size,
+            None => break
+        };
+
+        side.extend_from_slice(&raw[index..(index + block_size)]);
+        index += block_size + CRC_SIZE;
+    }
+
+    side.truncate(SIDE_SIZE);
+    side.resize(SIDE_SIZE, 0);
+    side
+}
+
+/// The size of a file data block comes from the file header block before it, so that's kept track of in file_size
+fn get_block_size(data: &[u8], index: usize, file_size: &mut usize) -> Option<usize> {
+    let size = match data.get(index) {
+        Some(&DISK_INFO_BLOCK) => DISK_INFO_BLOCK_SIZE,
+        Some(&FILE_AMOUNT_BLOCK) => FILE_AMOUNT_BLOCK_SIZE,
+        Some(&FILE_HEADER_BLOCK) => FILE_HEADER_BLOCK_SIZE,
+        Some(&FILE_DATA_BLOCK) => 1 + *file_size,
+        _ => return None
+    };
+
+    if index + size > data.len() {
+        return None;
+    }
+
+    if data[index] == FILE_HEADER_BLOCK {
+        *file_size = (data[index + 13] as usize) | ((data[index + 14] as usize) << 8);
+    }
+
+    Some(size)
+}
@@ -0,0 +1,269 @@
+use serde::{Serialize, Deserialize};
+
+/// Roughly how long it takes the motor to get the head back to the start of the disk
+const REWIND_DELAY: u32 = 50_000;
+
+/// A byte passes under the head about every 150 CPU cycles
+const BYTE_DELAY: u32 = 150;
+
+/// Long enough for the game to notice the disk was taken out before the next one goes in
+const INSERT_DELAY: u32 = 1_800_000;
+
+const CRC_POLYNOMIAL: u16 = 0x8408;
+
+/// The disk drive transfers one byte at a time and raises an IRQ after each one if $4025 bit 7 is set.
+/// $4024: Data to write
+/// $4025: Motor on(0), reset transfer(1), read mode(2), horizontal mirroring(3), CRC control(4), transfer enabled(6), IRQ enabled(7)
+/// $4030: Timer IRQ occurred(0), byte transferred(1)
+/// $4031: Data read
+/// $4032: Disk missing(0), disk not ready(1), write protected(2)
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Drive {
+    pub horizontal_mirroring: bool,
+    pub irq: bool,
+    pub sides: Vec<Vec<u8>>,
+    crc: u16,
+    crc_control: bool,
+    delay: u32,
+    end_of_head: bool,
+    gap_ended: bool,
+    insert_delay: u32,
+    irq_enabled: bool,
+    motor_on: bool,
+    pending_side: Option<usize>,
+    position: usize,
+    previous_crc_control: bool,
+    read_data: u8,
+    read_mode: bool,
+    reset_transfer: bool,
+    scanning: bool,
+    side: Option<usize>,
+    transfer_complete: bool,
+    transfer_enabled: bool,
+    write_data: u8
+}
+
+impl Drive {
+    pub fn new(sides: Vec<Vec<u8>>) -> Self {
+        Drive {
+            horizontal_mirroring: false,
+            irq: false,
+            side: if sides.is_empty() { None } else { Some(0) },
+            sides,
+            crc: 0,
+            crc_control: false,
+            delay: 0,
+            end_of_head: true,
+            gap_ended: false,
+            insert_delay: 0,
+            irq_enabled: false,
+            motor_on: false,
+            pending_side: None,
+            position: 0,
+            previous_crc_control: false,
+            read_data: 0,
+            read_mode: true,
+            reset_transfer: false,
+            scanning: false,
+            transfer_complete: false,
+            transfer_enabled: false,
+            write_data: 0
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if self.insert_delay > 0 {
+            self.insert_delay -= 1;
+            if self.insert_delay == 0 {
+                self.side = self.pending_side.take();
+            }
+        }
+
+        let side = match self.side {
+            Some(side) if self.motor_on => side,
+            _ => {
+                self.end_of_head = true;
+                self.scanning = false;
+                return;
+            }
+        };
+
+        if self.reset_transfer && !self.scanning {
+            return;
+        }
+
+        if self.end_of_head {
+            self.delay = REWIND_DELAY;
+            self.end_of_head = false;
+            self.position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+        if self.read_mode {
+            self.read_byte(side);
+        } else {
+            self.write_byte(side);
+        }
+
+        self.previous_crc_control = self.crc_control;
+        self.position += 1;
+        if self.position >= self.sides[side].len() {
+            self.motor_on = false;
+            self.end_of_head = true;
+        } else {
+            self.delay = BYTE_DELAY;
+        }
+    }
+
+    pub fn write_data(&mut self, data: u8) {
+        self.write_data = data;
+        self.transfer_complete = false;
+        self.irq = false;
+    }
+
+    pub fn write_control(&mut self, data: u8) {
+        self.motor_on = data & 0b0000_0001 != 0;
+        self.reset_transfer = data & 0b0000_0010 != 0;
+        self.read_mode = data & 0b0000_0100 != 0;
+        self.horizontal_mirroring = data & 0b0000_1000 != 0;
+        self.crc_control = data & 0b0001_0000 != 0;
+        self.transfer_enabled = data & 0b0100_0000 != 0;
+        self.irq_enabled = data & 0b1000_0000 != 0;
+        self.irq = false;
+    }
+
+    /// Reading $4030 acknowledges the IRQ
+    pub fn read_status(&mut self) -> u8 {
+        let status = if self.transfer_complete { 0b10 } else { 0 };
+        self.transfer_complete = false;
+        self.irq = false;
+        status
+    }
+
+    pub fn read_data(&mut self) -> u8 {
+        self.transfer_complete = false;
+        self.irq = false;
+        self.read_data
+    }
+
+    pub fn read_drive_status(&self) -> u8 {
+        let mut status = 0x40;
+        if self.side.is_none() {
+            status |= 0b111;
+        }
+
+        if !self.scanning {
+            status |= 0b10;
+        }
+
+        status
+    }
+
+    /// Takes the disk out if there's one in, otherwise puts the first side back in
+    pub fn insert_or_eject(&mut self) {
+        if self.side.is_some() || self.pending_side.is_some() {
+            self.side = None;
+            self.pending_side = None;
+            self.insert_delay = 0;
+        } else if !self.sides.is_empty() {
+            self.side = Some(0);
+        }
+    }
+
+    /// Ejects the disk and puts the next side in after a delay, so the game sees it change
+    pub fn switch_side(&mut self) {
+        if self.sides.is_empty() {
+            return;
+        }
+
+        let next_side = match self.side.or(self.pending_side) {
+            Some(side) => (side + 1) % self.sides.len(),
+            None => 0
+        };
+
+        self.side = None;
+        self.pending_side = Some(next_side);
+        self.insert_delay = INSERT_DELAY;
+    }
+
+    fn read_byte(&mut self, side: usize) {
+        let data = self.sides[side][self.position];
+        let mut trigger_irq = self.irq_enabled;
+        if !self.previous_crc_control {
+            self.update_crc(data);
+        }
+
+        if !self.transfer_enabled {
+            self.gap_ended = false;
+            self.crc = 0;
+        } else if data != 0 && !self.gap_ended {
+            // The first non zero byte is the start mark, which isn't passed on to the CPU
+            self.gap_ended = true;
+            trigger_irq = false;
+        }
+
+        if self.gap_ended {
+            self.transfer_complete = true;
+            self.read_data = data;
+            if trigger_irq {
+                self.irq = true;
+            }
+        }
+    }
+
+    fn write_byte(&mut self, side: usize) {
+        if !self.crc_control {
+            self.transfer_complete = true;
+            if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+
+        let data = if !self.transfer_enabled {
+            0
+        } else if !self.crc_control {
+            self.update_crc(self.write_data);
+            self.write_data
+        } else {
+            if !self.previous_crc_control {
+                self.update_crc(0);
+                self.update_crc(0);
+            }
+
+            let data = self.crc as u8;
+            self.crc >>= 8;
+            data
+        };
+
+        // The write head sits a little behind the read head
+        if self.position >= 2 {
+            self.sides[side][self.position - 2] = data;
+        }
+
+        self.gap_ended = false;
+    }
+
+    fn update_crc(&mut self, data: u8) {
+        let mut bit = 1;
+        while bit != 0 {
+            let carry = self.crc & 1 != 0;
+            self.crc >>= 1;
+            if carry {
+                self.crc ^= CRC_POLYNOMIAL;
+            }
+
+            if data & bit != 0 {
+                self.crc ^= 0x8000;
+            }
+
+            bit <<= 1;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Serialize, Deserialize};
+
+/// A 16 bit counter that counts down every CPU cycle and triggers an IRQ when it reaches 0
+/// $4020: Reload value low
+/// $4021: Reload value high
+/// $4022: Repeat(0), enabled(1)
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct InterruptRequest {
+    pub active: bool,
+    counter: u16,
+    enabled: bool,
+    reload: u16,
+    repeat: bool
+}
+
+impl InterruptRequest {
+    pub fn new() -> Self {
+        InterruptRequest {
+            active: false,
+            counter: 0,
+            enabled: false,
+            reload: 0,
+            repeat: false
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.counter == 0 {
+            self.active = true;
+            self.counter = self.reload;
+            if !self.repeat {
+                self.enabled = false;
+            }
+        } else {
+            self.counter -= 1;
+        }
+    }
+
+    pub fn write_reload_low(&mut self, data: u8) {
+        self.reload = (self.reload & 0xFF00) | (data as u16);
+    }
+
+    pub fn write_reload_high(&mut self, data: u8) {
+        self.reload = ((data as u16) << 8) | (self.reload & 0x00FF);
+    }
+
+    /// Writing to $4022 acknowledges the IRQ and reloads the counter if it's being enabled
+    pub fn write_control(&mut self, data: u8, disk_registers_enabled: bool) {
+        self.repeat = data & 0b01 != 0;
+        self.enabled = data & 0b10 != 0 && disk_registers_enabled;
+        self.active = false;
+        if self.enabled {
+            self.counter = self.reload;
+        }
+    }
+
+    /// Turning off the disk registers with $4023 also stops the timer
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.active = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.active = false;
+        self.counter = 0;
+        self.enabled = false;
+        self.reload = 0;
+        self.repeat = false;
+    }
+}
@@ -0,0 +1,193 @@
+pub mod disk;
+pub mod drive;
+pub mod interrupt_request;
+pub mod sound;
+
+use serde::{Serialize, Deserialize};
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, FdsSaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::cartridge::mirror::Mirror;
+
+pub const RAM_SIZE: usize = 0x8000;
+
+const RAM_MIN_ADDRESS: u16 = 0x6000;
+const RAM_MAX_ADDRESS: u16 = 0xDFFF;
+const BIOS_MIN_ADDRESS: u16 = 0xE000;
+const BATTERY_STATUS: u8 = 0x80;
+
+/// The Famicom Disk System plugs the RAM adapter into the cartridge slot, so it's treated as a mapper.
+/// $4020-$4022: Timer IRQ
+/// $4023: Enable disk registers(0), enable sound registers(1)
+/// $4024-$4025, $4030-$4032: Disk drive
+/// $4026: External connector output
+/// $4033: External connector input, bit 7 is the battery
+/// $4040-$4092: Sound
+/// $6000-$DFFF: 32KB PRG RAM
+/// $E000-$FFFF: 8KB BIOS
+/// The CHR is 8KB of RAM and the disk image is kept in the mapper so writes to it can be saved.
+#[derive(Serialize, Deserialize)]
+pub struct Fds {
+    pub interrupt_request: interrupt_request::InterruptRequest,
+    pub drive: drive::Drive,
+    pub sound: sound::Sound,
+    disk_registers_enabled: bool,
+    external_port: u8,
+    sound_registers_enabled: bool
+}
+
+impl Fds {
+    pub fn new(sides: Vec<Vec<u8>>) -> Self {
+        Fds {
+            interrupt_request: interrupt_request::InterruptRequest::new(),
+            drive: drive::Drive::new(sides),
+            sound: sound::Sound::new(),
+            disk_registers_enabled: true,
+            external_port: 0,
+            sound_registers_enabled: true
+        }
+    }
+
+    pub fn from(data: &FdsSaveData) -> Self {
+        Fds {
+            interrupt_request: data.interrupt_request,
+            drive: data.drive.clone(),
+            sound: data.sound.clone(),
+            disk_registers_enabled: data.disk_registers_enabled,
+            external_port: data.external_port,
+            sound_registers_enabled: data.sound_registers_enabled
+        }
+    }
+}
+
+impl Mapper for Fds {
+    fn reset(&mut self) {
+        self.interrupt_request.reset();
+        self.sound.reset();
+        self.disk_registers_enabled = true;
+        self.sound_registers_enabled = true;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        0
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        0
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        if self.drive.horizontal_mirroring { Mirror::Horizontal } else { Mirror::Vertical }
+    }
+
+    fn irq_active(&self) -> bool {
+        self.interrupt_request.active || self.drive.irq
+    }
+
+    /// The IRQs are only acknowledged through $4022, $4024, $4025, $4030 and $4031
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        match address {
+            0x4030 if self.disk_registers_enabled => {
+                let timer = if self.interrupt_request.active { 0b1 } else { 0 };
+                self.interrupt_request.active = false;
+                MapperReadResult::from_mapper_ram(timer | self.drive.read_status())
+            },
+            0x4031 if self.disk_registers_enabled => MapperReadResult::from_mapper_ram(self.drive.read_data()),
+            0x4032 if self.disk_registers_enabled => MapperReadResult::from_mapper_ram(self.drive.read_drive_status()),
+            0x4033 if self.disk_registers_enabled => MapperReadResult::from_mapper_ram(BATTERY_STATUS & self.external_port),
+            0x4040..=0x407F if self.sound_registers_enabled => MapperReadResult::from_mapper_ram(self.sound.read_wave_table(address)),
+            0x4090 if self.sound_registers_enabled => MapperReadResult::from_mapper_ram(self.sound.read_volume_gain()),
+            0x4092 if self.sound_registers_enabled => MapperReadResult::from_mapper_ram(self.sound.read_mod_gain()),
+            RAM_MIN_ADDRESS..=RAM_MAX_ADDRESS => MapperReadResult::from_cart_ram((address - RAM_MIN_ADDRESS) as u32),
+            BIOS_MIN_ADDRESS..=CPU_MAX_ADDRESS => MapperReadResult::from_cart_ram((RAM_SIZE as u32) + ((address - BIOS_MIN_ADDRESS) as u32)),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        match address {
+            0x4020 if self.disk_registers_enabled => self.interrupt_request.write_reload_low(data),
+            0x4021 if self.disk_registers_enabled => self.interrupt_request.write_reload_high(data),
+            0x4022 => self.interrupt_request.write_control(data, self.disk_registers_enabled),
+            0x4023 => {
+                self.disk_registers_enabled = data & 0b01 != 0;
+                self.sound_registers_enabled = data & 0b10 != 0;
+                if !self.disk_registers_enabled {
+                    self.interrupt_request.disable();
+                }
+            },
+            0x4024 if self.disk_registers_enabled => self.drive.write_data(data),
+            0x4025 if self.disk_registers_enabled => self.drive.write_control(data),
+            0x4026 if self.disk_registers_enabled => self.external_port = data,
+            0x4040..=0x407F if self.sound_registers_enabled => self.sound.write_wave_table(address, data),
+            0x4080..=0x408A if self.sound_registers_enabled => self.sound.write(address, data),
+            RAM_MIN_ADDRESS..=RAM_MAX_ADDRESS => return MapperWriteResult::write_to_cart_ram((address - RAM_MIN_ADDRESS) as u32),
+            BIOS_MIN_ADDRESS..=CPU_MAX_ADDRESS => (),
+            _ => return MapperWriteResult::none()
+        }
+
+        MapperWriteResult::handled()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+
+    /// The disk is the battery backed RAM, changes are saved as a patch next to the image
+    fn save_battery_backed_ram(&self, file_path: &str) {
+        disk::save_image(file_path, &self.drive.sides);
+    }
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Fds(FdsSaveData {
+            interrupt_request: self.interrupt_request,
+            drive: self.drive.clone(),
+            sound: self.sound.clone(),
+            disk_registers_enabled: self.disk_registers_enabled,
+            external_port: self.external_port,
+            sound_registers_enabled: self.sound_registers_enabled
+        })
+    }
+
+    fn cpu_clock(&mut self) {
+        self.interrupt_request.clock();
+        if self.disk_registers_enabled {
+            self.drive.clock();
+        }
+
+        self.sound.clock();
+    }
+
+    fn get_audio_sample(&self) -> f32 {
+        if !self.sound_registers_enabled {
+            return 0.0;
+        }
+
+        self.sound.get_sample()
+    }
+
+    fn insert_or_eject_disk(&mut self) {
+        self.drive.insert_or_eject();
+    }
+
+    fn switch_disk_side(&mut self) {
+        self.drive.switch_side();
+    }
+}
@@ -0,0 +1,284 @@
+use serde::{Serialize, Deserialize};
+
+pub const WAVE_TABLE_SIZE: usize = 64;
+
+const DEFAULT_MASTER_SPEED: u8 = 0xE8;
+const ENVELOPE_GAIN_MAX: u8 = 32;
+const MOD_TABLE_SIZE: usize = 64;
+const MOD_RESET: i8 = 0x7F;
+const OUTPUT_SCALE: f32 = 160.0;
+
+/// How much each mod table entry changes the mod counter by, 4 resets it to 0
+const MOD_LOOKUP: [i8; 8] = [0, 1, 2, 4, MOD_RESET, -4, -2, -1];
+
+/// Master volume 2/2, 2/3, 2/4 and 2/5, out of 36
+const MASTER_VOLUMES: [u32; 4] = [36, 24, 17, 14];
+
+/// The FDS has a single wavetable channel with a 64 step, 6 bit wave and a second wavetable that bends its pitch.
+/// $4040-$407F: Wave table, only writable while $4089 bit 7 is set
+/// $4080: Volume envelope
+/// $4082: Wave frequency low
+/// $4083: Wave frequency high(3-0), halt both envelopes(6), halt wave(7)
+/// $4084: Mod envelope
+/// $4085: Mod counter
+/// $4086: Mod frequency low
+/// $4087: Mod frequency high(3-0), halt mod(7)
+/// $4088: Mod table, only writable while the mod is halted
+/// $4089: Master volume(1-0), wave write enable(7)
+/// $408A: Envelope speed
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Sound {
+    envelopes_halted: bool,
+    master_speed: u8,
+    master_volume: u8,
+    mod_accumulator: u32,
+    mod_counter: i8,
+    mod_envelope: Envelope,
+    mod_frequency: u16,
+    mod_halted: bool,
+    mod_position: usize,
+    mod_table: Vec<u8>,
+    volume_envelope: Envelope,
+    wave_accumulator: u32,
+    wave_frequency: u16,
+    wave_halted: bool,
+    wave_output: u8,
+    wave_table: Vec<u8>,
+    wave_write_enabled: bool
+}
+
+impl Sound {
+    pub fn new() -> Self {
+        Sound {
+            envelopes_halted: false,
+            master_speed: DEFAULT_MASTER_SPEED,
+            master_volume: 0,
+            mod_accumulator: 0,
+            mod_counter: 0,
+            mod_envelope: Envelope::new(),
+            mod_frequency: 0,
+            mod_halted: true,
+            mod_position: 0,
+            mod_table: vec![0; MOD_TABLE_SIZE],
+            volume_envelope: Envelope::new(),
+            wave_accumulator: 0,
+            wave_frequency: 0,
+            wave_halted: true,
+            wave_output: 0,
+            wave_table: vec![0; WAVE_TABLE_SIZE],
+            wave_write_enabled: false
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.envelopes_halted && !self.wave_halted && self.master_speed != 0 {
+            self.volume_envelope.clock(self.master_speed);
+            self.mod_envelope.clock(self.master_speed);
+        }
+
+        self.clock_mod();
+
+        if self.wave_halted {
+            return;
+        }
+
+        let pitch = self.get_pitch();
+        self.wave_accumulator = (self.wave_accumulator + pitch) & 0x3F_FFFF;
+
+        // The output only changes while the wave table can't be written to
+        if !self.wave_write_enabled {
+            let position = (self.wave_accumulator >> 16) as usize;
+            self.wave_output = self.wave_table[position];
+        }
+    }
+
+    pub fn get_sample(&self) -> f32 {
+        let gain = self.volume_envelope.gain.min(ENVELOPE_GAIN_MAX) as u32;
+        let output = (self.wave_output as u32) * gain * MASTER_VOLUMES[self.master_volume as usize];
+
+        // Scale the 6 bit wave back down to 0-63 before mixing it in with the APU
+        (output as f32) / (ENVELOPE_GAIN_MAX as f32 * 36.0) / OUTPUT_SCALE
+    }
+
+    /// $4040-$407F: Reads back the current output while the wave is playing
+    pub fn read_wave_table(&self, address: u16) -> u8 {
+        if self.wave_write_enabled {
+            self.wave_table[(address & 0x3F) as usize]
+        } else {
+            self.wave_output
+        }
+    }
+
+    pub fn write_wave_table(&mut self, address: u16, data: u8) {
+        if self.wave_write_enabled {
+            self.wave_table[(address & 0x3F) as usize] = data & 0x3F;
+        }
+    }
+
+    /// $4090: Current volume gain
+    pub fn read_volume_gain(&self) -> u8 {
+        self.volume_envelope.gain
+    }
+
+    /// $4092: Current mod gain
+    pub fn read_mod_gain(&self) -> u8 {
+        self.mod_envelope.gain
+    }
+
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            0x4080 => self.volume_envelope.write(data),
+            0x4082 => self.wave_frequency = (self.wave_frequency & 0x0F00) | (data as u16),
+            0x4083 => {
+                self.wave_frequency = (((data & 0x0F) as u16) << 8) | (self.wave_frequency & 0x00FF);
+                self.envelopes_halted = data & 0b0100_0000 != 0;
+                self.wave_halted = data & 0b1000_0000 != 0;
+                if self.wave_halted {
+                    self.wave_accumulator = 0;
+                }
+
+                if self.envelopes_halted {
+                    self.volume_envelope.reset_timer(self.master_speed);
+                    self.mod_envelope.reset_timer(self.master_speed);
+                }
+            },
+            0x4084 => self.mod_envelope.write(data),
+            0x4085 => self.mod_counter = Sound::to_mod_counter(data),
+            0x4086 => self.mod_frequency = (self.mod_frequency & 0x0F00) | (data as u16),
+            0x4087 => {
+                self.mod_frequency = (((data & 0x0F) as u16) << 8) | (self.mod_frequency & 0x00FF);
+                self.mod_halted = data & 0b1000_0000 != 0;
+                if self.mod_halted {
+                    self.mod_accumulator = 0;
+                }
+            },
+            // Each write fills two entries since the table only has 32 unique steps
+            0x4088 if self.mod_halted => {
+                self.mod_table[self.mod_position] = data & 0b111;
+                self.mod_table[(self.mod_position + 1) % MOD_TABLE_SIZE] = data & 0b111;
+                self.mod_position = (self.mod_position + 2) % MOD_TABLE_SIZE;
+            },
+            0x4089 => {
+                self.master_volume = data & 0b11;
+                self.wave_write_enabled = data & 0b1000_0000 != 0;
+            },
+            0x408A => self.master_speed = data,
+            _ => ()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Sound::new();
+    }
+
+    fn clock_mod(&mut self) {
+        if self.mod_halted || self.mod_frequency == 0 {
+            return;
+        }
+
+        self.mod_accumulator += self.mod_frequency as u32;
+        if self.mod_accumulator <= 0xFFFF {
+            return;
+        }
+
+        self.mod_accumulator &= 0xFFFF;
+        let adjustment = MOD_LOOKUP[self.mod_table[self.mod_position] as usize];
+        self.mod_counter = if adjustment == MOD_RESET {
+            0
+        } else {
+            Sound::to_mod_counter(self.mod_counter.wrapping_add(adjustment) as u8)
+        };
+
+        self.mod_position = (self.mod_position + 1) % MOD_TABLE_SIZE;
+    }
+
+    /// The mod counter and gain bend the wave frequency, the rounding here matches the hardware
+    fn get_pitch(&self) -> u32 {
+        let pitch = self.wave_frequency as i32;
+        if self.mod_halted {
+            return pitch as u32;
+        }
+
+        let counter = self.mod_counter as i32;
+        let mut temp = counter * (self.mod_envelope.gain as i32);
+        let remainder = temp & 0x0F;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            temp += if counter < 0 { -1 } else { 2 };
+        }
+
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+
+        temp *= pitch;
+        let remainder = temp & 0x3F;
+        temp >>= 6;
+        if remainder >= 32 {
+            temp += 1;
+        }
+
+        (pitch + temp).max(0) as u32
+    }
+
+    /// The mod counter is a signed 7 bit value
+    fn to_mod_counter(data: u8) -> i8 {
+        ((data << 1) as i8) >> 1
+    }
+}
+
+/// Both envelopes tick every 8 * (speed + 1) * master speed CPU cycles, moving the gain up or down by 1.
+/// Bit 7 turns the envelope off, in which case the speed bits set the gain directly.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+struct Envelope {
+    gain: u8,
+    counter: u32,
+    disabled: bool,
+    increase: bool,
+    speed: u8
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Envelope {
+            gain: 0,
+            counter: 0,
+            disabled: true,
+            increase: false,
+            speed: 0
+        }
+    }
+
+    fn clock(&mut self, master_speed: u8) {
+        if self.disabled {
+            return;
+        }
+
+        if self.counter > 0 {
+            self.counter -= 1;
+            return;
+        }
+
+        self.reset_timer(master_speed);
+        if self.increase && self.gain < ENVELOPE_GAIN_MAX {
+            self.gain += 1;
+        } else if !self.increase && self.gain > 0 {
+            self.gain -= 1;
+        }
+    }
+
+    fn write(&mut self, data: u8) {
+        self.speed = data & 0b11_1111;
+        self.increase = data & 0b0100_0000 != 0;
+        self.disabled = data & 0b1000_0000 != 0;
+        if self.disabled {
+            self.gain = self.speed;
+        }
+    }
+
+    fn reset_timer(&mut self, master_speed: u8) {
+        self.counter = 8 * (self.speed as u32 + 1) * (master_speed as u32);
+    }
+}
@@ -10,14 +10,42 @@ pub trait Mapper {
     fn get_mirror(&self) -> Mirror;
     fn irq_active(&self) -> bool;
     fn irq_clear(&mut self);
-    fn irq_scanline(&mut self);
-    fn cpu_map_read(&self, address: u16) -> MapperReadResult;
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult;
     fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult;
     fn ppu_map_read(&self, address: u16) -> MapperReadResult;
     fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, data: u8) -> bool;
     fn load_battery_backed_ram(&mut self, data: Vec<u8>);
     fn save_battery_backed_ram(&self, file_path: &str);
     fn save_state(&self) -> MapperSaveData;
+
+    /// Called with every address the PPU puts on its bus, used by mappers that watch the PPU to count scanlines
+    fn ppu_address(&mut self, _address: u16) {}
+
+    /// Called once for every CPU cycle, used by mappers with cycle based IRQ counters or expansion audio
+    fn cpu_clock(&mut self) {}
+
+    /// The current output of the cartridge's expansion audio, mixed in with the APU output
+    fn get_audio_sample(&self) -> f32 { 0.0 }
+
+    /// Boards without a way to disable the PRG ROM during writes see the ROM and CPU drive the bus at the same time,
+    /// so the value written is ANDed with the byte in ROM at that address
+    fn has_bus_conflicts(&self) -> bool { false }
+
+    /// Takes the disk out of the drive or puts it back in, only the Famicom Disk System has one
+    fn insert_or_eject_disk(&mut self) {}
+
+    /// Flips the disk over, or moves on to the next disk once the last side is reached
+    fn switch_disk_side(&mut self) {}
+
+    /// Picks the song an NSF plays the next time it's reset
+    fn select_song(&mut self, _song: u8) {}
+
+    /// Changes a byte of the mapper's own PRG RAM without going through its registers, for the REPL's poke.
+    /// Only called with addresses the mapper reads back from that RAM.
+    fn poke_ram(&mut self, _address: u16, _data: u8) {}
+
+    /// The CIRAM page a pattern table address is mapped to, for boards that can use the name table RAM as CHR RAM
+    fn get_pattern_ciram_page(&self, _address: u16) -> Option<u8> { None }
 }
 
 impl Debug for dyn Mapper {
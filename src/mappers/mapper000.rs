@@ -47,9 +47,8 @@ impl Mapper for Mapper000 {
 
     fn irq_active(&self) -> bool { false }
     fn irq_clear(&mut self) {}
-    fn irq_scanline(&mut self) {}
 
-    fn cpu_map_read(&self, address: u16) -> MapperReadResult {
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
         if address < CPU_MIN_ADDRESS {
             return MapperReadResult::none();
         }
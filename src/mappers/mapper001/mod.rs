@@ -22,7 +22,6 @@ use crate::mappers::mapper::{Mapper};
 use crate::mappers::mapper_save_data::{MapperSaveData, Mapper001SaveData};
 use crate::mappers::mapper_results::{MapperReadResult, MapperWriteResult};
 use crate::addresses::mappers::*;
-use crate::memory_sizes::*;
 use crate::cartridge::mirror::Mirror;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -38,7 +37,7 @@ pub struct Mapper001 {
 }
 
 impl Mapper001 {
-    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, mirror: Mirror) -> Self {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, mirror: Mirror, ram_size: usize) -> Self {
         let control_mirror = match mirror {
             Mirror::OneScreenLow => 0,
             Mirror::OneScreenHigh => 1,
@@ -54,7 +53,7 @@ impl Mapper001 {
             chr_bank: chr_bank::ChrBank::new(),
             control_register: control_register::ControlRegister(control_mirror),
             prg_bank: prg_bank::PrgBank::new(prg_banks),
-            ram: vec![0; KILOBYTES_32 as usize],
+            ram: vec![0; ram_size],
             shift_register: Default::default()
         }
     }
@@ -95,12 +94,11 @@ impl Mapper for Mapper001 {
 
     fn irq_active(&self) -> bool { false }
     fn irq_clear(&mut self) {}
-    fn irq_scanline(&mut self) {}
 
-    fn cpu_map_read(&self, address: u16) -> MapperReadResult {
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
         match address {
             OPTIONAL_RAM_ADDRESS_LOWER..=OPTIONAL_RAM_ADDRESS_UPPER => {
-                let index = (address & RAM_ADDRESS_MASK) as usize;
+                let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
                 MapperReadResult::from_mapper_ram(self.ram[index])
             },
             PRG_ROM_FIRST_BANK_LOWER..=PRG_ROM_LAST_BANK_UPPER => {
@@ -115,7 +113,7 @@ impl Mapper for Mapper001 {
         if address < CPU_MIN_ADDRESS {
             match address {
                 OPTIONAL_RAM_ADDRESS_LOWER..=OPTIONAL_RAM_ADDRESS_UPPER => {
-                    let index = (address & RAM_ADDRESS_MASK) as usize;
+                    let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
                     self.ram[index] = data;
                     return MapperWriteResult::handled();
                 },
@@ -173,6 +171,11 @@ impl Mapper for Mapper001 {
         battery_backed_ram::save_battery_backed_ram(file_path, &self.ram);
     }
 
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+        self.ram[index] = data;
+    }
+
     fn save_state(&self) -> MapperSaveData {
         MapperSaveData::Mapper001(Mapper001SaveData{
             prg_banks: self.prg_banks,
@@ -61,9 +61,8 @@ impl Mapper for Mapper002 {
 
     fn irq_active(&self) -> bool { false }
     fn irq_clear(&mut self) {}
-    fn irq_scanline(&mut self) {}
 
-    fn cpu_map_read(&self, address: u16) -> MapperReadResult {
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
         match address {
             CPU_MIN_ADDRESS..=SWITCHABLE_ROM_BANK_MAX => {
                 let mapped_address = (self.prg_bank_low as u32) * (KILOBYTES_16 as u32) + ((address & KILOBYTES_16_MASK) as u32);
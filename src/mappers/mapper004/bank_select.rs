@@ -94,20 +94,21 @@ impl BankSelect {
     fn set_prg_banks(&mut self) {
         let prg_offset = KILOBYTES_8 as u32;
         let ignore_top_two_bits = 0x3F;
-        let last_bank = ((self.number_prg_banks as u32) * 2) - 1;
+        let bank_count = (self.number_prg_banks as u32) * 2;
+        let last_bank = bank_count - 1;
         let second_last_bank = last_bank - 1;
         match self.prg_bank_mode {
             PrgBankMode::LowerSwappable => {
-                self.prg_banks[0] = (self.bank_registers[6] & ignore_top_two_bits) * prg_offset;
+                self.prg_banks[0] = ((self.bank_registers[6] & ignore_top_two_bits) % bank_count) * prg_offset;
                 self.prg_banks[2] = second_last_bank * prg_offset;
             },
             PrgBankMode::UpperSwappable => {
                 self.prg_banks[0] = second_last_bank * prg_offset;
-                self.prg_banks[2] = (self.bank_registers[6] & ignore_top_two_bits) * prg_offset;
+                self.prg_banks[2] = ((self.bank_registers[6] & ignore_top_two_bits) % bank_count) * prg_offset;
             }
         }
 
-        self.prg_banks[1] = (self.bank_registers[7] & ignore_top_two_bits) * prg_offset;
+        self.prg_banks[1] = ((self.bank_registers[7] & ignore_top_two_bits) % bank_count) * prg_offset;
         self.prg_banks[3] = last_bank * prg_offset;
     }
 
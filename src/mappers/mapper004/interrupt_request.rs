@@ -1,33 +1,90 @@
 use serde::{Serialize, Deserialize};
 
+const A12_MASK: u16 = 0x1000;
+
+/// A12 has to have been low for a few CPU cycles before a rising edge counts,
+/// this filters out the rising edges between the individual sprite and background fetches
+const A12_FILTER_CYCLES: u8 = 3;
+
+/// The MMC3 counts scanlines by watching for A12 rising on the PPU bus.
+/// With the usual setup of backgrounds at $0000 and sprites at $1000 that happens once per scanline when the sprites are fetched.
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub struct InterruptRequest {
     pub active: bool,
     pub counter: u8,
     pub latch: u8,
-    enabled: bool
+    a12_high: bool,
+    a12_low_cycles: u8,
+    enabled: bool,
+    reload: bool,
+    revision: Revision
+}
+
+/// Rev A (NEC) only triggers when the counter is decremented to 0 or reloaded by a write to $C001,
+/// Rev B (Sharp) also triggers on every clock while the latch is 0
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Revision {
+    A,
+    B
 }
 
 impl InterruptRequest {
-    pub fn new() -> Self {
+    pub fn new(revision: Revision) -> Self {
         InterruptRequest {
             active: false,
             counter: 0,
             enabled: false,
-            latch: 0
+            latch: 0,
+            a12_high: false,
+            a12_low_cycles: 0,
+            reload: false,
+            revision
         }
     }
 
     pub fn clock(&mut self) {
-        if self.counter == 0 {
+        let previous_counter = self.counter;
+        if self.counter == 0 || self.reload {
             self.counter = self.latch;
         } else {
             self.counter -= 1;
         }
 
-        if self.counter == 0 && self.enabled {
+        let trigger = match self.revision {
+            Revision::A => self.counter == 0 && (previous_counter != 0 || self.reload),
+            Revision::B => self.counter == 0
+        };
+
+        if trigger && self.enabled {
             self.active = true;
         }
+
+        self.reload = false;
+    }
+
+    pub fn cpu_clock(&mut self) {
+        if !self.a12_high {
+            self.a12_low_cycles = self.a12_low_cycles.saturating_add(1);
+        }
+    }
+
+    pub fn ppu_address(&mut self, address: u16) {
+        let a12_high = address & A12_MASK != 0;
+        if a12_high {
+            if !self.a12_high && self.a12_low_cycles >= A12_FILTER_CYCLES {
+                self.clock();
+            }
+
+            self.a12_low_cycles = 0;
+        }
+
+        self.a12_high = a12_high;
+    }
+
+    /// Writing to $C001 clears the counter and reloads it from the latch on the next clock
+    pub fn reload(&mut self) {
+        self.counter = 0;
+        self.reload = true;
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -42,5 +99,8 @@ impl InterruptRequest {
         self.counter = 0;
         self.enabled = false;
         self.latch = 0;
+        self.a12_high = false;
+        self.a12_low_cycles = 0;
+        self.reload = false;
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,7 @@
 pub mod bank_select;
 pub mod prg_ram_protect;
 pub mod interrupt_request;
+pub mod variant;
 
 use serde::{Serialize, Deserialize};
 use crate::mappers::battery_backed_ram;
@@ -13,6 +14,10 @@ use crate::cartridge::mirror::Mirror;
 const OPTIONAL_RAM_ADDRESS_LOWER: u16 = 0x6000;
 const OPTIONAL_RAM_ADDRESS_UPPER: u16 = 0x7FFF;
 const RAM_ADDRESS_MASK: u16 = 0x1FFF;
+const MMC6_RAM_ADDRESS_LOWER: u16 = 0x7000;
+const MMC6_RAM_ADDRESS_MASK: u16 = 0x03FF;
+const TQROM_CHR_RAM_BIT: u32 = 0b0100_0000;
+const TXSROM_NAME_TABLE_BIT: u32 = 0b1000_0000;
 
 #[derive(Serialize, Deserialize)]
 pub struct Mapper004 {
@@ -23,20 +28,24 @@ pub struct Mapper004 {
     battery_backed_ram: bool,
     mirror: Mirror,
     prg_ram_protect: prg_ram_protect::PrgRamProtect,
-    ram: Vec<u8>
+    ram: Vec<u8>,
+    variant: variant::Variant
 }
 
 impl Mapper004 {
-    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, mirror: Mirror) -> Self {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, mirror: Mirror, variant: variant::Variant, revision: interrupt_request::Revision, ram_size: usize) -> Self {
+        // The MMC6's RAM is inside the mapper so it's always 1KB
+        let ram_size = if variant == variant::Variant::Mmc6 { KILOBYTES_1 as usize } else { ram_size };
         Mapper004 {
             prg_banks,
             chr_banks,
             bank_select: bank_select::BankSelect::new(prg_banks),
             battery_backed_ram,
-            interrupt_request: interrupt_request::InterruptRequest::new(),
+            interrupt_request: interrupt_request::InterruptRequest::new(revision),
             mirror,
             prg_ram_protect: prg_ram_protect::PrgRamProtect::new(),
-            ram: vec![0; KILOBYTES_8 as usize]
+            ram: vec![0; ram_size],
+            variant
         }
     }
 
@@ -49,7 +58,8 @@ impl Mapper004 {
             interrupt_request: data.interrupt_request,
             mirror: data.mirror,
             prg_ram_protect: data.prg_ram_protect,
-            ram: data.ram.to_owned()
+            ram: data.ram.to_owned(),
+            variant: data.variant
         }
     }
 }
@@ -69,6 +79,15 @@ impl Mapper for Mapper004 {
     }
 
     fn get_mirror(&self) -> Mirror {
+        if self.variant == variant::Variant::TxSrom {
+            let mut pages = [0; 4];
+            for (i, page) in pages.iter_mut().enumerate() {
+                *page = if self.get_chr_bank(i) & TXSROM_NAME_TABLE_BIT == 0 { 0 } else { 1 };
+            }
+
+            return Mirror::Mapped(pages);
+        }
+
         self.mirror
     }
 
@@ -80,13 +99,13 @@ impl Mapper for Mapper004 {
         self.interrupt_request.active = false;
     }
 
-    fn irq_scanline(&mut self) {
-        self.interrupt_request.clock();
-    }
-
-    fn cpu_map_read(&self, address: u16) -> MapperReadResult {
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
         if let OPTIONAL_RAM_ADDRESS_LOWER..=OPTIONAL_RAM_ADDRESS_UPPER = address {
-            let index = (address & RAM_ADDRESS_MASK) as usize;
+            if self.variant == variant::Variant::Mmc6 {
+                return self.read_mmc6_ram(address);
+            }
+
+            let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
             return MapperReadResult::from_mapper_ram(self.ram[index]);
         }
 
@@ -114,7 +133,12 @@ impl Mapper for Mapper004 {
 
     fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
         if let OPTIONAL_RAM_ADDRESS_LOWER..=OPTIONAL_RAM_ADDRESS_UPPER = address {
-            let index = (address & RAM_ADDRESS_MASK) as usize;
+            if self.variant == variant::Variant::Mmc6 {
+                self.write_mmc6_ram(address, data);
+                return MapperWriteResult::handled();
+            }
+
+            let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
             self.ram[index] = data;
             return MapperWriteResult::handled();
         }
@@ -123,6 +147,9 @@ impl Mapper for Mapper004 {
             0x8000..=0x9FFF => {
                 if address % 2 == 0 {
                     self.bank_select.select_bank(data);
+                    if self.variant == variant::Variant::Mmc6 {
+                        self.prg_ram_protect.set_mmc6_enabled(data & 0b0010_0000 != 0);
+                    }
                 } else {
                     self.bank_select.set_bank_data(data);
                 }
@@ -130,6 +157,8 @@ impl Mapper for Mapper004 {
             0xA000..=0xBFFF => {
                 if address % 2 == 0 {
                     self.set_mirror(data);
+                } else if self.variant == variant::Variant::Mmc6 {
+                    self.prg_ram_protect.set_mmc6_data(data);
                 } else {
                     self.prg_ram_protect.set_data(data);
                 }
@@ -138,7 +167,7 @@ impl Mapper for Mapper004 {
                 if address % 2 == 0 {
                     self.interrupt_request.latch = data;
                 } else {
-                    self.interrupt_request.counter = 0;
+                    self.interrupt_request.reload();
                 }
             },
             0xE000..=0xFFFF => {
@@ -151,50 +180,23 @@ impl Mapper for Mapper004 {
     }
 
     fn ppu_map_read(&self, address: u16) -> MapperReadResult {
-        let address_offset = (address & KILOBYTES_1_MASK) as u32;
         match address {
-            0x0000..=0x03FF => {
-                let mapped_address = self.bank_select.chr_banks[0] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x0400..=0x07FF => {
-                let mapped_address = self.bank_select.chr_banks[1] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x0800..=0x0BFF => {
-                let mapped_address = self.bank_select.chr_banks[2] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x0C00..=0x0FFF => {
-                let mapped_address = self.bank_select.chr_banks[3] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x1000..=0x13FF => {
-                let mapped_address = self.bank_select.chr_banks[4] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x1400..=0x17FF => {
-                let mapped_address = self.bank_select.chr_banks[5] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x1800..=0x1BFF => {
-                let mapped_address = self.bank_select.chr_banks[6] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
-            0x1C00..=0x1FFF => {
-                let mapped_address = self.bank_select.chr_banks[7] + address_offset;
-                MapperReadResult::from_cart_ram(mapped_address)
-            },
+            0x0000..=0x1FFF => MapperReadResult::from_cart_ram(self.get_chr_address(address)),
             _ => MapperReadResult::none()
         }
     }
 
-    fn ppu_map_write(&mut self, _address: u16, _mapped_address: &mut u32, _data: u8) -> bool {
-        false
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > 0x1FFF || !self.is_chr_ram(address) {
+            return false;
+        }
+
+        *mapped_address = self.get_chr_address(address);
+        true
     }
 
     fn load_battery_backed_ram(&mut self, data: Vec<u8>) {
-        if !self.battery_backed_ram {
+        if !self.battery_backed_ram || data.len() != self.ram.len() {
             return;
         }
 
@@ -209,6 +211,12 @@ impl Mapper for Mapper004 {
         battery_backed_ram::save_battery_backed_ram(file_path, &self.ram);
     }
 
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        let mask = if self.variant == variant::Variant::Mmc6 { MMC6_RAM_ADDRESS_MASK } else { RAM_ADDRESS_MASK };
+        let index = (address & mask) as usize % self.ram.len();
+        self.ram[index] = data;
+    }
+
     fn save_state(&self) -> MapperSaveData {
         MapperSaveData::Mapper004(Mapper004SaveData{
             prg_banks: self.prg_banks,
@@ -218,17 +226,80 @@ impl Mapper for Mapper004 {
             interrupt_request: self.interrupt_request,
             mirror: self.mirror,
             prg_ram_protect: self.prg_ram_protect,
-            ram: self.ram.to_owned()
+            ram: self.ram.to_owned(),
+            variant: self.variant
         })
     }
+
+    fn ppu_address(&mut self, address: u16) {
+        self.interrupt_request.ppu_address(address);
+    }
+
+    fn cpu_clock(&mut self) {
+        self.interrupt_request.cpu_clock();
+    }
 }
 
 impl Mapper004 {
+    /// TxSROM wires the name tables to the CHR banks so the mirroring register does nothing
     pub fn set_mirror(&mut self, data: u8) {
+        if self.variant == variant::Variant::TxSrom {
+            return;
+        }
+
         self.mirror = if data & 0b1 == 0 {
             Mirror::Vertical
         } else {
             Mirror::Horizontal
         };
     }
+
+    fn get_chr_bank(&self, slot: usize) -> u32 {
+        self.bank_select.chr_banks[slot] / (KILOBYTES_1 as u32)
+    }
+
+    /// Boards without CHR ROM have 8KB of CHR RAM, TQROM has its 8KB of CHR RAM after the CHR ROM
+    fn get_chr_address(&self, address: u16) -> u32 {
+        let bank = self.get_chr_bank((address / KILOBYTES_1) as usize);
+        let address_offset = (address & KILOBYTES_1_MASK) as u32;
+        let chr_rom_size = (self.chr_banks as u32) * (KILOBYTES_8 as u32);
+        if self.variant == variant::Variant::Tqrom && bank & TQROM_CHR_RAM_BIT != 0 {
+            return chr_rom_size + (bank & 0b111) * (KILOBYTES_1 as u32) + address_offset;
+        }
+
+        let bank_count = if self.chr_banks == 0 { 8 } else { (self.chr_banks as u32) * 8 };
+        (bank % bank_count) * (KILOBYTES_1 as u32) + address_offset
+    }
+
+    fn is_chr_ram(&self, address: u16) -> bool {
+        if self.variant == variant::Variant::Tqrom {
+            return self.get_chr_bank((address / KILOBYTES_1) as usize) & TQROM_CHR_RAM_BIT != 0;
+        }
+
+        self.chr_banks == 0
+    }
+
+    /// The 1KB of RAM is mirrored across $7000-$7FFF, $6000-$6FFF is open bus
+    fn read_mmc6_ram(&self, address: u16) -> MapperReadResult {
+        if address < MMC6_RAM_ADDRESS_LOWER {
+            return MapperReadResult::none();
+        }
+
+        let index = (address & MMC6_RAM_ADDRESS_MASK) as usize;
+        match self.prg_ram_protect.read_mmc6(&self.ram, index) {
+            Some(data) => MapperReadResult::from_mapper_ram(data),
+            None => MapperReadResult::none()
+        }
+    }
+
+    fn write_mmc6_ram(&mut self, address: u16, data: u8) {
+        if address < MMC6_RAM_ADDRESS_LOWER {
+            return;
+        }
+
+        let index = (address & MMC6_RAM_ADDRESS_MASK) as usize;
+        if self.prg_ram_protect.can_write_mmc6(index) {
+            self.ram[index] = data;
+        }
+    }
 }
\ No newline at end of file
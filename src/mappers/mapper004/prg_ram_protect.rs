@@ -1,16 +1,30 @@
 use serde::{Serialize, Deserialize};
 
+/// MMC3 uses $A001 to enable the chip and protect it from writes.
+/// MMC6 enables its RAM with bit 5 of $8000 and uses $A001 to protect the two 512 byte halves separately:
+/// Bit 4: Allow writes to $7000-$71FF
+/// Bit 5: Allow reads from $7000-$71FF
+/// Bit 6: Allow writes to $7200-$73FF
+/// Bit 7: Allow reads from $7200-$73FF
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub struct PrgRamProtect {
     enable_chip: bool,
-    allow_writes: bool
+    allow_writes: bool,
+    read_high: bool,
+    read_low: bool,
+    write_high: bool,
+    write_low: bool
 }
 
 impl PrgRamProtect {
     pub fn new() -> Self {
         PrgRamProtect {
             enable_chip: false,
-            allow_writes: true
+            allow_writes: true,
+            read_high: false,
+            read_low: false,
+            write_high: false,
+            write_low: false
         }
     }
 
@@ -18,4 +32,45 @@ impl PrgRamProtect {
         self.allow_writes = data & 0b0100_0000 == 0;
         self.enable_chip = data & 0b1000_0000 != 0;
     }
+
+    pub fn set_mmc6_enabled(&mut self, enabled: bool) {
+        self.enable_chip = enabled;
+        if !enabled {
+            self.set_mmc6_halves(0);
+        }
+    }
+
+    /// Writes to $A001 are ignored while the RAM is disabled
+    pub fn set_mmc6_data(&mut self, data: u8) {
+        if self.enable_chip {
+            self.set_mmc6_halves(data);
+        }
+    }
+
+    /// Returns None when neither half can be read, which leaves the bus open.
+    /// If only one half can be read then the other one reads back as 0.
+    pub fn read_mmc6(&self, ram: &[u8], index: usize) -> Option<u8> {
+        if !self.read_low && !self.read_high {
+            return None;
+        }
+
+        let readable = if Self::is_high_half(index) { self.read_high } else { self.read_low };
+        Some(if readable { ram[index] } else { 0 })
+    }
+
+    /// A half can only be written while it can also be read
+    pub fn can_write_mmc6(&self, index: usize) -> bool {
+        if Self::is_high_half(index) { self.write_high && self.read_high } else { self.write_low && self.read_low }
+    }
+
+    fn set_mmc6_halves(&mut self, data: u8) {
+        self.write_low = data & 0b0001_0000 != 0;
+        self.read_low = data & 0b0010_0000 != 0;
+        self.write_high = data & 0b0100_0000 != 0;
+        self.read_high = data & 0b1000_0000 != 0;
+    }
+
+    fn is_high_half(index: usize) -> bool {
+        index & 0x200 != 0
+    }
 }
\ No newline at end of file
@@ -0,0 +1,21 @@
+use serde::{Serialize, Deserialize};
+
+/// Boards built around the MMC3 that only differ in how a few of its outputs are wired up
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Variant {
+    /// TxROM and friends, mapper 4
+    Standard,
+
+    /// TKSROM/TLSROM, mapper 118
+    /// The name tables are wired to CHR A17 instead of the mirroring register,
+    /// so bit 7 of the CHR bank in each 1KB slot at $0000-$0FFF selects the CIRAM page for the matching quadrant
+    TxSrom,
+
+    /// TQROM, mapper 119
+    /// Has both CHR ROM and 8KB of CHR RAM, bit 6 of a CHR bank selects the RAM
+    Tqrom,
+
+    /// MMC6, mapper 4 submapper 1
+    /// Has 1KB of RAM at $7000-$7FFF, split into two 512 byte halves that can be protected separately
+    Mmc6
+}
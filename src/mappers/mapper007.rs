@@ -0,0 +1,124 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper007SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::memory_sizes::*;
+use crate::cartridge::mirror::Mirror;
+
+const BUS_CONFLICTS_SUBMAPPER: u8 = 2;
+
+/// AxROM
+/// $8000-$FFFF: Select 32KB PRG bank(2-0), select one screen name table(4)
+/// AMROM and ANROM boards have bus conflicts, AOROM doesn't. Most dumps don't say which board they're from
+/// so bus conflicts are only emulated for NES 2.0 submapper 2.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper007 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    battery_backed_ram: bool,
+    bus_conflicts: bool,
+    name_table: u8,
+    prg_bank: u8
+}
+
+impl Mapper007 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, submapper: u8) -> Self {
+        Mapper007 {
+            prg_banks,
+            chr_banks,
+            battery_backed_ram,
+            bus_conflicts: submapper == BUS_CONFLICTS_SUBMAPPER,
+            name_table: 0,
+            prg_bank: 0
+        }
+    }
+
+    pub fn from(data: &Mapper007SaveData) -> Self {
+        Mapper007 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            battery_backed_ram: data.battery_backed_ram,
+            bus_conflicts: data.bus_conflicts,
+            name_table: data.name_table,
+            prg_bank: data.prg_bank
+        }
+    }
+}
+
+impl Mapper for Mapper007 {
+    fn reset(&mut self) {
+        self.name_table = 0;
+        self.prg_bank = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        if self.name_table == 0 { Mirror::OneScreenLow } else { Mirror::OneScreenHigh }
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        if address < CPU_MIN_ADDRESS {
+            return MapperReadResult::none();
+        }
+
+        let bank_count = ((self.prg_banks / 2) as u32).max(1);
+        let bank = (self.prg_bank as u32) % bank_count;
+        MapperReadResult::from_cart_ram(bank * (KILOBYTES_32 as u32) + ((address & KILOBYTES_32_MASK) as u32))
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        if address >= CPU_MIN_ADDRESS {
+            self.prg_bank = data & 0b111;
+            self.name_table = (data >> 4) & 1;
+            return MapperWriteResult::handled();
+        }
+
+        MapperWriteResult::none()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS || self.chr_banks != 0 {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper007(Mapper007SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            battery_backed_ram: self.battery_backed_ram,
+            bus_conflicts: self.bus_conflicts,
+            name_table: self.name_table,
+            prg_bank: self.prg_bank
+        })
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+const COUNTER_MAX: u16 = 0x7FFF;
+
+/// A 15 bit counter that counts up every CPU cycle and triggers an IRQ when it reaches $7FFF
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct InterruptRequest {
+    pub active: bool,
+    counter: u16,
+    enabled: bool
+}
+
+impl InterruptRequest {
+    pub fn new() -> Self {
+        InterruptRequest {
+            active: false,
+            counter: 0,
+            enabled: false
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.enabled || self.counter == COUNTER_MAX {
+            return;
+        }
+
+        self.counter += 1;
+        if self.counter == COUNTER_MAX {
+            self.active = true;
+        }
+    }
+
+    /// $5000: Low 8 bits of the counter
+    pub fn read_low(&self) -> u8 {
+        (self.counter & 0xFF) as u8
+    }
+
+    /// $5800: High 7 bits of the counter and the enable flag
+    pub fn read_high(&self) -> u8 {
+        let enabled = if self.enabled { 0b1000_0000 } else { 0 };
+        ((self.counter >> 8) as u8 & 0b0111_1111) | enabled
+    }
+
+    pub fn write_low(&mut self, data: u8) {
+        self.counter = (self.counter & 0x7F00) | (data as u16);
+        self.active = false;
+    }
+
+    pub fn write_high(&mut self, data: u8) {
+        self.counter = (((data & 0b0111_1111) as u16) << 8) | (self.counter & 0x00FF);
+        self.enabled = data & 0b1000_0000 != 0;
+        self.active = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.active = false;
+        self.counter = 0;
+        self.enabled = false;
+    }
+}
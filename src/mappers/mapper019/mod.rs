@@ -0,0 +1,287 @@
+pub mod interrupt_request;
+pub mod sound;
+
+use serde::{Serialize, Deserialize};
+use crate::mappers::battery_backed_ram;
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper019SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::memory_sizes::*;
+use crate::cartridge::mirror::Mirror;
+
+const CHR_BANK_LENGTH: usize = 8;
+const CHR_CIRAM_DISABLE_HIGH: u8 = 0b1000_0000;
+const CHR_CIRAM_DISABLE_LOW: u8 = 0b0100_0000;
+const CIRAM_BANK_MIN: u8 = 0xE0;
+const NAME_TABLE_LENGTH: usize = 4;
+const NO_CIRAM_PAGE: u8 = 0xFF;
+const PRG_BANK_LENGTH: usize = 3;
+const RAM_ADDRESS_MASK: u16 = 0x1FFF;
+
+/// Namco 163
+/// $4800-$4FFF: Internal sound RAM data port
+/// $5000-$57FF: IRQ counter low
+/// $5800-$5FFF: IRQ counter high and enable
+/// $6000-$7FFF: 8KB PRG RAM
+/// $8000-$BFFF: CHR banks for $0000-$1FFF, one 1KB bank per $800
+/// $C000-$DFFF: Name table banks for $2000-$2FFF, one 1KB bank per $800
+/// $E000-$F7FF: PRG banks for $8000-$DFFF, one 8KB bank per $800
+///     $E800 bits 6 and 7 stop CHR banks $E0-$FF selecting CIRAM for $0000-$0FFF and $1000-$1FFF
+/// $F800-$FFFF: Internal sound RAM address port and PRG RAM write protection
+#[derive(Serialize, Deserialize)]
+pub struct Mapper019 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub interrupt_request: interrupt_request::InterruptRequest,
+    pub sound: sound::Sound,
+    battery_backed_ram: bool,
+    chr_bank_registers: [u8; CHR_BANK_LENGTH],
+    name_table_registers: [u8; NAME_TABLE_LENGTH],
+    prg_bank_registers: [u8; PRG_BANK_LENGTH],
+    ram: Vec<u8>,
+    write_protect: u8
+}
+
+impl Mapper019 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, ram_size: usize) -> Self {
+        Mapper019 {
+            prg_banks,
+            chr_banks,
+            interrupt_request: interrupt_request::InterruptRequest::new(),
+            sound: sound::Sound::new(),
+            battery_backed_ram,
+            chr_bank_registers: [0; CHR_BANK_LENGTH],
+            name_table_registers: [0; NAME_TABLE_LENGTH],
+            prg_bank_registers: [0; PRG_BANK_LENGTH],
+            ram: vec![0; ram_size],
+            write_protect: 0
+        }
+    }
+
+    pub fn from(data: &Mapper019SaveData) -> Self {
+        Mapper019 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            interrupt_request: data.interrupt_request,
+            sound: data.sound.clone(),
+            battery_backed_ram: data.battery_backed_ram,
+            chr_bank_registers: data.chr_bank_registers,
+            name_table_registers: data.name_table_registers,
+            prg_bank_registers: data.prg_bank_registers,
+            ram: data.ram.to_owned(),
+            write_protect: data.write_protect
+        }
+    }
+}
+
+impl Mapper for Mapper019 {
+    fn reset(&mut self) {
+        self.interrupt_request.reset();
+        self.prg_bank_registers = [0; PRG_BANK_LENGTH];
+        self.write_protect = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        let mut pages = [NO_CIRAM_PAGE; NAME_TABLE_LENGTH];
+        for (i, page) in pages.iter_mut().enumerate() {
+            let bank = self.name_table_registers[i];
+            if bank >= CIRAM_BANK_MIN {
+                *page = bank & 0b1;
+            }
+        }
+
+        Mirror::Mapped(pages)
+    }
+
+    fn irq_active(&self) -> bool {
+        self.interrupt_request.active
+    }
+
+    fn irq_clear(&mut self) {
+        self.interrupt_request.active = false;
+    }
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        match address {
+            0x4800..=0x4FFF => MapperReadResult::from_mapper_ram(self.sound.read_data()),
+            0x5000..=0x57FF => MapperReadResult::from_mapper_ram(self.interrupt_request.read_low()),
+            0x5800..=0x5FFF => MapperReadResult::from_mapper_ram(self.interrupt_request.read_high()),
+            0x6000..=0x7FFF => {
+                let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+                MapperReadResult::from_mapper_ram(self.ram[index])
+            },
+            0x8000..=0xDFFF => {
+                let bank = self.prg_bank_registers[((address - 0x8000) / KILOBYTES_8) as usize] & 0b11_1111;
+                MapperReadResult::from_cart_ram(self.get_prg_address(bank as u32, address))
+            },
+            0xE000..=0xFFFF => {
+                let last_bank = (self.prg_banks as u32) * 2 - 1;
+                MapperReadResult::from_cart_ram(self.get_prg_address(last_bank, address))
+            },
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        match address {
+            0x4800..=0x4FFF => self.sound.write_data(data),
+            0x5000..=0x57FF => self.interrupt_request.write_low(data),
+            0x5800..=0x5FFF => self.interrupt_request.write_high(data),
+            0x6000..=0x7FFF => {
+                if self.can_write_ram(address) {
+                    let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+                    self.ram[index] = data;
+                }
+            },
+            0x8000..=0xBFFF => {
+                let index = ((address - 0x8000) / KILOBYTES_2) as usize;
+                self.chr_bank_registers[index] = data;
+            },
+            0xC000..=0xDFFF => {
+                let index = ((address - 0xC000) / KILOBYTES_2) as usize;
+                self.name_table_registers[index] = data;
+            },
+            0xE000..=0xE7FF => {
+                self.prg_bank_registers[0] = data;
+                self.sound.set_disabled(data & 0b0100_0000 != 0);
+            },
+            0xE800..=0xEFFF => self.prg_bank_registers[1] = data,
+            0xF000..=0xF7FF => self.prg_bank_registers[2] = data,
+            0xF800..=0xFFFF => {
+                self.sound.write_address(data);
+                self.write_protect = data;
+            },
+            _ => return MapperWriteResult::none()
+        }
+
+        MapperWriteResult::handled()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        let address_offset = (address & KILOBYTES_1_MASK) as u32;
+        match address {
+            0x0000..=0x1FFF => {
+                if self.get_pattern_ciram_page(address).is_some() {
+                    return MapperReadResult::none();
+                }
+
+                let bank = self.chr_bank_registers[(address / KILOBYTES_1) as usize];
+                MapperReadResult::from_cart_ram(self.get_chr_address(bank, address_offset))
+            },
+            0x2000..=0x3EFF => {
+                let bank = self.name_table_registers[((address >> 10) & 0b11) as usize];
+                if bank >= CIRAM_BANK_MIN {
+                    // CIRAM is handled by the PPU through the mapped mirror
+                    return MapperReadResult::none();
+                }
+
+                MapperReadResult::from_cart_ram(self.get_chr_address(bank, address_offset))
+            },
+            _ => MapperReadResult::none()
+        }
+    }
+
+    /// CHR ROM can't be written, banks mapped to CIRAM are left to the PPU
+    fn ppu_map_write(&mut self, _address: u16, _mapped_address: &mut u32, _data: u8) -> bool {
+        false
+    }
+
+    fn get_pattern_ciram_page(&self, address: u16) -> Option<u8> {
+        if address > 0x1FFF {
+            return None;
+        }
+
+        let bank = self.chr_bank_registers[(address / KILOBYTES_1) as usize];
+        let disable = if address < 0x1000 { CHR_CIRAM_DISABLE_LOW } else { CHR_CIRAM_DISABLE_HIGH };
+        if bank >= CIRAM_BANK_MIN && self.prg_bank_registers[1] & disable == 0 {
+            Some(bank & 0b1)
+        } else {
+            None
+        }
+    }
+
+    /// The save data is the PRG RAM followed by the internal sound RAM
+    fn load_battery_backed_ram(&mut self, data: Vec<u8>) {
+        if !self.battery_backed_ram || data.len() != self.ram.len() + sound::INTERNAL_RAM_SIZE {
+            return;
+        }
+
+        let (ram, internal_ram) = data.split_at(self.ram.len());
+        self.ram = ram.to_vec();
+        self.sound.ram = internal_ram.to_vec();
+    }
+
+    fn save_battery_backed_ram(&self, file_path: &str) {
+        if !self.battery_backed_ram {
+            return;
+        }
+
+        let mut data = self.ram.to_owned();
+        data.extend_from_slice(&self.sound.ram);
+        battery_backed_ram::save_battery_backed_ram(file_path, &data);
+    }
+
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+        self.ram[index] = data;
+    }
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper019(Mapper019SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            interrupt_request: self.interrupt_request,
+            sound: self.sound.clone(),
+            battery_backed_ram: self.battery_backed_ram,
+            chr_bank_registers: self.chr_bank_registers,
+            name_table_registers: self.name_table_registers,
+            prg_bank_registers: self.prg_bank_registers,
+            ram: self.ram.to_owned(),
+            write_protect: self.write_protect
+        })
+    }
+
+    fn cpu_clock(&mut self) {
+        self.interrupt_request.clock();
+        self.sound.clock();
+    }
+
+    fn get_audio_sample(&self) -> f32 {
+        self.sound.get_sample()
+    }
+}
+
+impl Mapper019 {
+    fn get_prg_address(&self, bank: u32, address: u16) -> u32 {
+        let bank_count = (self.prg_banks as u32) * 2;
+        (bank % bank_count) * (KILOBYTES_8 as u32) + ((address & KILOBYTES_8_MASK) as u32)
+    }
+
+    fn get_chr_address(&self, bank: u8, address_offset: u32) -> u32 {
+        let bank_count = (self.chr_banks as u32) * 8;
+        if bank_count == 0 {
+            return address_offset;
+        }
+
+        ((bank as u32) % bank_count) * (KILOBYTES_1 as u32) + address_offset
+    }
+
+    /// Writes are only allowed when the upper nibble of $F800 is %0100.
+    /// Each of the lower 4 bits then protects a 2KB window of the PRG RAM.
+    fn can_write_ram(&self, address: u16) -> bool {
+        if self.write_protect & 0xF0 != 0x40 {
+            return false;
+        }
+
+        let window = (address & RAM_ADDRESS_MASK) / KILOBYTES_2;
+        self.write_protect & (1 << window) == 0
+    }
+}
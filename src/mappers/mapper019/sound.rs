@@ -0,0 +1,145 @@
+use serde::{Serialize, Deserialize};
+
+pub const INTERNAL_RAM_SIZE: usize = 128;
+
+const CHANNEL_REGISTER_START: usize = 0x40;
+const CHANNEL_REGISTER_SIZE: usize = 8;
+const CHANNEL_COUNT_REGISTER: usize = 0x7F;
+const CYCLES_PER_CHANNEL: u8 = 15;
+const MAX_CHANNELS: usize = 8;
+const OUTPUT_SCALE: f32 = 900.0;
+
+/// The Namco 163 has 128 bytes of internal RAM which holds both the wave samples and the channel registers.
+/// Channels are not mixed together, instead a single channel is updated and output every 15 CPU cycles.
+/// Each channel's registers live at $40 + (channel * 8):
+/// +0: Frequency low
+/// +1: Phase low
+/// +2: Frequency middle
+/// +3: Phase middle
+/// +4: Frequency high (bits 0-1), wave length (bits 2-7, 256 - length in 4 bit samples)
+/// +5: Phase high
+/// +6: Wave address (in 4 bit samples)
+/// +7: Volume (bits 0-3), channel 7 also holds the number of enabled channels - 1 (bits 4-6)
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Sound {
+    pub ram: Vec<u8>,
+    address: u8,
+    auto_increment: bool,
+    current_channel: usize,
+    cycle_counter: u8,
+    disabled: bool,
+    outputs: [i16; MAX_CHANNELS]
+}
+
+impl Sound {
+    pub fn new() -> Self {
+        Sound {
+            ram: vec![0; INTERNAL_RAM_SIZE],
+            address: 0,
+            auto_increment: false,
+            current_channel: MAX_CHANNELS - 1,
+            cycle_counter: 0,
+            disabled: false,
+            outputs: [0; MAX_CHANNELS]
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if self.disabled {
+            return;
+        }
+
+        self.cycle_counter += 1;
+        if self.cycle_counter < CYCLES_PER_CHANNEL {
+            return;
+        }
+
+        self.cycle_counter = 0;
+        self.update_channel(self.current_channel);
+
+        // The enabled channels are the highest ones, so we count down until the lowest enabled channel and then wrap back to 7
+        let lowest_channel = MAX_CHANNELS - self.get_channel_count();
+        self.current_channel = if self.current_channel <= lowest_channel {
+            MAX_CHANNELS - 1
+        } else {
+            self.current_channel - 1
+        };
+    }
+
+    pub fn get_sample(&self) -> f32 {
+        if self.disabled {
+            return 0.0;
+        }
+
+        // Since the channels are time multiplexed, the more channels that are enabled the quieter each one gets
+        let channel_count = self.get_channel_count();
+        let total: i16 = self.outputs[(MAX_CHANNELS - channel_count)..].iter().sum();
+        (total as f32) / (channel_count as f32) / OUTPUT_SCALE
+    }
+
+    /// $4800: Read data from the internal RAM
+    pub fn read_data(&mut self) -> u8 {
+        let data = self.ram[self.address as usize];
+        self.increment_address();
+        data
+    }
+
+    /// $4800: Write data to the internal RAM
+    pub fn write_data(&mut self, data: u8) {
+        self.ram[self.address as usize] = data;
+        self.increment_address();
+    }
+
+    /// $F800: Set the internal RAM address(bits 0-6) and the auto increment flag(bit 7)
+    pub fn write_address(&mut self, data: u8) {
+        self.address = data & 0b0111_1111;
+        self.auto_increment = data & 0b1000_0000 != 0;
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    fn get_channel_count(&self) -> usize {
+        (((self.ram[CHANNEL_COUNT_REGISTER] >> 4) & 0b111) + 1) as usize
+    }
+
+    fn update_channel(&mut self, channel: usize) {
+        let base = CHANNEL_REGISTER_START + (channel * CHANNEL_REGISTER_SIZE);
+        let frequency =
+            (self.ram[base] as u32) |
+            ((self.ram[base + 2] as u32) << 8) |
+            (((self.ram[base + 4] & 0b11) as u32) << 16);
+
+        let length = 256 - ((self.ram[base + 4] & 0b1111_1100) as u32);
+        let mut phase =
+            (self.ram[base + 1] as u32) |
+            ((self.ram[base + 3] as u32) << 8) |
+            ((self.ram[base + 5] as u32) << 16);
+
+        phase = (phase + frequency) % (length << 16);
+        self.ram[base + 1] = (phase & 0xFF) as u8;
+        self.ram[base + 3] = ((phase >> 8) & 0xFF) as u8;
+        self.ram[base + 5] = ((phase >> 16) & 0xFF) as u8;
+
+        let wave_address = self.ram[base + 6] as u32;
+        let sample_index = (((phase >> 16) + wave_address) & 0xFF) as usize;
+        let sample = self.get_wave_sample(sample_index);
+        let volume = (self.ram[base + 7] & 0b1111) as i16;
+
+        self.outputs[channel] = (sample - 8) * volume;
+    }
+
+    /// Samples are 4 bits, stored low nibble first
+    fn get_wave_sample(&self, index: usize) -> i16 {
+        let data = self.ram[(index >> 1) & (INTERNAL_RAM_SIZE - 1)];
+        let sample = if index & 1 == 0 { data & 0x0F } else { data >> 4 };
+        sample as i16
+    }
+
+    fn increment_address(&mut self) {
+        if self.auto_increment {
+            self.address = (self.address + 1) & 0b0111_1111;
+        }
+    }
+}
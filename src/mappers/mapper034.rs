@@ -0,0 +1,171 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper034SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::memory_sizes::*;
+use crate::cartridge::mirror::Mirror;
+
+const NINA_001_SUBMAPPER: u8 = 1;
+const BNROM_SUBMAPPER: u8 = 2;
+const RAM_ADDRESS_MASK: u16 = 0x1FFF;
+
+/// Mapper 34 covers two unrelated boards.
+/// BNROM has CHR RAM, bus conflicts and a single register:
+/// $8000-$FFFF: Select 32KB PRG bank
+/// NINA-001 has CHR ROM, 8KB of PRG RAM and its registers sit on top of the RAM:
+/// $7FFD: Select 32KB PRG bank
+/// $7FFE: Select 4KB CHR bank at $0000
+/// $7FFF: Select 4KB CHR bank at $1000
+/// iNES headers don't say which board it is so any game with more than 8KB of CHR is treated as NINA-001.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper034 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    battery_backed_ram: bool,
+    chr_bank_high: u8,
+    chr_bank_low: u8,
+    nina_001: bool,
+    prg_bank: u8,
+    ram: Vec<u8>
+}
+
+impl Mapper034 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, submapper: u8, ram_size: usize) -> Self {
+        let nina_001 = match submapper {
+            NINA_001_SUBMAPPER => true,
+            BNROM_SUBMAPPER => false,
+            _ => chr_banks > 1
+        };
+
+        Mapper034 {
+            prg_banks,
+            chr_banks,
+            battery_backed_ram,
+            chr_bank_high: 1,
+            chr_bank_low: 0,
+            nina_001,
+            prg_bank: 0,
+            ram: vec![0; ram_size]
+        }
+    }
+
+    pub fn from(data: &Mapper034SaveData) -> Self {
+        Mapper034 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            battery_backed_ram: data.battery_backed_ram,
+            chr_bank_high: data.chr_bank_high,
+            chr_bank_low: data.chr_bank_low,
+            nina_001: data.nina_001,
+            prg_bank: data.prg_bank,
+            ram: data.ram.to_owned()
+        }
+    }
+}
+
+impl Mapper for Mapper034 {
+    fn reset(&mut self) {
+        self.chr_bank_high = 1;
+        self.chr_bank_low = 0;
+        self.prg_bank = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        Mirror::Hardware
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        match address {
+            0x6000..=0x7FFF if self.nina_001 => {
+                MapperReadResult::from_mapper_ram(self.ram[(address & RAM_ADDRESS_MASK) as usize % self.ram.len()])
+            },
+            CPU_MIN_ADDRESS..=CPU_MAX_ADDRESS => {
+                let bank_count = ((self.prg_banks / 2) as u32).max(1);
+                let bank = (self.prg_bank as u32) % bank_count;
+                MapperReadResult::from_cart_ram(bank * (KILOBYTES_32 as u32) + ((address & KILOBYTES_32_MASK) as u32))
+            },
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        if self.nina_001 {
+            if let 0x6000..=0x7FFF = address {
+                match address {
+                    0x7FFD => self.prg_bank = data & 0b1,
+                    0x7FFE => self.chr_bank_low = data & 0b1111,
+                    0x7FFF => self.chr_bank_high = data & 0b1111,
+                    _ => ()
+                }
+
+                let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+                self.ram[index] = data;
+                return MapperWriteResult::handled();
+            }
+        } else if address >= CPU_MIN_ADDRESS {
+            self.prg_bank = data;
+            return MapperWriteResult::handled();
+        }
+
+        MapperWriteResult::none()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS if self.nina_001 => {
+                let bank = if address < KILOBYTES_4 { self.chr_bank_low } else { self.chr_bank_high };
+                let bank = (bank as u32) % ((self.chr_banks as u32) * 2).max(1);
+                MapperReadResult::from_cart_ram(bank * (KILOBYTES_4 as u32) + ((address & KILOBYTES_4_MASK) as u32))
+            },
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS || self.chr_banks != 0 {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+        self.ram[index] = data;
+    }
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper034(Mapper034SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            battery_backed_ram: self.battery_backed_ram,
+            chr_bank_high: self.chr_bank_high,
+            chr_bank_low: self.chr_bank_low,
+            nina_001: self.nina_001,
+            prg_bank: self.prg_bank,
+            ram: self.ram.to_owned()
+        })
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        !self.nina_001
+    }
+}
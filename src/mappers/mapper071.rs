@@ -0,0 +1,135 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper071SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::memory_sizes::{KILOBYTES_16, KILOBYTES_16_MASK};
+use crate::cartridge::mirror::Mirror;
+
+const FIRE_HAWK_SUBMAPPER: u8 = 1;
+const SWITCHABLE_ROM_BANK_MAX: u16 = 0xBFFF;
+const FIXED_BANK_MIN: u16 = 0xC000;
+
+/// Camerica/Codemasters BF9093 and BF9097
+/// $8000-$9FFF: Select one screen name table(4), only on the BF9097 used by Fire Hawk
+/// $C000-$FFFF: Select 16KB PRG bank at $8000, $C000 is fixed to the last bank
+/// Fire Hawk is NES 2.0 submapper 1, iNES dumps of it turn on mirroring control the first time $9000-$9FFF is written
+/// since none of the BF9093 games write there.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper071 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    battery_backed_ram: bool,
+    mirroring_control: bool,
+    name_table: u8,
+    prg_bank: u8
+}
+
+impl Mapper071 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, submapper: u8) -> Self {
+        Mapper071 {
+            prg_banks,
+            chr_banks,
+            battery_backed_ram,
+            mirroring_control: submapper == FIRE_HAWK_SUBMAPPER,
+            name_table: 0,
+            prg_bank: 0
+        }
+    }
+
+    pub fn from(data: &Mapper071SaveData) -> Self {
+        Mapper071 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            battery_backed_ram: data.battery_backed_ram,
+            mirroring_control: data.mirroring_control,
+            name_table: data.name_table,
+            prg_bank: data.prg_bank
+        }
+    }
+}
+
+impl Mapper for Mapper071 {
+    fn reset(&mut self) {
+        self.name_table = 0;
+        self.prg_bank = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        if !self.mirroring_control {
+            return Mirror::Hardware;
+        }
+
+        if self.name_table == 0 { Mirror::OneScreenLow } else { Mirror::OneScreenHigh }
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        let bank = match address {
+            CPU_MIN_ADDRESS..=SWITCHABLE_ROM_BANK_MAX => (self.prg_bank as u32) % (self.prg_banks.max(1) as u32),
+            FIXED_BANK_MIN..=CPU_MAX_ADDRESS => (self.prg_banks.max(1) as u32) - 1,
+            _ => return MapperReadResult::none()
+        };
+
+        MapperReadResult::from_cart_ram(bank * (KILOBYTES_16 as u32) + ((address & KILOBYTES_16_MASK) as u32))
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        match address {
+            0x8000..=0x9FFF => {
+                if address >= 0x9000 {
+                    self.mirroring_control = true;
+                }
+
+                self.name_table = (data >> 4) & 1;
+                MapperWriteResult::handled()
+            },
+            FIXED_BANK_MIN..=CPU_MAX_ADDRESS => {
+                self.prg_bank = data;
+                MapperWriteResult::handled()
+            },
+            _ => MapperWriteResult::none()
+        }
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS || self.chr_banks != 0 {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper071(Mapper071SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            battery_backed_ram: self.battery_backed_ram,
+            mirroring_control: self.mirroring_control,
+            name_table: self.name_table,
+            prg_bank: self.prg_bank
+        })
+    }
+}
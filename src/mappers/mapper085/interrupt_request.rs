@@ -0,0 +1,86 @@
+use serde::{Serialize, Deserialize};
+
+const PRESCALER_PERIOD: i16 = 341;
+
+/// The VRC IRQ counts up from the latch value and triggers an IRQ when it overflows from $FF.
+/// In scanline mode a prescaler divides the CPU clock by 113.667 (341 / 3) so it approximates one scanline,
+/// in cycle mode the counter is clocked every CPU cycle.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct InterruptRequest {
+    pub active: bool,
+    pub latch: u8,
+    counter: u8,
+    cycle_mode: bool,
+    enabled: bool,
+    enabled_after_acknowledge: bool,
+    prescaler: i16
+}
+
+impl InterruptRequest {
+    pub fn new() -> Self {
+        InterruptRequest {
+            active: false,
+            latch: 0,
+            counter: 0,
+            cycle_mode: false,
+            enabled: false,
+            enabled_after_acknowledge: false,
+            prescaler: PRESCALER_PERIOD
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock_counter();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += PRESCALER_PERIOD;
+                self.clock_counter();
+            }
+        }
+    }
+
+    /// Bit 0: Enable after acknowledgement
+    /// Bit 1: Enable
+    /// Bit 2: Mode (0 = scanline, 1 = cycle)
+    pub fn write_control(&mut self, data: u8) {
+        self.enabled_after_acknowledge = data & 0b001 != 0;
+        self.enabled = data & 0b010 != 0;
+        self.cycle_mode = data & 0b100 != 0;
+        self.active = false;
+
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.active = false;
+        self.enabled = self.enabled_after_acknowledge;
+    }
+
+    pub fn reset(&mut self) {
+        self.active = false;
+        self.latch = 0;
+        self.counter = 0;
+        self.cycle_mode = false;
+        self.enabled = false;
+        self.enabled_after_acknowledge = false;
+        self.prescaler = PRESCALER_PERIOD;
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.active = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+}
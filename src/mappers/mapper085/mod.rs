@@ -0,0 +1,286 @@
+pub mod interrupt_request;
+
+#[cfg(feature = "vrc7")]
+pub mod opll;
+
+use serde::{Serialize, Deserialize};
+use crate::mappers::battery_backed_ram;
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper085SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::memory_sizes::*;
+use crate::cartridge::mirror::Mirror;
+
+const CHR_BANK_LENGTH: usize = 8;
+const PRG_BANK_LENGTH: usize = 3;
+const RAM_ADDRESS_MASK: u16 = 0x1FFF;
+
+/// Konami VRC7
+/// The VRC7a (Lagrange Point) uses A4 to select between the register pairs and the VRC7b (Tiny Toon Adventures 2) uses A3,
+/// so both are accepted.
+/// $8000, $8010: PRG banks for $8000 and $A000
+/// $9000: PRG bank for $C000
+/// $9010, $9030: Sound register select and data
+/// $A000-$D010: CHR banks, one 1KB bank per register
+/// $E000: Mirroring(1-0), PRG RAM enable(6), sound reset(7)
+/// $E010: IRQ latch
+/// $F000, $F010: IRQ control and acknowledge
+#[derive(Serialize, Deserialize)]
+pub struct Mapper085 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub interrupt_request: interrupt_request::InterruptRequest,
+    #[cfg(feature = "vrc7")]
+    pub sound: opll::Opll,
+    battery_backed_ram: bool,
+    chr_bank_registers: [u8; CHR_BANK_LENGTH],
+    control: u8,
+    prg_bank_registers: [u8; PRG_BANK_LENGTH],
+    ram: Vec<u8>
+}
+
+impl Mapper085 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool, ram_size: usize) -> Self {
+        Mapper085 {
+            prg_banks,
+            chr_banks,
+            interrupt_request: interrupt_request::InterruptRequest::new(),
+            #[cfg(feature = "vrc7")]
+            sound: opll::Opll::new(),
+            battery_backed_ram,
+            chr_bank_registers: [0; CHR_BANK_LENGTH],
+            control: 0,
+            prg_bank_registers: [0; PRG_BANK_LENGTH],
+            ram: vec![0; ram_size]
+        }
+    }
+
+    pub fn from(data: &Mapper085SaveData) -> Self {
+        Mapper085 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            interrupt_request: data.interrupt_request,
+            #[cfg(feature = "vrc7")]
+            sound: data.sound.clone(),
+            battery_backed_ram: data.battery_backed_ram,
+            chr_bank_registers: data.chr_bank_registers,
+            control: data.control,
+            prg_bank_registers: data.prg_bank_registers,
+            ram: data.ram.to_owned()
+        }
+    }
+}
+
+impl Mapper for Mapper085 {
+    fn reset(&mut self) {
+        self.interrupt_request.reset();
+        self.control = 0;
+
+        #[cfg(feature = "vrc7")]
+        self.sound.reset();
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        match self.control & 0b11 {
+            0 => Mirror::Vertical,
+            1 => Mirror::Horizontal,
+            2 => Mirror::OneScreenLow,
+            _ => Mirror::OneScreenHigh
+        }
+    }
+
+    fn irq_active(&self) -> bool {
+        self.interrupt_request.active
+    }
+
+    fn irq_clear(&mut self) {
+        self.interrupt_request.active = false;
+    }
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        match address {
+            0x6000..=0x7FFF => {
+                if !self.is_ram_enabled() {
+                    return MapperReadResult::none();
+                }
+
+                let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+                MapperReadResult::from_mapper_ram(self.ram[index])
+            },
+            0x8000..=0xDFFF => {
+                let bank = self.prg_bank_registers[((address - 0x8000) / KILOBYTES_8) as usize] & 0b11_1111;
+                MapperReadResult::from_cart_ram(self.get_prg_address(bank as u32, address))
+            },
+            0xE000..=0xFFFF => {
+                let last_bank = (self.prg_banks as u32) * 2 - 1;
+                MapperReadResult::from_cart_ram(self.get_prg_address(last_bank, address))
+            },
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        if let 0x6000..=0x7FFF = address {
+            if self.is_ram_enabled() {
+                let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+                self.ram[index] = data;
+            }
+
+            return MapperWriteResult::handled();
+        }
+
+        let high_register = address & 0x18 != 0;
+        match address & 0xF000 {
+            0x8000 => self.prg_bank_registers[if high_register { 1 } else { 0 }] = data,
+            0x9000 => self.write_sound(address, data),
+            0xA000..=0xD000 => {
+                let index = ((((address & 0xF000) - 0xA000) >> 11) as usize) + if high_register { 1 } else { 0 };
+                self.chr_bank_registers[index] = data;
+            },
+            0xE000 => {
+                if high_register {
+                    self.interrupt_request.latch = data;
+                } else {
+                    self.write_control(data);
+                }
+            },
+            0xF000 => {
+                if high_register {
+                    self.interrupt_request.acknowledge();
+                } else {
+                    self.interrupt_request.write_control(data);
+                }
+            },
+            _ => return MapperWriteResult::none()
+        }
+
+        MapperWriteResult::handled()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            0x0000..=0x1FFF => MapperReadResult::from_cart_ram(self.get_chr_address(address)),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > 0x1FFF || self.chr_banks != 0 {
+            return false;
+        }
+
+        *mapped_address = self.get_chr_address(address);
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, data: Vec<u8>) {
+        if !self.battery_backed_ram || data.len() != self.ram.len() {
+            return;
+        }
+
+        self.ram = data;
+    }
+
+    fn save_battery_backed_ram(&self, file_path: &str) {
+        if !self.battery_backed_ram {
+            return;
+        }
+
+        battery_backed_ram::save_battery_backed_ram(file_path, &self.ram);
+    }
+
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        let index = (address & RAM_ADDRESS_MASK) as usize % self.ram.len();
+        self.ram[index] = data;
+    }
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper085(Box::new(Mapper085SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            interrupt_request: self.interrupt_request,
+            #[cfg(feature = "vrc7")]
+            sound: self.sound.clone(),
+            battery_backed_ram: self.battery_backed_ram,
+            chr_bank_registers: self.chr_bank_registers,
+            control: self.control,
+            prg_bank_registers: self.prg_bank_registers,
+            ram: self.ram.to_owned()
+        }))
+    }
+
+    fn cpu_clock(&mut self) {
+        self.interrupt_request.clock();
+
+        #[cfg(feature = "vrc7")]
+        self.sound.clock();
+    }
+
+    #[cfg(feature = "vrc7")]
+    fn get_audio_sample(&self) -> f32 {
+        if self.is_sound_silenced() {
+            return 0.0;
+        }
+
+        self.sound.get_sample()
+    }
+}
+
+impl Mapper085 {
+    fn get_prg_address(&self, bank: u32, address: u16) -> u32 {
+        let bank_count = (self.prg_banks as u32) * 2;
+        (bank % bank_count) * (KILOBYTES_8 as u32) + ((address & KILOBYTES_8_MASK) as u32)
+    }
+
+    /// Boards without CHR ROM have 8KB of CHR RAM
+    fn get_chr_address(&self, address: u16) -> u32 {
+        let bank_count = if self.chr_banks == 0 { 8 } else { (self.chr_banks as u32) * 8 };
+        let bank = self.chr_bank_registers[(address / KILOBYTES_1) as usize] as u32;
+        (bank % bank_count) * (KILOBYTES_1 as u32) + ((address & KILOBYTES_1_MASK) as u32)
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.control & 0b0100_0000 != 0
+    }
+
+    #[cfg(feature = "vrc7")]
+    fn is_sound_silenced(&self) -> bool {
+        self.control & 0b1000_0000 != 0
+    }
+
+    fn write_control(&mut self, data: u8) {
+        self.control = data;
+
+        #[cfg(feature = "vrc7")]
+        {
+            if self.is_sound_silenced() {
+                self.sound.reset();
+            }
+        }
+    }
+
+    #[cfg(feature = "vrc7")]
+    fn write_sound(&mut self, address: u16, data: u8) {
+        match address & 0x0030 {
+            0x0000 => self.prg_bank_registers[2] = data,
+            0x0010 => self.sound.write_address(data),
+            0x0030 => self.sound.write_data(data),
+            _ => ()
+        }
+    }
+
+    #[cfg(not(feature = "vrc7"))]
+    fn write_sound(&mut self, address: u16, data: u8) {
+        if address & 0x0030 == 0 {
+            self.prg_bank_registers[2] = data;
+        }
+    }
+}
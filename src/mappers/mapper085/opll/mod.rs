@@ -0,0 +1,204 @@
+pub mod operator;
+pub mod patch;
+
+use serde::{Serialize, Deserialize};
+use std::f32::consts::PI;
+use operator::{Operator, get_key_scale, get_key_scale_level};
+use patch::{Patch, INSTRUMENTS};
+
+const AMPLITUDE_MODULATION_DEPTH: f32 = 4.8;
+const AMPLITUDE_MODULATION_FREQUENCY: f32 = 3.7;
+const CHANNEL_COUNT: usize = 6;
+const CUSTOM_PATCH_SIZE: usize = 8;
+const CYCLES_PER_SAMPLE: u8 = 36;
+const OUTPUT_SCALE: f32 = 0.15;
+const SAMPLE_RATE: f32 = 49_716.0;
+const SUSTAIN_RELEASE_RATE: u8 = 5;
+const UNSUSTAINED_RELEASE_RATE: u8 = 7;
+const VIBRATO_DEPTH: f32 = 0.0081;
+const VIBRATO_FREQUENCY: f32 = 6.4;
+
+/// The VRC7's sound chip is a cut down YM2413 (OPLL) with 6 two operator FM channels and its own set of instruments.
+/// It runs at 3.58MHz / 72, which is one sample every 36 CPU cycles.
+/// Registers:
+/// $00-$07: Custom instrument
+/// $10-$15: Channel frequency number (low 8 bits)
+/// $20-$25: Channel sustain(5), key on(4), block(3-1), frequency number high bit(0)
+/// $30-$35: Channel instrument(7-4), volume(3-0)
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Opll {
+    address: u8,
+    amplitude_modulation_phase: f32,
+    channels: [Channel; CHANNEL_COUNT],
+    custom_patch: [u8; CUSTOM_PATCH_SIZE],
+    cycle_counter: u8,
+    output: f32,
+    vibrato_phase: f32
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+struct Channel {
+    block: u8,
+    carrier: Operator,
+    frequency: u16,
+    instrument: u8,
+    key_on: bool,
+    modulator: Operator,
+    sustain: bool,
+    volume: u8
+}
+
+impl Opll {
+    pub fn new() -> Self {
+        Opll {
+            address: 0,
+            amplitude_modulation_phase: 0.0,
+            channels: [Channel::new(); CHANNEL_COUNT],
+            custom_patch: [0; CUSTOM_PATCH_SIZE],
+            cycle_counter: 0,
+            output: 0.0,
+            vibrato_phase: 0.0
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Opll::new();
+    }
+
+    pub fn write_address(&mut self, data: u8) {
+        self.address = data;
+    }
+
+    pub fn write_data(&mut self, data: u8) {
+        let address = self.address as usize;
+        match address {
+            0x00..=0x07 => self.custom_patch[address] = data,
+            0x10..=0x15 => {
+                let channel = &mut self.channels[address - 0x10];
+                channel.frequency = (channel.frequency & 0x100) | (data as u16);
+            },
+            0x20..=0x25 => self.channels[address - 0x20].write_control(data),
+            0x30..=0x35 => {
+                let channel = &mut self.channels[address - 0x30];
+                channel.instrument = data >> 4;
+                channel.volume = data & 0b1111;
+            },
+            _ => ()
+        }
+    }
+
+    pub fn clock(&mut self) {
+        self.cycle_counter += 1;
+        if self.cycle_counter < CYCLES_PER_SAMPLE {
+            return;
+        }
+
+        self.cycle_counter = 0;
+        self.amplitude_modulation_phase = (self.amplitude_modulation_phase + 2.0 * PI * AMPLITUDE_MODULATION_FREQUENCY / SAMPLE_RATE) % (2.0 * PI);
+        self.vibrato_phase = (self.vibrato_phase + 2.0 * PI * VIBRATO_FREQUENCY / SAMPLE_RATE) % (2.0 * PI);
+
+        let amplitude_modulation = AMPLITUDE_MODULATION_DEPTH * (1.0 - self.amplitude_modulation_phase.cos()) / 2.0;
+        let vibrato = 1.0 + VIBRATO_DEPTH * self.vibrato_phase.sin();
+
+        let mut output = 0.0;
+        for i in 0..CHANNEL_COUNT {
+            let patch = self.get_patch(self.channels[i].instrument);
+            output += self.channels[i].clock(&patch, amplitude_modulation, vibrato);
+        }
+
+        self.output = output * OUTPUT_SCALE;
+    }
+
+    pub fn get_sample(&self) -> f32 {
+        self.output
+    }
+
+    fn get_patch(&self, instrument: u8) -> Patch {
+        match instrument {
+            0 => Patch::new(&self.custom_patch),
+            _ => Patch::new(&INSTRUMENTS[(instrument - 1) as usize])
+        }
+    }
+}
+
+impl Channel {
+    fn new() -> Self {
+        Channel {
+            block: 0,
+            carrier: Operator::new(),
+            frequency: 0,
+            instrument: 0,
+            key_on: false,
+            modulator: Operator::new(),
+            sustain: false,
+            volume: 0
+        }
+    }
+
+    fn write_control(&mut self, data: u8) {
+        let key_on = data & 0b1_0000 != 0;
+        if key_on && !self.key_on {
+            self.modulator.key_on();
+            self.carrier.key_on();
+        } else if !key_on && self.key_on {
+            // Only the carrier is released, the modulator keeps going so the tone doesn't change as it fades out
+            self.carrier.key_off();
+        }
+
+        self.key_on = key_on;
+        self.sustain = data & 0b10_0000 != 0;
+        self.block = (data >> 1) & 0b111;
+        self.frequency = (((data & 0b1) as u16) << 8) | (self.frequency & 0xFF);
+    }
+
+    fn clock(&mut self, patch: &Patch, amplitude_modulation: f32, vibrato: f32) -> f32 {
+        let modulator_patch = &patch.modulator;
+        let carrier_patch = &patch.carrier;
+
+        let modulator_key_scale = get_key_scale(modulator_patch, self.frequency, self.block);
+        self.modulator.clock_envelope(modulator_patch, modulator_key_scale, modulator_patch.release);
+        self.modulator.clock_phase(modulator_patch, self.frequency, self.block, vibrato);
+
+        let carrier_key_scale = get_key_scale(carrier_patch, self.frequency, self.block);
+        let release_rate = self.get_release_rate(carrier_patch.sustained, carrier_patch.release);
+        self.carrier.clock_envelope(carrier_patch, carrier_key_scale, release_rate);
+        self.carrier.clock_phase(carrier_patch, self.frequency, self.block, vibrato);
+
+        if self.carrier.is_silent() {
+            return 0.0;
+        }
+
+        let feedback = if patch.feedback == 0 {
+            0.0
+        } else {
+            let average = (self.modulator.output + self.modulator.previous_output) / 2.0;
+            average * 4.0 * PI / ((1 << (7 - patch.feedback)) as f32)
+        };
+
+        let modulator_attenuation =
+            (modulator_patch.total_level as f32) * 0.75 +
+            get_key_scale_level(modulator_patch, self.frequency, self.block) +
+            if modulator_patch.amplitude_modulation { amplitude_modulation } else { 0.0 };
+
+        let modulation = self.modulator.calculate(feedback, modulator_attenuation, modulator_patch.rectified);
+
+        let carrier_attenuation =
+            (self.volume as f32) * 3.0 +
+            get_key_scale_level(carrier_patch, self.frequency, self.block) +
+            if carrier_patch.amplitude_modulation { amplitude_modulation } else { 0.0 };
+
+        self.carrier.calculate(modulation * 8.0 * PI, carrier_attenuation, carrier_patch.rectified)
+    }
+
+    fn get_release_rate(&self, sustained: bool, release: u8) -> u8 {
+        if self.key_on {
+            release
+        } else if self.sustain {
+            SUSTAIN_RELEASE_RATE
+        } else if sustained {
+            release
+        } else {
+            UNSUSTAINED_RELEASE_RATE
+        }
+    }
+}
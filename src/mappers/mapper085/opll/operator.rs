@@ -0,0 +1,159 @@
+use serde::{Serialize, Deserialize};
+use std::f32::consts::PI;
+use super::patch::OperatorPatch;
+
+pub const MAX_ATTENUATION: f32 = 48.0;
+
+const ATTACK_SCALE: f32 = 3.6e-5;
+const DECAY_SCALE: f32 = 9.65e-5;
+const KEY_SCALE_LEVEL_FACTORS: [f32; 4] = [0.0, 0.25, 0.5, 1.0];
+const MULTIPLIERS: [u32; 16] = [1, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 20, 24, 24, 30, 30];
+const PHASE_BITS: u32 = 19;
+
+/// Attenuation in dB at 6dB/octave for the upper 4 bits of the frequency number, in the highest block
+const KEY_SCALE_LEVELS: [f32; 16] = [
+    0.0, 18.0, 24.0, 27.75, 30.0, 32.25, 33.75, 35.25, 36.0, 37.5, 38.25, 39.0, 39.75, 40.5, 41.25, 42.0
+];
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum EnvelopeState {
+    Attack,
+    Decay,
+    Sustain,
+    Release
+}
+
+/// A single FM operator: a sine wave phase generator with an ADSR envelope.
+/// The envelope is kept as an attenuation in dB, where 0 is full volume and 48 is silent.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Operator {
+    pub output: f32,
+    pub previous_output: f32,
+    envelope: f32,
+    phase: f64,
+    state: EnvelopeState
+}
+
+impl Operator {
+    pub fn new() -> Self {
+        Operator {
+            output: 0.0,
+            previous_output: 0.0,
+            envelope: MAX_ATTENUATION,
+            phase: 0.0,
+            state: EnvelopeState::Release
+        }
+    }
+
+    pub fn key_on(&mut self) {
+        self.phase = 0.0;
+        self.state = EnvelopeState::Attack;
+    }
+
+    pub fn key_off(&mut self) {
+        self.state = EnvelopeState::Release;
+    }
+
+    pub fn clock_phase(&mut self, patch: &OperatorPatch, frequency: u16, block: u8, vibrato: f32) {
+        let increment = (((frequency as u32) * MULTIPLIERS[patch.multiplier as usize]) << block) >> 1;
+        let mut increment = (increment as f64) / ((1 << PHASE_BITS) as f64);
+        if patch.vibrato {
+            increment *= vibrato as f64;
+        }
+
+        self.phase = (self.phase + increment).fract();
+    }
+
+    pub fn clock_envelope(&mut self, patch: &OperatorPatch, key_scale: u8, release_rate: u8) {
+        match self.state {
+            EnvelopeState::Attack => {
+                let rate = get_effective_rate(patch.attack, key_scale);
+                if rate >= 60 {
+                    self.envelope = 0.0;
+                } else if rate > 0 {
+                    let coefficient = (get_rate_factor(rate) * ATTACK_SCALE).min(1.0);
+                    self.envelope -= (self.envelope + 0.375) * coefficient;
+                }
+
+                if self.envelope <= 0.0 {
+                    self.envelope = 0.0;
+                    self.state = EnvelopeState::Decay;
+                }
+            },
+            EnvelopeState::Decay => {
+                let sustain_level = (patch.sustain_level as f32) * 3.0;
+                self.envelope += get_decay_step(patch.decay, key_scale);
+                if self.envelope >= sustain_level {
+                    self.envelope = sustain_level;
+                    self.state = EnvelopeState::Sustain;
+                }
+            },
+            EnvelopeState::Sustain => {
+                // Percussive sounds keep decaying at the release rate even while the key is held
+                if !patch.sustained {
+                    self.envelope += get_decay_step(patch.release, key_scale);
+                }
+            },
+            EnvelopeState::Release => {
+                self.envelope += get_decay_step(release_rate, key_scale);
+            }
+        }
+
+        self.envelope = self.envelope.min(MAX_ATTENUATION);
+    }
+
+    /// Calculates the output of the operator, modulation is a phase offset in radians
+    pub fn calculate(&mut self, modulation: f32, attenuation: f32, rectified: bool) -> f32 {
+        let total_attenuation = self.envelope + attenuation;
+        let mut sample = if total_attenuation >= MAX_ATTENUATION {
+            0.0
+        } else {
+            let angle = (self.phase as f32) * 2.0 * PI + modulation;
+            angle.sin() * 10f32.powf(-total_attenuation / 20.0)
+        };
+
+        if rectified && sample < 0.0 {
+            sample = 0.0;
+        }
+
+        self.previous_output = self.output;
+        self.output = sample;
+        sample
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.state == EnvelopeState::Release && self.envelope >= MAX_ATTENUATION
+    }
+}
+
+pub fn get_key_scale_level(patch: &OperatorPatch, frequency: u16, block: u8) -> f32 {
+    let level = KEY_SCALE_LEVELS[(frequency >> 5) as usize] - 6.0 * ((7 - block) as f32);
+    level.max(0.0) * KEY_SCALE_LEVEL_FACTORS[patch.key_scale_level as usize]
+}
+
+/// The key scale is made up of the block and the highest bit of the frequency number
+pub fn get_key_scale(patch: &OperatorPatch, frequency: u16, block: u8) -> u8 {
+    let key_scale = (block << 1) | ((frequency >> 8) as u8);
+    if patch.key_scale_rate { key_scale } else { key_scale >> 2 }
+}
+
+fn get_effective_rate(rate: u8, key_scale: u8) -> u8 {
+    if rate == 0 {
+        return 0;
+    }
+
+    (rate * 4 + key_scale).min(63)
+}
+
+fn get_rate_factor(effective_rate: u8) -> f32 {
+    (1.0 + ((effective_rate & 0b11) as f32) * 0.25) * ((1 << (effective_rate >> 2)) as f32)
+}
+
+fn get_decay_step(rate: u8, key_scale: u8) -> f32 {
+    let effective_rate = get_effective_rate(rate, key_scale);
+    if effective_rate == 0 {
+        return 0.0;
+    }
+
+    get_rate_factor(effective_rate) * DECAY_SCALE
+}
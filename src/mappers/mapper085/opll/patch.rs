@@ -0,0 +1,79 @@
+/// The 15 built-in VRC7 instruments, instrument 0 is the user defined patch in registers $00-$07
+pub const INSTRUMENTS: [[u8; 8]; 15] = [
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27], // Buzzy bell
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12], // Guitar
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12], // Wurly
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27], // Flute
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28], // Clarinet
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4], // Synth
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07], // Trumpet
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17], // Organ
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01], // Bells
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02], // Vibes
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12], // Vibraphone
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16], // Tutti
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02], // Fretless
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6], // Synth bass
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06]  // Sweep
+];
+
+/// Patch layout:
+/// $00: Modulator AM(7), vibrato(6), sustained(5), key scale rate(4), multiplier(3-0)
+/// $01: Carrier AM(7), vibrato(6), sustained(5), key scale rate(4), multiplier(3-0)
+/// $02: Modulator key scale level(7-6), total level(5-0)
+/// $03: Carrier key scale level(7-6), carrier rectified(4), modulator rectified(3), feedback(2-0)
+/// $04: Modulator attack(7-4), decay(3-0)
+/// $05: Carrier attack(7-4), decay(3-0)
+/// $06: Modulator sustain level(7-4), release(3-0)
+/// $07: Carrier sustain level(7-4), release(3-0)
+#[derive(Clone, Copy, Debug)]
+pub struct Patch {
+    pub modulator: OperatorPatch,
+    pub carrier: OperatorPatch,
+    pub feedback: u8
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OperatorPatch {
+    pub amplitude_modulation: bool,
+    pub vibrato: bool,
+    pub sustained: bool,
+    pub key_scale_rate: bool,
+    pub multiplier: u8,
+    pub key_scale_level: u8,
+    pub total_level: u8,
+    pub rectified: bool,
+    pub attack: u8,
+    pub decay: u8,
+    pub sustain_level: u8,
+    pub release: u8
+}
+
+impl Patch {
+    pub fn new(data: &[u8; 8]) -> Self {
+        Patch {
+            modulator: OperatorPatch::new(data[0], data[2] >> 6, data[2] & 0b11_1111, data[3] & 0b1000 != 0, data[4], data[6]),
+            carrier: OperatorPatch::new(data[1], data[3] >> 6, 0, data[3] & 0b1_0000 != 0, data[5], data[7]),
+            feedback: data[3] & 0b111
+        }
+    }
+}
+
+impl OperatorPatch {
+    fn new(flags: u8, key_scale_level: u8, total_level: u8, rectified: bool, attack_decay: u8, sustain_release: u8) -> Self {
+        OperatorPatch {
+            amplitude_modulation: flags & 0b1000_0000 != 0,
+            vibrato: flags & 0b0100_0000 != 0,
+            sustained: flags & 0b0010_0000 != 0,
+            key_scale_rate: flags & 0b0001_0000 != 0,
+            multiplier: flags & 0b1111,
+            key_scale_level,
+            total_level,
+            rectified,
+            attack: attack_decay >> 4,
+            decay: attack_decay & 0b1111,
+            sustain_level: sustain_release >> 4,
+            release: sustain_release & 0b1111
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper140SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::memory_sizes::*;
+use crate::cartridge::mirror::Mirror;
+
+/// Jaleco JF-11/JF-14
+/// $6000-$7FFF: Select 8KB CHR bank(3-0), select 32KB PRG bank(5-4)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper140 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    battery_backed_ram: bool,
+    chr_bank: u8,
+    prg_bank: u8
+}
+
+impl Mapper140 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool) -> Self {
+        Mapper140 {
+            prg_banks,
+            chr_banks,
+            battery_backed_ram,
+            chr_bank: 0,
+            prg_bank: 0
+        }
+    }
+
+    pub fn from(data: &Mapper140SaveData) -> Self {
+        Mapper140 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            battery_backed_ram: data.battery_backed_ram,
+            chr_bank: data.chr_bank,
+            prg_bank: data.prg_bank
+        }
+    }
+}
+
+impl Mapper for Mapper140 {
+    fn reset(&mut self) {
+        self.chr_bank = 0;
+        self.prg_bank = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        Mirror::Hardware
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        if address < CPU_MIN_ADDRESS {
+            return MapperReadResult::none();
+        }
+
+        let bank_count = ((self.prg_banks / 2) as u32).max(1);
+        let bank = (self.prg_bank as u32) % bank_count;
+        MapperReadResult::from_cart_ram(bank * (KILOBYTES_32 as u32) + ((address & KILOBYTES_32_MASK) as u32))
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        if let 0x6000..=0x7FFF = address {
+            self.chr_bank = data & 0b1111;
+            self.prg_bank = (data >> 4) & 0b11;
+            return MapperWriteResult::handled();
+        }
+
+        MapperWriteResult::none()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => {
+                let bank = (self.chr_bank as u32) % (self.chr_banks.max(1) as u32);
+                MapperReadResult::from_cart_ram(bank * (KILOBYTES_8 as u32) + ((address & KILOBYTES_8_MASK) as u32))
+            },
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, _address: u16, _mapped_address: &mut u32, _data: u8) -> bool {
+        false
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper140(Mapper140SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            battery_backed_ram: self.battery_backed_ram,
+            chr_bank: self.chr_bank,
+            prg_bank: self.prg_bank
+        })
+    }
+}
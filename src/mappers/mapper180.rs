@@ -0,0 +1,113 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, Mapper180SaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use crate::addresses::mappers::*;
+use crate::memory_sizes::{KILOBYTES_16, KILOBYTES_16_MASK};
+use crate::cartridge::mirror::Mirror;
+
+const FIXED_BANK_MAX: u16 = 0xBFFF;
+const SWITCHABLE_ROM_BANK_MIN: u16 = 0xC000;
+
+/// UNROM with the banks swapped around, used by Crazy Climber
+/// $8000-$FFFF: Select 16KB PRG bank at $C000(2-0), $8000 is fixed to the first bank
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper180 {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    battery_backed_ram: bool,
+    prg_bank: u8
+}
+
+impl Mapper180 {
+    pub fn new(prg_banks: u8, chr_banks: u8, battery_backed_ram: bool) -> Self {
+        Mapper180 {
+            prg_banks,
+            chr_banks,
+            battery_backed_ram,
+            prg_bank: 0
+        }
+    }
+
+    pub fn from(data: &Mapper180SaveData) -> Self {
+        Mapper180 {
+            prg_banks: data.prg_banks,
+            chr_banks: data.chr_banks,
+            battery_backed_ram: data.battery_backed_ram,
+            prg_bank: data.prg_bank
+        }
+    }
+}
+
+impl Mapper for Mapper180 {
+    fn reset(&mut self) {
+        self.prg_bank = 0;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        self.prg_banks
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        self.chr_banks
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        Mirror::Hardware
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        let bank = match address {
+            CPU_MIN_ADDRESS..=FIXED_BANK_MAX => 0,
+            SWITCHABLE_ROM_BANK_MIN..=CPU_MAX_ADDRESS => (self.prg_bank as u32) % (self.prg_banks.max(1) as u32),
+            _ => return MapperReadResult::none()
+        };
+
+        MapperReadResult::from_cart_ram(bank * (KILOBYTES_16 as u32) + ((address & KILOBYTES_16_MASK) as u32))
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        if address >= CPU_MIN_ADDRESS {
+            self.prg_bank = data & 0b111;
+            return MapperWriteResult::handled();
+        }
+
+        MapperWriteResult::none()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS || self.chr_banks != 0 {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Mapper180(Mapper180SaveData {
+            prg_banks: self.prg_banks,
+            chr_banks: self.chr_banks,
+            battery_backed_ram: self.battery_backed_ram,
+            prg_bank: self.prg_bank
+        })
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        true
+    }
+}
@@ -1,6 +1,12 @@
 use serde::{Serialize, Deserialize};
 use crate::cartridge::mirror::Mirror;
+use crate::region::Region;
 use super::mapper001::*;
+use super::mapper019;
+use super::mapper085;
+use super::fds;
+#[cfg(feature = "vrc7")]
+use super::mapper085::opll;
 use super::mapper004::*;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -11,7 +17,18 @@ pub enum MapperSaveData {
     Mapper002(Mapper002SaveData),
     Mapper003(Mapper003SaveData),
     Mapper004(Mapper004SaveData),
-    Mapper066(Mapper066SaveData)
+    Mapper007(Mapper007SaveData),
+    Mapper011(Mapper011SaveData),
+    Mapper019(Mapper019SaveData),
+    Mapper034(Mapper034SaveData),
+    Mapper066(Mapper066SaveData),
+    Mapper071(Mapper071SaveData),
+    Mapper079(Mapper079SaveData),
+    Mapper085(Box<Mapper085SaveData>),
+    Mapper140(Mapper140SaveData),
+    Mapper180(Mapper180SaveData),
+    Fds(FdsSaveData),
+    Nsf(Box<NsfSaveData>)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -59,6 +76,52 @@ pub struct Mapper004SaveData {
     pub battery_backed_ram: bool,
     pub mirror: Mirror,
     pub prg_ram_protect: prg_ram_protect::PrgRamProtect,
+    pub ram: Vec<u8>,
+    pub variant: variant::Variant
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper007SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub bus_conflicts: bool,
+    pub name_table: u8,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper011SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub chr_bank: u8,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper019SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub interrupt_request: mapper019::interrupt_request::InterruptRequest,
+    pub sound: mapper019::sound::Sound,
+    pub battery_backed_ram: bool,
+    pub chr_bank_registers: [u8; 8],
+    pub name_table_registers: [u8; 4],
+    pub prg_bank_registers: [u8; 3],
+    pub ram: Vec<u8>,
+    pub write_protect: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper034SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub chr_bank_high: u8,
+    pub chr_bank_low: u8,
+    pub nina_001: bool,
+    pub prg_bank: u8,
     pub ram: Vec<u8>
 }
 
@@ -69,4 +132,83 @@ pub struct Mapper066SaveData {
     pub battery_backed_ram: bool,
     pub chr_bank: u8,
     pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper071SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub mirroring_control: bool,
+    pub name_table: u8,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper079SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub chr_bank: u8,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper085SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub interrupt_request: mapper085::interrupt_request::InterruptRequest,
+    #[cfg(feature = "vrc7")]
+    pub sound: mapper085::opll::Opll,
+    pub battery_backed_ram: bool,
+    pub chr_bank_registers: [u8; 8],
+    pub control: u8,
+    pub prg_bank_registers: [u8; 3],
+    pub ram: Vec<u8>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper140SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub chr_bank: u8,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mapper180SaveData {
+    pub prg_banks: u8,
+    pub chr_banks: u8,
+    pub battery_backed_ram: bool,
+    pub prg_bank: u8
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FdsSaveData {
+    pub interrupt_request: fds::interrupt_request::InterruptRequest,
+    pub drive: fds::drive::Drive,
+    pub sound: fds::sound::Sound,
+    pub disk_registers_enabled: bool,
+    pub external_port: u8,
+    pub sound_registers_enabled: bool
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NsfSaveData {
+    pub fds_sound: Option<fds::sound::Sound>,
+    pub n163_sound: Option<mapper019::sound::Sound>,
+    #[cfg(feature = "vrc7")]
+    pub vrc7_sound: Option<opll::Opll>,
+    pub banks: [u8; 10],
+    pub driver: Vec<u8>,
+    pub fds: bool,
+    pub initial_banks: [u8; 10],
+    pub play_counter: u32,
+    pub play_period: u32,
+    pub play_pending: bool,
+    pub ram: Vec<u8>,
+    pub region: Region,
+    pub rom: Vec<u8>,
+    pub song: u8
 }
\ No newline at end of file
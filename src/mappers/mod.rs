@@ -1,10 +1,21 @@
 pub mod battery_backed_ram;
+pub mod fds;
 pub mod mapper;
 pub mod mapper000;
 pub mod mapper001;
 pub mod mapper002;
 pub mod mapper003;
 pub mod mapper004;
+pub mod mapper007;
+pub mod mapper011;
+pub mod mapper019;
+pub mod mapper034;
 pub mod mapper066;
+pub mod mapper071;
+pub mod mapper079;
+pub mod mapper085;
+pub mod mapper140;
+pub mod mapper180;
 pub mod mapper_save_data;
-pub mod mapper_results;
\ No newline at end of file
+pub mod mapper_results;
+pub mod nsf;
\ No newline at end of file
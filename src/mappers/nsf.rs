@@ -0,0 +1,369 @@
+use serde::{Serialize, Deserialize};
+
+use super::mapper::{Mapper};
+use super::mapper_save_data::{MapperSaveData, NsfSaveData};
+use super::mapper_results::{MapperReadResult, MapperWriteResult};
+use super::fds;
+use super::mapper019;
+#[cfg(feature = "vrc7")]
+use super::mapper085::opll;
+use crate::addresses::mappers::*;
+use crate::cartridge::mirror::Mirror;
+use crate::memory_sizes::*;
+use crate::nsf;
+use crate::region::Region;
+
+const BANK_COUNT: usize = 10;
+const BANK_REGISTER_MIN: u16 = 0x5FF6;
+const BANK_REGISTER_MAX: u16 = 0x5FFF;
+const RAM_ADDRESS: u16 = 0x6000;
+const FDS_RAM_MAX_ADDRESS: u16 = 0xDFFF;
+const DRIVER_ADDRESS: u16 = 0x4100;
+const SONG_REGISTER: u16 = 0x41F0;
+const REGION_REGISTER: u16 = 0x41F1;
+const PLAY_REGISTER: u16 = 0x41F2;
+const NMI_VECTOR: u16 = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const RTI: u8 = 0x40;
+
+/// Not a real board, this is what an NSF player cartridge looks like to the CPU.
+/// Since there's no game to call INIT and PLAY, a small driver lives at $4100 and the reset vector points to it.
+/// It clears the APU, calls INIT with the song and region from $41F0/$41F1 and then waits on $41F2,
+/// which reads back non zero once each time PLAY is due.
+/// $5FF6-$5FF7: Select 4KB bank at $6000 and $7000, only used by FDS NSFs
+/// $5FF8-$5FFF: Select 4KB bank at $8000-$F000
+/// $6000-$7FFF: 8KB RAM, FDS NSFs have RAM all the way up to $DFFF which the banks get copied into
+/// VRC7, FDS and Namco 163 sound is supported, NSFs that use VRC6, MMC5 or Sunsoft 5B sound play without it.
+#[derive(Serialize, Deserialize)]
+pub struct Nsf {
+    pub fds_sound: Option<fds::sound::Sound>,
+    pub n163_sound: Option<mapper019::sound::Sound>,
+    #[cfg(feature = "vrc7")]
+    pub vrc7_sound: Option<opll::Opll>,
+    banks: [u8; BANK_COUNT],
+    driver: Vec<u8>,
+    fds: bool,
+    initial_banks: [u8; BANK_COUNT],
+    play_counter: u32,
+    play_period: u32,
+    play_pending: bool,
+    ram: Vec<u8>,
+    region: Region,
+    rom: Vec<u8>,
+    song: u8
+}
+
+impl Nsf {
+    pub fn new(file: &nsf::NsfFile) -> Self {
+        let fds = file.has_sound_chip(nsf::SOUND_CHIP_FDS);
+        let base_address = if fds { RAM_ADDRESS } else { CPU_MIN_ADDRESS };
+        let mut initial_banks = [0; BANK_COUNT];
+        let padding = if file.is_bank_switched() {
+            initial_banks[2..].copy_from_slice(&file.bank_switch);
+            if fds {
+                initial_banks[0] = file.bank_switch[6];
+                initial_banks[1] = file.bank_switch[7];
+            }
+
+            (file.load_address & KILOBYTES_4_MASK) as usize
+        } else {
+            let first_slot = ((base_address - RAM_ADDRESS) / KILOBYTES_4) as usize;
+            for (bank, slot) in initial_banks.iter_mut().skip(first_slot).enumerate() {
+                *slot = bank as u8;
+            }
+
+            file.load_address.saturating_sub(base_address) as usize
+        };
+
+        let mut rom = vec![0; padding];
+        rom.extend_from_slice(&file.data);
+        let bank_size = KILOBYTES_4 as usize;
+        rom.resize(rom.len().div_ceil(bank_size).max(1) * bank_size, 0);
+
+        let ram_size = if fds { (FDS_RAM_MAX_ADDRESS - RAM_ADDRESS + 1) as usize } else { KILOBYTES_8 as usize };
+        let mut nsf = Nsf {
+            fds_sound: if fds { Some(fds::sound::Sound::new()) } else { None },
+            n163_sound: if file.has_sound_chip(nsf::SOUND_CHIP_N163) { Some(mapper019::sound::Sound::new()) } else { None },
+            #[cfg(feature = "vrc7")]
+            vrc7_sound: if file.has_sound_chip(nsf::SOUND_CHIP_VRC7) { Some(opll::Opll::new()) } else { None },
+            banks: initial_banks,
+            driver: Nsf::get_driver(file.init_address, file.play_address),
+            fds,
+            initial_banks,
+            play_counter: 0,
+            play_period: ((file.get_play_speed() as f64) * (file.region.get_cpu_frequency() as f64) / 1_000_000.0) as u32,
+            play_pending: false,
+            ram: vec![0; ram_size],
+            region: file.region,
+            rom,
+            song: file.starting_song
+        };
+
+        nsf.reset();
+        nsf
+    }
+
+    pub fn from(data: &NsfSaveData) -> Self {
+        Nsf {
+            fds_sound: data.fds_sound.clone(),
+            n163_sound: data.n163_sound.clone(),
+            #[cfg(feature = "vrc7")]
+            vrc7_sound: data.vrc7_sound.clone(),
+            banks: data.banks,
+            driver: data.driver.to_owned(),
+            fds: data.fds,
+            initial_banks: data.initial_banks,
+            play_counter: data.play_counter,
+            play_period: data.play_period,
+            play_pending: data.play_pending,
+            ram: data.ram.to_owned(),
+            region: data.region,
+            rom: data.rom.to_owned(),
+            song: data.song
+        }
+    }
+
+    /// SEI, CLD, reset the stack, silence the APU, JSR INIT, then loop on $41F2 calling JSR PLAY
+    fn get_driver(init_address: u16, play_address: u16) -> Vec<u8> {
+        let [init_low, init_high] = init_address.to_le_bytes();
+        let [play_low, play_high] = play_address.to_le_bytes();
+        let [song_low, song_high] = SONG_REGISTER.to_le_bytes();
+        let [region_low, region_high] = REGION_REGISTER.to_le_bytes();
+        let [play_register_low, play_register_high] = PLAY_REGISTER.to_le_bytes();
+        let mut driver = vec![
+            0x78,                                       // SEI
+            0xD8,                                       // CLD
+            0xA2, 0xFF,                                 // LDX #$FF
+            0x9A,                                       // TXS
+            0xA9, 0x00,                                 // LDA #$00
+            0xA2, 0x13,                                 // LDX #$13
+            0x9D, 0x00, 0x40,                           // STA $4000,X
+            0xCA,                                       // DEX
+            0x10, 0xFA,                                 // BPL -6
+            0x8D, 0x15, 0x40,                           // STA $4015
+            0xA9, 0x0F,                                 // LDA #$0F
+            0x8D, 0x15, 0x40,                           // STA $4015
+            0xA9, 0x40,                                 // LDA #$40
+            0x8D, 0x17, 0x40,                           // STA $4017
+            0xAD, song_low, song_high,                  // LDA song
+            0xAE, region_low, region_high,              // LDX region
+            0x20, init_low, init_high,                  // JSR INIT
+            0xAD, play_register_low, play_register_high // LDA play pending
+        ];
+
+        let idle_address = DRIVER_ADDRESS + (driver.len() as u16) - 3;
+        let [idle_low, idle_high] = idle_address.to_le_bytes();
+        driver.extend_from_slice(&[
+            0xF0, 0xFB,                                 // BEQ -5
+            0x20, play_low, play_high,                  // JSR PLAY
+            0x4C, idle_low, idle_high,                  // JMP idle
+            RTI
+        ]);
+
+        driver
+    }
+
+    fn get_rom_address(&self, slot: usize, address: u16) -> usize {
+        let bank_count = self.rom.len() / (KILOBYTES_4 as usize);
+        (self.banks[slot] as usize % bank_count) * (KILOBYTES_4 as usize) + ((address & KILOBYTES_4_MASK) as usize)
+    }
+
+    /// FDS NSFs run from RAM, so selecting a bank copies it in
+    fn write_bank(&mut self, slot: usize, data: u8) {
+        self.banks[slot] = data;
+        if self.fds && slot < 8 {
+            let start = self.get_rom_address(slot, 0);
+            let ram_start = slot * (KILOBYTES_4 as usize);
+            let bank = self.rom[start..(start + KILOBYTES_4 as usize)].to_vec();
+            self.ram[ram_start..(ram_start + KILOBYTES_4 as usize)].copy_from_slice(&bank);
+        }
+    }
+
+    /// Reset goes to the driver, NMI and IRQ go straight to an RTI
+    fn read_vector(&self, address: u16) -> u8 {
+        let rti_address = DRIVER_ADDRESS + (self.driver.len() as u16) - 1;
+        let vector = if address & 0b110 == RESET_VECTOR & 0b110 { DRIVER_ADDRESS } else { rti_address };
+        if address & 1 == 0 { vector as u8 } else { (vector >> 8) as u8 }
+    }
+}
+
+impl Mapper for Nsf {
+    fn reset(&mut self) {
+        self.ram.iter_mut().for_each(|b| *b = 0);
+        for slot in 0..BANK_COUNT {
+            self.write_bank(slot, self.initial_banks[slot]);
+        }
+
+        if let Some(ref mut sound) = self.fds_sound {
+            sound.reset();
+        }
+
+        if let Some(ref mut sound) = self.n163_sound {
+            *sound = mapper019::sound::Sound::new();
+        }
+
+        #[cfg(feature = "vrc7")]
+        {
+            if let Some(ref mut sound) = self.vrc7_sound {
+                sound.reset();
+            }
+        }
+
+        self.play_counter = 0;
+        self.play_pending = false;
+    }
+
+    fn get_prg_banks(&self) -> u8 {
+        0
+    }
+
+    fn get_chr_banks(&self) -> u8 {
+        0
+    }
+
+    fn get_mirror(&self) -> Mirror {
+        Mirror::Hardware
+    }
+
+    fn irq_active(&self) -> bool { false }
+    fn irq_clear(&mut self) {}
+
+    fn cpu_map_read(&mut self, address: u16) -> MapperReadResult {
+        let data = match address {
+            SONG_REGISTER => self.song,
+            REGION_REGISTER => (self.region == Region::Pal) as u8,
+            PLAY_REGISTER => {
+                let play_pending = self.play_pending;
+                self.play_pending = false;
+                play_pending as u8
+            },
+            DRIVER_ADDRESS..=0x41FF => match self.driver.get((address - DRIVER_ADDRESS) as usize) {
+                Some(data) => *data,
+                None => 0
+            },
+            0x4040..=0x407F if self.fds_sound.is_some() => self.fds_sound.as_ref().unwrap().read_wave_table(address),
+            0x4090 if self.fds_sound.is_some() => self.fds_sound.as_ref().unwrap().read_volume_gain(),
+            0x4092 if self.fds_sound.is_some() => self.fds_sound.as_ref().unwrap().read_mod_gain(),
+            0x4800..=0x4FFF if self.n163_sound.is_some() => self.n163_sound.as_mut().unwrap().read_data(),
+            NMI_VECTOR..=CPU_MAX_ADDRESS => self.read_vector(address),
+            RAM_ADDRESS..=0x7FFF => self.ram[(address - RAM_ADDRESS) as usize],
+            CPU_MIN_ADDRESS..=FDS_RAM_MAX_ADDRESS if self.fds => self.ram[(address - RAM_ADDRESS) as usize],
+            CPU_MIN_ADDRESS..=CPU_MAX_ADDRESS => {
+                let slot = ((address - RAM_ADDRESS) / KILOBYTES_4) as usize;
+                self.rom[self.get_rom_address(slot, address)]
+            },
+            _ => return MapperReadResult::none()
+        };
+
+        MapperReadResult::from_mapper_ram(data)
+    }
+
+    fn cpu_map_write(&mut self, address: u16, data: u8) -> MapperWriteResult {
+        match address {
+            BANK_REGISTER_MIN..=BANK_REGISTER_MAX => self.write_bank((address - BANK_REGISTER_MIN) as usize, data),
+            0x4040..=0x407F if self.fds_sound.is_some() => self.fds_sound.as_mut().unwrap().write_wave_table(address, data),
+            0x4080..=0x408A if self.fds_sound.is_some() => self.fds_sound.as_mut().unwrap().write(address, data),
+            0x4800..=0x4FFF if self.n163_sound.is_some() => self.n163_sound.as_mut().unwrap().write_data(data),
+            0xF800..=0xFFFF if self.n163_sound.is_some() => self.n163_sound.as_mut().unwrap().write_address(data),
+            #[cfg(feature = "vrc7")]
+            0x9010 if self.vrc7_sound.is_some() => self.vrc7_sound.as_mut().unwrap().write_address(data),
+            #[cfg(feature = "vrc7")]
+            0x9030 if self.vrc7_sound.is_some() => self.vrc7_sound.as_mut().unwrap().write_data(data),
+            RAM_ADDRESS..=0x7FFF => self.ram[(address - RAM_ADDRESS) as usize] = data,
+            CPU_MIN_ADDRESS..=FDS_RAM_MAX_ADDRESS if self.fds => self.ram[(address - RAM_ADDRESS) as usize] = data,
+            _ => return MapperWriteResult::none()
+        }
+
+        MapperWriteResult::handled()
+    }
+
+    fn ppu_map_read(&self, address: u16) -> MapperReadResult {
+        match address {
+            PPU_MIN_ADDRESS..=PPU_MAX_ADDRESS => MapperReadResult::from_cart_ram(address as u32),
+            _ => MapperReadResult::none()
+        }
+    }
+
+    fn ppu_map_write(&mut self, address: u16, mapped_address: &mut u32, _data: u8) -> bool {
+        if address > PPU_MAX_ADDRESS {
+            return false;
+        }
+
+        *mapped_address = address as u32;
+        true
+    }
+
+    fn load_battery_backed_ram(&mut self, _data: Vec<u8>) {}
+    fn save_battery_backed_ram(&self, _file_path: &str) {}
+
+    fn poke_ram(&mut self, address: u16, data: u8) {
+        self.ram[(address - RAM_ADDRESS) as usize] = data;
+    }
+
+    fn save_state(&self) -> MapperSaveData {
+        MapperSaveData::Nsf(Box::new(NsfSaveData {
+            fds_sound: self.fds_sound.clone(),
+            n163_sound: self.n163_sound.clone(),
+            #[cfg(feature = "vrc7")]
+            vrc7_sound: self.vrc7_sound.clone(),
+            banks: self.banks,
+            driver: self.driver.to_owned(),
+            fds: self.fds,
+            initial_banks: self.initial_banks,
+            play_counter: self.play_counter,
+            play_period: self.play_period,
+            play_pending: self.play_pending,
+            ram: self.ram.to_owned(),
+            region: self.region,
+            rom: self.rom.to_owned(),
+            song: self.song
+        }))
+    }
+
+    fn cpu_clock(&mut self) {
+        self.play_counter += 1;
+        if self.play_counter >= self.play_period {
+            self.play_counter = 0;
+            self.play_pending = true;
+        }
+
+        if let Some(ref mut sound) = self.fds_sound {
+            sound.clock();
+        }
+
+        if let Some(ref mut sound) = self.n163_sound {
+            sound.clock();
+        }
+
+        #[cfg(feature = "vrc7")]
+        {
+            if let Some(ref mut sound) = self.vrc7_sound {
+                sound.clock();
+            }
+        }
+    }
+
+    fn get_audio_sample(&self) -> f32 {
+        let mut sample = 0.0;
+        if let Some(ref sound) = self.fds_sound {
+            sample += sound.get_sample();
+        }
+
+        if let Some(ref sound) = self.n163_sound {
+            sample += sound.get_sample();
+        }
+
+        #[cfg(feature = "vrc7")]
+        {
+            if let Some(ref sound) = self.vrc7_sound {
+                sample += sound.get_sample();
+            }
+        }
+
+        sample
+    }
+
+    fn select_song(&mut self, song: u8) {
+        self.song = song;
+    }
+}
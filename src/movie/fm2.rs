@@ -0,0 +1,166 @@
+use std::path::Path;
+use crate::region::Region;
+use super::{Frame, Movie};
+
+/// FCEUX writes the buttons from the highest bit of the controller state to the lowest
+const BUTTONS: &[u8; 8] = b"RLDUTSBA";
+const SOFT_RESET: u8 = 0b01;
+const HARD_RESET: u8 = 0b10;
+
+/// An FM2 file is a header of "key value" lines followed by one line per frame,
+/// like |0|R......A|........||, with the commands and then each port's buttons
+pub fn read(contents: &str) -> Movie {
+    let mut movie = Movie::new(Region::Ntsc, [0; 16], None);
+    for line in contents.lines() {
+        if line.starts_with('|') {
+            movie.record(read_frame(line, movie.frames.is_empty()));
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ' ');
+        let key = parts.next().unwrap_or_default();
+        let value = parts.next().unwrap_or_default().trim();
+        match key {
+            "palFlag" if value == "1" => movie.region = Region::Pal,
+            "romChecksum" => movie.rom_md5 = read_checksum(value),
+            "binary" if value == "1" => panic!("Binary FM2 movies aren't supported"),
+            "savestate" => panic!("FM2 movies that start from an FCEUX save state can't be played"),
+            _ => ()
+        }
+    }
+
+    movie
+}
+
+/// Only movies that start from power on can be written, save states aren't compatible with FCEUX
+pub fn write(movie: &Movie, rom_file_path: &str) -> String {
+    if movie.save_state.is_some() {
+        panic!("Movies that start from a save state can't be written as FM2");
+    }
+
+    let rom_file_name = Path::new(rom_file_path).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let mut lines = vec![
+        "version 3".to_owned(),
+        "emuVersion 22020".to_owned(),
+        "rerecordCount 0".to_owned(),
+        format!("palFlag {}", if movie.region == Region::Pal { 1 } else { 0 }),
+        format!("romFilename {}", rom_file_name),
+        format!("romChecksum base64:{}", base64::encode(movie.rom_md5)),
+        format!("guid {}", create_guid()),
+        "fourscore 0".to_owned(),
+        "microphone 0".to_owned(),
+        "port0 1".to_owned(),
+        "port1 1".to_owned(),
+        "port2 0".to_owned(),
+        "FDS 0".to_owned(),
+        "NewPPU 0".to_owned()
+    ];
+
+    for frame in &movie.frames {
+        let commands = if frame.reset { SOFT_RESET } else { 0 };
+        lines.push(format!("|{}|{}|{}||", commands, write_buttons(frame.controllers[0]), write_buttons(frame.controllers[1])));
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// FCEUX movies start with a hard reset, which is where this emulator starts anyway
+fn read_frame(line: &str, first_frame: bool) -> Frame {
+    let fields: Vec<&str> = line.split('|').collect();
+    let commands: u8 = fields.get(1).and_then(|c| c.parse().ok()).unwrap_or_default();
+    let hard_reset = commands & HARD_RESET != 0 && !first_frame;
+    Frame {
+        controllers: [
+            read_buttons(fields.get(2).copied().unwrap_or_default()),
+            read_buttons(fields.get(3).copied().unwrap_or_default())
+        ],
+        reset: commands & SOFT_RESET != 0 || hard_reset
+    }
+}
+
+/// Any character other than . or a space means the button is held
+fn read_buttons(field: &str) -> u8 {
+    field.bytes().take(BUTTONS.len()).enumerate()
+        .filter(|(_, button)| *button != b'.' && *button != b' ')
+        .fold(0, |state, (index, _)| state | (0x80 >> index))
+}
+
+fn write_buttons(state: u8) -> String {
+    BUTTONS.iter().enumerate()
+        .map(|(index, button)| if state & (0x80 >> index) != 0 { *button as char } else { '.' })
+        .collect()
+}
+
+fn read_checksum(value: &str) -> [u8; 16] {
+    let mut md5 = [0; 16];
+    if let Some(encoded) = value.strip_prefix("base64:") {
+        if let Ok(decoded) = base64::decode(encoded) {
+            if decoded.len() == md5.len() {
+                md5.copy_from_slice(&decoded);
+            }
+        }
+    }
+
+    md5
+}
+
+fn create_guid() -> String {
+    let bytes: [u8; 16] = rand::random();
+    let hex: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "version 3\npalFlag 1\nromChecksum base64:AAECAwQFBgcICQoLDA0ODw==\n";
+
+    #[test]
+    fn reads_header() {
+        let movie = read(HEADER);
+        assert_eq!(movie.region, Region::Pal);
+        assert_eq!(movie.rom_md5, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+        assert!(movie.frames.is_empty());
+    }
+
+    #[test]
+    fn reads_frames() {
+        let movie = read(&format!("{}|2|R......A|........||\n|0|........|..D.T...||\n|1|........|........||\n|2|........|........||", HEADER));
+        assert_eq!(movie.frames, vec![
+            Frame { controllers: [0x81, 0x00], reset: false },
+            Frame { controllers: [0x00, 0x28], reset: false },
+            Frame { controllers: [0x00, 0x00], reset: true },
+            Frame { controllers: [0x00, 0x00], reset: true }
+        ]);
+    }
+
+    #[test]
+    fn written_movie_reads_back() {
+        let mut movie = Movie::new(Region::Pal, [7; 16], None);
+        movie.record(Frame { controllers: [0xFF, 0x01], reset: false });
+        movie.record(Frame { controllers: [0x10, 0x80], reset: true });
+
+        let contents = write(&movie, "/roms/game.nes");
+        assert!(contents.contains("romFilename game\n"));
+        assert!(contents.contains("|0|RLDUTSBA|.......A||\n"));
+
+        let read_movie = read(&contents);
+        assert_eq!(read_movie.region, movie.region);
+        assert_eq!(read_movie.rom_md5, movie.rom_md5);
+        assert_eq!(read_movie.frames, movie.frames);
+    }
+
+    #[test]
+    #[should_panic(expected = "save state")]
+    fn rejects_save_states() {
+        read("version 3\nsavestate base64:AAAA\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Binary")]
+    fn rejects_binary_movies() {
+        read("version 3\nbinary 1\n");
+    }
+}
@@ -0,0 +1,79 @@
+pub mod fm2;
+
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::Path;
+use crate::controller::controller_state::ControllerState;
+use crate::region::Region;
+
+/// The buttons held on both controllers for one frame, and whether the console was reset before it
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Frame {
+    pub controllers: [u8; 2],
+    pub reset: bool
+}
+
+impl Frame {
+    pub fn get_input(&self) -> [ControllerState; 2] {
+        [ControllerState(self.controllers[0]), ControllerState(self.controllers[1])]
+    }
+}
+
+/// A recording of the input for every frame, starting from either power on or a save state.
+/// Input is only read between frames and RAM always starts zeroed, so playing the same input back from the same start gives the same game.
+#[derive(Serialize, Deserialize)]
+pub struct Movie {
+    pub frames: Vec<Frame>,
+    pub region: Region,
+    pub rom_md5: [u8; 16],
+    pub save_state: Option<String>,
+    #[serde(skip)]
+    position: usize
+}
+
+impl Movie {
+    pub fn new(region: Region, rom_md5: [u8; 16], save_state: Option<String>) -> Self {
+        Movie {
+            frames: vec![],
+            region,
+            rom_md5,
+            save_state,
+            position: 0
+        }
+    }
+
+    /// .fm2 files are imported from FCEUX, anything else is this emulator's own format
+    pub fn load(file_path: &str) -> Self {
+        let contents = fs::read_to_string(file_path).expect("Error reading movie");
+        if is_fm2(file_path) {
+            fm2::read(&contents)
+        } else {
+            serde_json::from_str(&contents).expect("Error loading movie")
+        }
+    }
+
+    pub fn save(&self, file_path: &str, rom_file_path: &str) {
+        let contents = if is_fm2(file_path) {
+            fm2::write(self, rom_file_path)
+        } else {
+            serde_json::to_string(self).expect("Error serializing movie")
+        };
+
+        fs::write(file_path, contents).expect("Error writing movie");
+    }
+
+    pub fn record(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    /// The input for the next frame of playback, or None once the movie is over
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let frame = self.frames.get(self.position).copied();
+        self.position += 1;
+        frame
+    }
+}
+
+fn is_fm2(file_path: &str) -> bool {
+    Path::new(file_path).extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("fm2")).unwrap_or_default()
+}
@@ -1,83 +1,133 @@
-use sdl2::keyboard::{Scancode};
 use sdl2::render::{Canvas, Texture};
 use sdl2::video::{Window};
 use serde::{Serialize, Deserialize};
-use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
-use std::time::{Instant};
 
 use crate::audio;
+use crate::audio::rate_control::RateControl;
+use crate::audio::ring_buffer::RingBuffer;
 use crate::bus;
+use crate::controller::controller_state::ControllerState;
 use crate::cpu;
 use crate::display;
-use crate::instant::InstantWrapper;
+use crate::palette::Palette;
 use crate::ppu;
+use crate::ppu::fps_limiter::Speed;
+use crate::region::Region;
+use crate::screenshot::Screenshot;
+use crate::video::animated_gif::GifRecorder;
+use crate::video::y4m::Y4mRecorder;
 
 #[derive(Serialize, Deserialize)]
 pub struct Nes {
     pub cpu: cpu::Cpu6502,
-    buffer: Arc<Mutex<Vec<f32>>>,
+    pub region: Region,
+    apu_cycle: bool,
+    #[serde(skip)]
+    buffer: Arc<Mutex<RingBuffer>>,
+    cpu_clock_counter: u32,
     dma_dummy: bool,
     fps_limiter: ppu::fps_limiter::FpsLimiter,
-    system_clock_counter: u32,
-    #[serde(skip_serializing, skip_deserializing)]
-    timer: InstantWrapper
+    #[serde(skip)]
+    pub gif_recorder: Option<GifRecorder>,
+    #[serde(skip)]
+    pub palette: Palette,
+    #[serde(skip)]
+    pub speed: Speed,
+    #[serde(skip)]
+    pub video_recorder: Option<Y4mRecorder>,
+    #[serde(skip, default = "default_rate_control")]
+    rate_control: RateControl,
+    system_clock_counter: u32
 }
 
 impl Nes {
-    pub fn new(buffer: Arc<Mutex<Vec<f32>>>) -> Self {
-        Nes {
+    pub fn new(buffer: Arc<Mutex<RingBuffer>>) -> Self {
+        let rate_control = create_rate_control(Region::Ntsc, &buffer);
+        let mut nes = Nes {
             cpu: cpu::Cpu6502::new(),
+            region: Region::Ntsc,
+            apu_cycle: true,
             buffer,
+            cpu_clock_counter: 0,
             dma_dummy: false,
-            fps_limiter: ppu::fps_limiter::FpsLimiter::new(60),
-            system_clock_counter: 0,
-            timer: Default::default()
-        }
+            fps_limiter: ppu::fps_limiter::FpsLimiter::new(Region::Ntsc.get_fps()),
+            gif_recorder: None,
+            palette: Default::default(),
+            speed: Default::default(),
+            video_recorder: None,
+            rate_control,
+            system_clock_counter: 0
+        };
+
+        nes.apu().mixer.stereo = is_stereo(&nes.buffer);
+        nes
     }
 
-    pub fn clock(&mut self, texture: &mut Texture, canvas: &mut Canvas<Window>, event_pump: &sdl2::EventPump) -> bool {
+    /// Returns true once a frame has finished, it's then ready to be drawn
+    pub fn clock(&mut self) -> bool {
         let frame_complete = self.ppu().clock();
 
-        // The CPU runs 3 times slower than the PPU
-        if self.system_clock_counter % 3 == 0 {
+        // The CPU runs 3 times slower than the PPU on NTSC and 3.2 times slower on PAL,
+        // so the counter carries the remainder over to the next CPU clock
+        let (ppu_clocks, cpu_clocks) = self.region.get_clock_ratio();
+        if self.cpu_clock_counter < cpu_clocks {
+            self.cpu_clock_counter += ppu_clocks;
+
             // If DMA transer is happening, then the cpu is suspended
             if self.bus().dma_transfer {
                 self.dma_transfer();
             } else {
                 self.cpu.clock();
             }
-        }
 
-        // The APU runs 6 times slower than the PPU
-        if self.system_clock_counter % 6 == 0 {
-            self.apu().clock();
+            self.clock_mapper();
+            self.apu().clock_frame_counter();
+
+            // The APU runs 2 times slower than the CPU
+            if self.apu_cycle {
+                let expansion_sample = self.bus().get_expansion_audio_sample();
+                self.apu().clock(expansion_sample);
+            }
+
+            self.apu_cycle = !self.apu_cycle;
         }
 
+        self.cpu_clock_counter -= cpu_clocks;
+
         if self.ppu().nmi {
             self.ppu().nmi = false;
             self.cpu.non_mask_interrupt_request();
         }
 
-        if self.apu().trigger_interrupt && self.cpu.get_flag(cpu::Flags6502::DisableInterrupts) == 0 {
+        if self.apu().irq_active() && self.cpu.get_flag(cpu::Flags6502::DisableInterrupts) == 0 {
             self.cpu.interrupt_request();
-            self.apu().trigger_interrupt = false;
         }
 
         self.check_mapper_irq();
 
         if frame_complete {
-            let pixels = self.ppu().frame.get_pixels();
-            display::draw_frame(texture, canvas, &pixels);
-            self.fps_limiter.limit(&self.timer);
-            self.timer.instant = Instant::now();
+            self.bus().apply_freeze_codes();
+            self.fps_limiter.limit(self.speed);
+
+            // Recordings take every frame whatever the speed, along with its sound
+            if let Some(ref mut r) = self.video_recorder {
+                r.add_frame(&self.cpu.bus.ppu.frame, &self.palette, &self.cpu.bus.apu.buffer);
+            }
+
+            if let Some(ref mut r) = self.gif_recorder {
+                r.add_frame(&self.cpu.bus.ppu.frame, &self.palette);
+            }
+
+            // Audio is muted when not running at normal speed, otherwise samples would pile up faster or slower than they're played
             let mut lock = self.buffer.lock().expect("Error getting a lock for the buffer");
-            lock.append(&mut self.cpu.bus.apu.buffer);
-        }
+            if self.speed.is_normal() {
+                self.rate_control.push(&self.cpu.bus.apu.buffer, &mut lock);
+            } else {
+                lock.clear();
+            }
 
-        if self.bus().strobe_pulse & 1 == 1 {
-            let pressed_scancodes: HashSet<Scancode> = event_pump.keyboard_state().pressed_scancodes().collect();
-            self.bus().controllers[0].set_controller_state(pressed_scancodes);
+            self.cpu.bus.apu.buffer.clear();
         }
 
         self.fps_limiter.calculate_fps();
@@ -88,13 +138,44 @@ impl Nes {
 
     pub fn reset(&mut self) {
         self.cpu.reset();
+        self.apu_cycle = true;
+        self.cpu_clock_counter = 0;
         self.system_clock_counter = 0;
     }
 
-    pub fn load_buffer(&mut self, buffer: Arc<Mutex<Vec<f32>>>) {
+    /// Sets the buttons held on both controllers, done once before each frame so the same input always gives the same game
+    pub fn set_input(&mut self, input: [ControllerState; 2]) {
+        self.bus().controllers[0].set_controller_state(input[0]);
+        self.bus().controllers[1].set_controller_state(input[1]);
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+        self.fps_limiter = ppu::fps_limiter::FpsLimiter::new(region.get_fps());
+        self.rate_control = create_rate_control(region, &self.buffer);
+        self.ppu().region = region;
+        self.apu().region = region;
+    }
+
+    pub fn load_buffer(&mut self, buffer: Arc<Mutex<RingBuffer>>) {
+        self.rate_control = create_rate_control(self.region, &buffer);
+        self.apu().mixer.stereo = is_stereo(&buffer);
         self.buffer = buffer;
     }
 
+    pub fn draw(&self, texture: &mut Texture, canvas: &mut Canvas<Window>) {
+        display::draw_frame(texture, canvas, &self.cpu.bus.ppu.frame, &self.palette);
+    }
+
+    /// The last complete frame at the console's resolution
+    pub fn screenshot(&self) -> Screenshot {
+        Screenshot {
+            width: display::SCREEN_WIDTH,
+            height: display::SCREEN_HEIGHT,
+            pixels: display::to_rgb(&self.cpu.bus.ppu.frame, &self.palette)
+        }
+    }
+
     pub fn bus(&mut self) -> &mut bus::Bus {
         &mut self.cpu.bus
     }
@@ -107,6 +188,12 @@ impl Nes {
         &mut self.cpu.bus.apu
     }
 
+    fn clock_mapper(&mut self) {
+        if let Some(ref mut c) = self.cpu.bus.cartridge {
+            c.borrow_mut().cpu_clock();
+        }
+    }
+
     fn check_mapper_irq(&mut self) {
         let mut trigger_interrupt = false;
         if let Some(ref mut c) = self.cpu.bus.cartridge {
@@ -151,4 +238,18 @@ impl Nes {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// The APU makes a sample every other CPU cycle, and they're resampled to the rate the buffer is played at
+fn create_rate_control(region: Region, buffer: &Arc<Mutex<RingBuffer>>) -> RateControl {
+    let lock = buffer.lock().expect("Error getting a lock for the buffer");
+    RateControl::new((region.get_cpu_frequency() as f64) / 2.0, lock.sample_rate as f64, lock.channels)
+}
+
+fn default_rate_control() -> RateControl {
+    RateControl::new((Region::Ntsc.get_cpu_frequency() as f64) / 2.0, audio::DEFAULT_SAMPLE_RATE as f64, 1)
+}
+
+fn is_stereo(buffer: &Arc<Mutex<RingBuffer>>) -> bool {
+    buffer.lock().expect("Error getting a lock for the buffer").channels == 2
+}
@@ -0,0 +1,142 @@
+pub mod nsfe;
+pub mod player;
+
+use std::fs;
+use crate::region::Region;
+
+pub const DEFAULT_PLAY_SPEED: u16 = 16_639;
+pub const DEFAULT_PAL_PLAY_SPEED: u16 = 19_997;
+#[cfg(feature = "vrc7")]
+pub const SOUND_CHIP_VRC7: u8 = 0b0000_0010;
+pub const SOUND_CHIP_FDS: u8 = 0b0000_0100;
+pub const SOUND_CHIP_N163: u8 = 0b0001_0000;
+
+/// Expansion chips an NSF can use that aren't emulated, by their bit in the header
+const UNSUPPORTED_SOUND_CHIPS: &[(u8, &str)] = &[
+    (0b0000_0001, "VRC6"),
+    #[cfg(not(feature = "vrc7"))]
+    (0b0000_0010, "VRC7"),
+    (0b0000_1000, "MMC5"),
+    (0b0010_0000, "Sunsoft 5B")
+];
+
+const HEADER: &[u8] = b"NESM\x1A";
+const HEADER_SIZE: usize = 0x80;
+const STRING_SIZE: usize = 32;
+
+/// An NSF is a music rip, the sound code and data from a game along with where to load it and which routines to call.
+/// INIT is called once with the song number in A to set up a song, PLAY is then called at the rate given by the play speed.
+#[derive(Clone, Debug)]
+pub struct NsfFile {
+    pub title: String,
+    pub artist: String,
+    pub copyright: String,
+    pub track_labels: Vec<String>,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub total_songs: u8,
+    pub starting_song: u8,
+    /// Microseconds between each call to PLAY on NTSC
+    pub play_speed: u16,
+    /// Microseconds between each call to PLAY on PAL
+    pub pal_play_speed: u16,
+    pub region: Region,
+    pub bank_switch: [u8; 8],
+    pub sound_chips: u8,
+    pub data: Vec<u8>
+}
+
+impl NsfFile {
+    pub fn new(file_path: &str) -> Self {
+        let bytes = fs::read(file_path).expect("Cannot find file");
+        if bytes.starts_with(nsfe::HEADER) {
+            return nsfe::parse(&bytes);
+        }
+
+        NsfFile::parse(&bytes)
+    }
+
+    /// The header is 128 bytes:
+    /// $00: "NESM" followed by $1A
+    /// $05: Version
+    /// $06: Total songs
+    /// $07: Starting song, counting from 1
+    /// $08: Load address
+    /// $0A: Init address
+    /// $0C: Play address
+    /// $0E: Title, artist and copyright, 32 bytes each
+    /// $6E: NTSC play speed
+    /// $70: Initial banks for $8000-$FFFF, all 0 if the NSF isn't bank switched
+    /// $78: PAL play speed
+    /// $7A: PAL/NTSC
+    /// $7B: Expansion sound chips
+    /// $7D: Length of the data, 0 means the rest of the file
+    fn parse(bytes: &[u8]) -> Self {
+        if !bytes.starts_with(HEADER) || bytes.len() < HEADER_SIZE {
+            panic!("Invalid NSF file");
+        }
+
+        let mut bank_switch = [0; 8];
+        bank_switch.copy_from_slice(&bytes[0x70..0x78]);
+
+        let data_length = (bytes[0x7D] as usize) | ((bytes[0x7E] as usize) << 8) | ((bytes[0x7F] as usize) << 16);
+        let data_end = if data_length == 0 { bytes.len() } else { (HEADER_SIZE + data_length).min(bytes.len()) };
+
+        let total_songs = bytes[0x06].max(1);
+        let starting_song = bytes[0x07].max(1) - 1;
+        NsfFile {
+            title: read_string(&bytes[0x0E..(0x0E + STRING_SIZE)]),
+            artist: read_string(&bytes[0x2E..(0x2E + STRING_SIZE)]),
+            copyright: read_string(&bytes[0x4E..(0x4E + STRING_SIZE)]),
+            track_labels: vec![],
+            load_address: read_u16(bytes, 0x08),
+            init_address: read_u16(bytes, 0x0A),
+            play_address: read_u16(bytes, 0x0C),
+            total_songs,
+            starting_song: if starting_song < total_songs { starting_song } else { 0 },
+            play_speed: match read_u16(bytes, 0x6E) { 0 => DEFAULT_PLAY_SPEED, speed => speed },
+            pal_play_speed: match read_u16(bytes, 0x78) { 0 => DEFAULT_PAL_PLAY_SPEED, speed => speed },
+            region: get_region(bytes[0x7A]),
+            bank_switch,
+            sound_chips: bytes[0x7B],
+            data: bytes[HEADER_SIZE..data_end].to_vec()
+        }
+    }
+
+    pub fn is_bank_switched(&self) -> bool {
+        self.bank_switch.iter().any(|bank| *bank != 0)
+    }
+
+    pub fn has_sound_chip(&self, sound_chip: u8) -> bool {
+        self.sound_chips & sound_chip != 0
+    }
+
+    /// The names of the expansion chips the file uses that play without sound
+    pub fn get_unsupported_sound_chips(&self) -> Vec<&'static str> {
+        UNSUPPORTED_SOUND_CHIPS.iter()
+            .filter(|(sound_chip, _)| self.has_sound_chip(*sound_chip))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Microseconds between each call to PLAY in the region the file is played in
+    pub fn get_play_speed(&self) -> u16 {
+        if self.region == Region::Pal { self.pal_play_speed } else { self.play_speed }
+    }
+}
+
+/// Bit 0 is set for PAL and bit 1 for files that play on either, which get NTSC
+pub fn get_region(flags: u8) -> Region {
+    if flags & 0b11 == 0b01 { Region::Pal } else { Region::Ntsc }
+}
+
+/// Strings are null terminated, or fill the whole field if they're exactly 32 characters
+pub fn read_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_owned()
+}
+
+pub fn read_u16(bytes: &[u8], index: usize) -> u16 {
+    (bytes[index] as u16) | ((bytes[index + 1] as u16) << 8)
+}
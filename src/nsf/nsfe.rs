@@ -0,0 +1,102 @@
+use super::{NsfFile, DEFAULT_PLAY_SPEED, DEFAULT_PAL_PLAY_SPEED, get_region, read_string, read_u16};
+use crate::region::Region;
+
+pub const HEADER: &[u8] = b"NSFE";
+
+const CHUNK_HEADER_SIZE: usize = 8;
+const INFO_MIN_SIZE: usize = 9;
+
+/// NSFe files are "NSFE" followed by chunks, each one a 4 byte length, a 4 byte id and then the data.
+/// Chunks with an upper case first letter have to be understood to play the file, the rest can be skipped.
+/// INFO: Addresses, PAL/NTSC, sound chips, total songs and starting song, laid out like the NSF header
+/// DATA: The data to load
+/// BANK: Initial banks
+/// RATE: NTSC play speed, optionally followed by the PAL play speed
+/// auth: Title, artist, copyright and ripper as null terminated strings
+/// tlbl: Null terminated name of each track
+/// NEND: End of the file
+pub fn parse(bytes: &[u8]) -> NsfFile {
+    let mut file = NsfFile {
+        title: String::new(),
+        artist: String::new(),
+        copyright: String::new(),
+        track_labels: vec![],
+        load_address: 0,
+        init_address: 0,
+        play_address: 0,
+        total_songs: 1,
+        starting_song: 0,
+        play_speed: DEFAULT_PLAY_SPEED,
+        pal_play_speed: DEFAULT_PAL_PLAY_SPEED,
+        region: Region::Ntsc,
+        bank_switch: [0; 8],
+        sound_chips: 0,
+        data: vec![]
+    };
+
+    let mut has_info = false;
+    let mut index = HEADER.len();
+    while index + CHUNK_HEADER_SIZE <= bytes.len() {
+        let length = (read_u16(bytes, index) as usize) | ((read_u16(bytes, index + 2) as usize) << 16);
+        let id = &bytes[(index + 4)..(index + CHUNK_HEADER_SIZE)];
+        let start = index + CHUNK_HEADER_SIZE;
+        let end = start + length;
+        if end > bytes.len() {
+            panic!("NSFe chunk runs past the end of the file");
+        }
+
+        let chunk = &bytes[start..end];
+        match id {
+            b"INFO" => {
+                if chunk.len() < INFO_MIN_SIZE {
+                    panic!("NSFe INFO chunk is too small");
+                }
+
+                file.load_address = read_u16(chunk, 0);
+                file.init_address = read_u16(chunk, 2);
+                file.play_address = read_u16(chunk, 4);
+                file.region = get_region(chunk[6]);
+                file.sound_chips = chunk[7];
+                file.total_songs = chunk[8].max(1);
+                file.starting_song = chunk.get(9).copied().unwrap_or(0);
+                has_info = true;
+            },
+            b"DATA" => file.data = chunk.to_vec(),
+            b"BANK" => {
+                for (i, bank) in chunk.iter().take(8).enumerate() {
+                    file.bank_switch[i] = *bank;
+                }
+            },
+            b"RATE" if chunk.len() >= 2 => {
+                file.play_speed = read_u16(chunk, 0);
+                if chunk.len() >= 4 {
+                    file.pal_play_speed = read_u16(chunk, 2);
+                }
+            },
+            b"auth" => {
+                let mut strings = chunk.split(|b| *b == 0).map(read_string);
+                file.title = strings.next().unwrap_or_default();
+                file.artist = strings.next().unwrap_or_default();
+                file.copyright = strings.next().unwrap_or_default();
+            },
+            b"tlbl" => {
+                file.track_labels = chunk.split(|b| *b == 0).map(read_string).collect();
+            },
+            b"NEND" => break,
+            _ if id[0].is_ascii_uppercase() => panic!("Unsupported NSFe chunk {}", String::from_utf8_lossy(id)),
+            _ => ()
+        }
+
+        index = end;
+    }
+
+    if !has_info || file.data.is_empty() {
+        panic!("NSFe file is missing its INFO or DATA chunk");
+    }
+
+    if file.starting_song >= file.total_songs {
+        file.starting_song = 0;
+    }
+
+    file
+}
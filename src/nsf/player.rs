@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use crate::audio::ring_buffer::RingBuffer;
+use crate::cartridge::Cartridge;
+use crate::nes::Nes;
+use crate::region::Region;
+use super::NsfFile;
+
+/// Keeps track of which song is playing and restarts the NES on the new song when it changes
+pub struct Player {
+    file: NsfFile,
+    song: u8
+}
+
+impl Player {
+    /// The region can be forced, otherwise it's the one the file asks for
+    pub fn new(file_path: &str, region: Option<Region>) -> Self {
+        let mut file = NsfFile::new(file_path);
+        if let Some(region) = region {
+            file.region = region;
+        }
+
+        Player {
+            song: file.starting_song,
+            file
+        }
+    }
+
+    pub fn create_nes(&self, file_path: &str, buffer: Arc<Mutex<RingBuffer>>) -> Nes {
+        let mut nes = Nes::new(buffer);
+        nes.bus().load_cartridge(Cartridge::from_nsf(file_path, &self.file));
+        nes.set_region(self.file.region);
+        self.play(&mut nes);
+        nes
+    }
+
+    pub fn next_song(&mut self, nes: &mut Nes) {
+        self.song = (self.song + 1) % self.file.total_songs;
+        self.play(nes);
+    }
+
+    pub fn previous_song(&mut self, nes: &mut Nes) {
+        self.song = if self.song == 0 { self.file.total_songs - 1 } else { self.song - 1 };
+        self.play(nes);
+    }
+
+    pub fn get_unsupported_sound_chips(&self) -> Vec<&'static str> {
+        self.file.get_unsupported_sound_chips()
+    }
+
+    /// There's no way to draw text so the song info goes in the window title
+    pub fn get_title(&self) -> String {
+        let mut title = match self.file.track_labels.get(self.song as usize) {
+            Some(label) if !label.is_empty() => format!("{} - {}", self.file.title, label),
+            _ => self.file.title.to_owned()
+        };
+
+        if !self.file.artist.is_empty() {
+            title.push_str(&format!(" by {}", self.file.artist));
+        }
+
+        if !self.file.copyright.is_empty() {
+            title.push_str(&format!(" ({})", self.file.copyright));
+        }
+
+        format!("{} - Song {}/{}", title, self.song + 1, self.file.total_songs)
+    }
+
+    fn play(&self, nes: &mut Nes) {
+        if let Some(ref c) = nes.bus().cartridge {
+            c.borrow_mut().select_song(self.song);
+        }
+
+        nes.reset();
+    }
+}
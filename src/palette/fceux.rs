@@ -0,0 +1,72 @@
+use crate::ppu::colors::Color;
+
+/// FCEUX's default palette
+pub const FCEUX_COLORS: [Color; 64] = [
+    (116, 116, 116),
+    (36, 24, 140),
+    (0, 0, 168),
+    (68, 0, 156),
+    (140, 0, 116),
+    (168, 0, 16),
+    (164, 0, 0),
+    (124, 8, 0),
+    (64, 44, 0),
+    (0, 68, 0),
+    (0, 80, 0),
+    (0, 60, 20),
+    (24, 60, 92),
+    (0, 0, 0),
+    (0, 0, 0),
+    (0, 0, 0),
+
+    (188, 188, 188),
+    (0, 112, 236),
+    (32, 56, 236),
+    (128, 0, 240),
+    (188, 0, 188),
+    (228, 0, 88),
+    (216, 40, 0),
+    (200, 76, 12),
+    (136, 112, 0),
+    (0, 148, 0),
+    (0, 168, 0),
+    (0, 144, 56),
+    (0, 128, 136),
+    (0, 0, 0),
+    (0, 0, 0),
+    (0, 0, 0),
+
+    (252, 252, 252),
+    (60, 188, 252),
+    (92, 148, 252),
+    (204, 136, 252),
+    (244, 120, 252),
+    (252, 116, 180),
+    (252, 116, 96),
+    (252, 152, 56),
+    (240, 188, 60),
+    (128, 208, 16),
+    (76, 220, 72),
+    (88, 248, 152),
+    (0, 232, 216),
+    (120, 120, 120),
+    (0, 0, 0),
+    (0, 0, 0),
+
+    (252, 252, 252),
+    (168, 228, 252),
+    (196, 212, 252),
+    (212, 200, 252),
+    (252, 196, 252),
+    (252, 196, 216),
+    (252, 188, 176),
+    (252, 216, 168),
+    (252, 228, 160),
+    (224, 252, 160),
+    (168, 240, 188),
+    (176, 252, 204),
+    (156, 252, 240),
+    (196, 196, 196),
+    (0, 0, 0),
+    (0, 0, 0)
+];
@@ -0,0 +1,99 @@
+pub mod fceux;
+pub mod ntsc;
+pub mod yuv;
+
+use std::fs;
+use std::path::Path;
+use crate::ppu::colors::{self, Color, Pixel, COLOR_RAM};
+use ntsc::NtscSettings;
+
+const COLORS: usize = 64;
+const EMPHASIS_COLORS: usize = COLORS * 8;
+
+/// Less saturated with lifted blacks, so neighbouring colours blend into each other more
+const SMOOTH_SETTINGS: NtscSettings = NtscSettings {
+    hue: 0.0,
+    saturation: 0.8,
+    contrast: 0.92,
+    brightness: 0.04,
+    gamma: 2.2,
+    white_balance: [1.0, 1.0, 1.0]
+};
+
+/// Like a Sony PVM monitor, slightly more saturated with the bluer D93 white point
+const PVM_SETTINGS: NtscSettings = NtscSettings {
+    hue: 0.0,
+    saturation: 1.1,
+    contrast: 1.0,
+    brightness: 0.0,
+    gamma: 2.2,
+    white_balance: [0.630, 0.753, 1.0]
+};
+
+/// The colour for every pixel the PPU can output, each of the 64 colours with every combination of emphasis bits
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub name: String,
+    colors: Vec<Color>
+}
+
+impl Palette {
+    /// 64 colours get their emphasis worked out, 512 colours already have it
+    pub fn from_colors(name: &str, colors: &[Color]) -> Self {
+        let colors = match colors.len() {
+            COLORS => (0..EMPHASIS_COLORS).map(|pixel| get_emphasized_color(colors, pixel as Pixel)).collect(),
+            EMPHASIS_COLORS => colors.to_vec(),
+            _ => panic!("A palette needs 64 or 512 colours")
+        };
+
+        Palette {
+            name: name.to_owned(),
+            colors
+        }
+    }
+
+    /// A .pal file is 3 bytes of RGB for each colour, with or without the emphasized colours after the first 64
+    pub fn load(file_path: &str) -> Self {
+        let data = fs::read(file_path).expect("Cannot find palette file");
+        if data.len() != COLORS * 3 && data.len() != EMPHASIS_COLORS * 3 {
+            panic!("A palette file should be 192 or 1536 bytes");
+        }
+
+        let colors: Vec<Color> = data.chunks(3).map(|rgb| (rgb[0], rgb[1], rgb[2])).collect();
+        let name = Path::new(file_path).file_stem().and_then(|name| name.to_str()).unwrap_or(file_path);
+        Palette::from_colors(name, &colors)
+    }
+
+    pub fn ntsc(name: &str, settings: &NtscSettings) -> Self {
+        Palette::from_colors(name, &ntsc::generate(settings))
+    }
+
+    pub fn get_color(&self, pixel: Pixel) -> Color {
+        self.colors[(pixel as usize) % EMPHASIS_COLORS]
+    }
+}
+
+/// The 2C02 palette from the NESdev wiki, the one the emulator has always used
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::from_colors("2C02", &COLOR_RAM)
+    }
+}
+
+/// The palettes that are always there, the NTSC one is generated with the given settings.
+/// Smooth and PVM are generated too, with settings of their own.
+pub fn get_presets(settings: &NtscSettings) -> Vec<Palette> {
+    vec![
+        Palette::default(),
+        Palette::ntsc("NTSC", settings),
+        Palette::from_colors("FCEUX", &fceux::FCEUX_COLORS),
+        Palette::from_colors("Nestopia YUV", &yuv::generate()),
+        Palette::ntsc("Smooth", &SMOOTH_SETTINGS),
+        Palette::ntsc("PVM", &PVM_SETTINGS)
+    ]
+}
+
+fn get_emphasized_color(colors: &[Color], pixel: Pixel) -> Color {
+    let emphasis = ((pixel & 0x40) > 0, (pixel & 0x80) > 0, (pixel & 0x100) > 0);
+    colors::emphasize(colors[(pixel & 0x3F) as usize], emphasis)
+}
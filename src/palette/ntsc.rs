@@ -0,0 +1,96 @@
+use std::f32::consts::PI;
+use crate::ppu::colors::Color;
+
+/// Voltages of the PPU's signal for the low and high half of each colour's wave, for each of the 4 brightness levels
+const LOW_LEVELS: [f32; 4] = [0.350, 0.518, 0.962, 1.550];
+const HIGH_LEVELS: [f32; 4] = [1.094, 1.506, 1.962, 1.962];
+const BLACK: f32 = 0.518;
+const WHITE: f32 = 1.962;
+/// How much an emphasis bit weakens the signal while it's active
+const EMPHASIS_ATTENUATION: f32 = 0.746;
+/// The wave for each colour is 12 steps long, and each of the 12 hues starts it on a different step
+const PHASES: usize = 12;
+/// Where the decoder samples each step, in steps, so the hues land where a TV puts them
+const PHASE_OFFSET: f32 = 4.0;
+
+/// How a TV set turns the signal into colours, the same knobs a TV has
+#[derive(Clone, Copy, Debug)]
+pub struct NtscSettings {
+    /// Rotates every hue, in degrees
+    pub hue: f32,
+    pub saturation: f32,
+    pub contrast: f32,
+    pub brightness: f32,
+    /// The gamma of the screen it's shown on, 2.2 leaves the signal's own gamma alone
+    pub gamma: f32,
+    /// How bright the screen's red, green and blue are at full strength, which sets the colour of white
+    pub white_balance: [f32; 3]
+}
+
+impl Default for NtscSettings {
+    fn default() -> Self {
+        NtscSettings {
+            hue: 0.0,
+            saturation: 1.0,
+            contrast: 1.0,
+            brightness: 0.0,
+            gamma: 2.2,
+            white_balance: [1.0, 1.0, 1.0]
+        }
+    }
+}
+
+/// Decodes what the PPU would send to a TV for all 64 colours with each of the 8 combinations of emphasis bits
+pub fn generate(settings: &NtscSettings) -> Vec<Color> {
+    (0..512).map(|pixel| generate_color((pixel & 0x3F) as u8, (pixel >> 6) as u8, settings)).collect()
+}
+
+fn generate_color(color_index: u8, emphasis: u8, settings: &NtscSettings) -> Color {
+    let hue = (color_index & 0x0F) as usize;
+    let level = if hue >= 0x0E { 1 } else { ((color_index >> 4) & 0b11) as usize };
+
+    // Hue 0 is a flat grey at the high level and hues 13 to 15 are flat at the low level, the rest are square waves
+    let low = if hue == 0 { HIGH_LEVELS[level] } else { LOW_LEVELS[level] };
+    let high = if hue > 12 { LOW_LEVELS[level] } else { HIGH_LEVELS[level] };
+
+    let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+    for phase in 0..PHASES {
+        let mut signal = if is_in_phase(hue, phase) { high } else { low };
+
+        // Red, green and blue emphasis each darken the part of the wave for their opposite, hues 12, 4 and 8
+        let emphasized = ((emphasis & 0b001) > 0 && is_in_phase(12, phase))
+            || ((emphasis & 0b010) > 0 && is_in_phase(4, phase))
+            || ((emphasis & 0b100) > 0 && is_in_phase(8, phase));
+
+        if emphasized && hue < 0x0E {
+            signal *= EMPHASIS_ATTENUATION;
+        }
+
+        let level = (signal - BLACK) / (WHITE - BLACK);
+        let angle = (PI * ((phase as f32) + PHASE_OFFSET) / 6.0) + settings.hue.to_radians();
+        y += level;
+        i += level * angle.cos();
+        q += level * angle.sin();
+    }
+
+    let y = ((y / (PHASES as f32)) * settings.contrast) + settings.brightness;
+    let i = (i / (PHASES as f32)) * settings.saturation * 2.0;
+    let q = (q / (PHASES as f32)) * settings.saturation * 2.0;
+
+    let red = y + (0.956 * i) + (0.621 * q);
+    let green = y - (0.272 * i) - (0.647 * q);
+    let blue = y - (1.106 * i) + (1.703 * q);
+    let [red_balance, green_balance, blue_balance] = settings.white_balance;
+    (to_byte(red, red_balance, settings.gamma), to_byte(green, green_balance, settings.gamma), to_byte(blue, blue_balance, settings.gamma))
+}
+
+/// Whether the wave for a hue is high on this step
+fn is_in_phase(hue: usize, phase: usize) -> bool {
+    (hue + phase) % PHASES < PHASES / 2
+}
+
+/// The balance is applied to the light coming off the screen, so it's done after undoing the signal's gamma
+fn to_byte(value: f32, balance: f32, gamma: f32) -> u8 {
+    let corrected = (value.max(0.0).powf(2.2) * balance).powf(1.0 / gamma);
+    (corrected * 255.0).round().clamp(0.0, 255.0) as u8
+}
@@ -0,0 +1,40 @@
+use std::f32::consts::PI;
+use crate::ppu::colors::Color;
+
+/// The low and high level of the signal for each of the 4 brightness levels, where 0 is black and 1 is white
+const LOW_LEVELS: [f32; 4] = [-0.12, 0.00, 0.31, 0.72];
+const HIGH_LEVELS: [f32; 4] = [0.40, 0.68, 1.00, 1.00];
+/// Turns hue 3 onto the V axis, plus the angle that lines the hues up with Nestopia's
+const HUE_OFFSET: f32 = 18.0;
+
+/// Nestopia's YUV palette, which treats each colour as a sine wave halfway between its two levels
+/// and decodes it with the standard YUV matrix. Only the 64 colours, emphasis is worked out from them.
+pub fn generate() -> Vec<Color> {
+    (0..64).map(generate_color).collect()
+}
+
+fn generate_color(color_index: u8) -> Color {
+    let hue = color_index & 0x0F;
+    let level = ((color_index >> 4) & 0b11) as usize;
+    let (low, high) = match hue {
+        0x00 => (HIGH_LEVELS[level], HIGH_LEVELS[level]),
+        0x0D => (LOW_LEVELS[level], LOW_LEVELS[level]),
+        0x0E | 0x0F => (0.0, 0.0),
+        _ => (LOW_LEVELS[level], HIGH_LEVELS[level])
+    };
+
+    let y = (high + low) / 2.0;
+    let saturation = (high - low) / 2.0;
+    let angle = (PI / 6.0 * ((hue as f32) - 3.0)) + HUE_OFFSET.to_radians();
+    let u = saturation * angle.cos();
+    let v = saturation * angle.sin();
+
+    let red = y + (1.140 * v);
+    let green = y - (0.394 * u) - (0.581 * v);
+    let blue = y + (2.030 * u);
+    (to_byte(red), to_byte(green), to_byte(blue))
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
@@ -0,0 +1,135 @@
+use crate::crc32;
+use super::{read_number, read_u32, verify_checksums, FOOTER_SIZE};
+
+const HEADER: &[u8] = b"BPS1";
+const SOURCE_READ: usize = 0;
+const TARGET_READ: usize = 1;
+const SOURCE_COPY: usize = 2;
+
+/// A BPS patch is "BPS1", the source, target and metadata sizes, the metadata and then a list of actions
+/// that build the target from the start, each one a number with the action in the low 2 bits and the length above that:
+/// 0: Copy from the source at the current output position
+/// 1: Copy bytes from the patch
+/// 2: Copy from a position in the source, moved relative to the last source copy
+/// 3: Copy from a position in the output so far, moved relative to the last target copy
+/// It ends with the CRC-32 of the source, the target and the patch itself.
+pub fn apply(data: &mut Vec<u8>, patch: &[u8]) {
+    if !patch.starts_with(HEADER) || patch.len() < HEADER.len() + FOOTER_SIZE {
+        panic!("Invalid BPS patch, missing BPS1 header");
+    }
+
+    let footer_index = patch.len() - FOOTER_SIZE;
+    let source_crc = read_u32(patch, footer_index);
+    let target_crc = read_u32(patch, footer_index + 4);
+    let patch_crc = read_u32(patch, footer_index + 8);
+    if crc32::checksum(&patch[..(footer_index + 8)]) != patch_crc {
+        panic!("BPS patch is corrupt, its checksum doesn't match");
+    }
+
+    let mut index = HEADER.len();
+    let source_size = read_number(patch, &mut index);
+    let target_size = read_number(patch, &mut index);
+    let metadata_size = read_number(patch, &mut index);
+    index += metadata_size;
+
+    if data.len() != source_size {
+        panic!("BPS patch is for a {} byte file but the ROM is {} bytes", source_size, data.len());
+    }
+
+    let mut target = Vec::with_capacity(target_size);
+    let mut source_offset: isize = 0;
+    let mut target_offset: isize = 0;
+    while index < footer_index {
+        let command = read_number(patch, &mut index);
+        let length = (command >> 2) + 1;
+        match command & 0b11 {
+            SOURCE_READ => {
+                let start = target.len();
+                target.extend_from_slice(&data[start..(start + length)]);
+            },
+            TARGET_READ => {
+                target.extend_from_slice(&patch[index..(index + length)]);
+                index += length;
+            },
+            SOURCE_COPY => {
+                source_offset += read_offset(patch, &mut index);
+                let start = source_offset as usize;
+                target.extend_from_slice(&data[start..(start + length)]);
+                source_offset += length as isize;
+            },
+            _ => {
+                // The copy can overlap what it's writing, so it has to go a byte at a time
+                target_offset += read_offset(patch, &mut index);
+                for _ in 0..length {
+                    let byte = target[target_offset as usize];
+                    target.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    verify_checksums("BPS", data, &target, source_crc, target_crc);
+    *data = target;
+}
+
+/// Offsets are stored with the sign in the lowest bit
+fn read_offset(patch: &[u8], index: &mut usize) -> isize {
+    let number = read_number(patch, index);
+    let offset = (number >> 1) as isize;
+    if number & 1 != 0 { -offset } else { offset }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::add_footer;
+
+    fn create_patch(source: &[u8], target: &[u8], actions: &[u8]) -> Vec<u8> {
+        let mut patch = HEADER.to_vec();
+        patch.extend_from_slice(&[0x80 | source.len() as u8, 0x80 | target.len() as u8, 0x80]);
+        patch.extend_from_slice(actions);
+        add_footer(&mut patch, source, target);
+        patch
+    }
+
+    #[test]
+    fn applies_source_and_target_reads() {
+        let mut data = b"hello world".to_vec();
+        let patch = create_patch(&data, b"hello there!", b"\x94\x95there!");
+        apply(&mut data, &patch);
+        assert_eq!(data, b"hello there!");
+    }
+
+    #[test]
+    fn applies_source_and_target_copies() {
+        let mut data = b"hello world".to_vec();
+        let patch = create_patch(&data, b"worldworld", b"\x92\x8C\x93\x80");
+        apply(&mut data, &patch);
+        assert_eq!(data, b"worldworld");
+    }
+
+    #[test]
+    fn target_copies_can_overlap() {
+        let mut data = b"ab".to_vec();
+        let patch = create_patch(&data, b"ababab", b"\x84\x8F\x80");
+        apply(&mut data, &patch);
+        assert_eq!(data, b"ababab");
+    }
+
+    #[test]
+    #[should_panic(expected = "different ROM")]
+    fn rejects_wrong_rom() {
+        let patch = create_patch(b"hello world", b"hello there!", b"\x94\x95there!");
+        apply(&mut b"HELLO WORLD".to_vec(), &patch);
+    }
+
+    #[test]
+    #[should_panic(expected = "corrupt")]
+    fn rejects_corrupt_patch() {
+        let mut data = b"hello world".to_vec();
+        let mut patch = create_patch(&data, b"hello there!", b"\x94\x95there!");
+        patch[8] = b'T';
+        apply(&mut data, &patch);
+    }
+}
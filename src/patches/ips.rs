@@ -0,0 +1,146 @@
+const HEADER: &[u8] = b"PATCH";
+const FOOTER: &[u8] = b"EOF";
+const FOOTER_OFFSET: usize = 0x45_4F46;
+const MAX_OFFSET: usize = 0xFF_FFFF;
+const MAX_RECORD_SIZE: usize = 0xFFFF;
+
+/// Runs of unchanged bytes shorter than a record header are cheaper to copy than to start a new record for
+const MERGE_DISTANCE: usize = 5;
+
+/// An IPS patch is "PATCH", followed by records and then "EOF".
+/// Each record is a 3 byte offset and 2 byte size followed by that many bytes to copy,
+/// a size of 0 means it's a run length encoded record with a 2 byte count and the byte to repeat.
+pub fn apply(data: &mut Vec<u8>, patch: &[u8]) {
+    if !patch.starts_with(HEADER) {
+        panic!("Invalid IPS patch, missing PATCH header");
+    }
+
+    let mut index = HEADER.len();
+    while index + FOOTER.len() <= patch.len() && &patch[index..(index + FOOTER.len())] != FOOTER {
+        let offset = read_u24(patch, index);
+        let size = read_u16(patch, index + 3);
+        index += 5;
+
+        let (bytes, record_size) = if size == 0 {
+            let count = read_u16(patch, index);
+            (vec![patch[index + 2]; count], 3)
+        } else {
+            (patch[index..(index + size)].to_vec(), size)
+        };
+
+        index += record_size;
+        if data.len() < offset + bytes.len() {
+            data.resize(offset + bytes.len(), 0);
+        }
+
+        data[offset..(offset + bytes.len())].copy_from_slice(&bytes);
+    }
+
+    // Some patches also truncate the file with a 3 byte size after EOF
+    let truncate_index = index + FOOTER.len();
+    if truncate_index + 3 == patch.len() {
+        data.truncate(read_u24(patch, truncate_index));
+    }
+}
+
+/// Creates a patch that turns original into modified, both are expected to be the same size
+pub fn create(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut patch = HEADER.to_vec();
+    let mut index = 0;
+    while index < modified.len() && index <= MAX_OFFSET {
+        if original.get(index) == Some(&modified[index]) {
+            index += 1;
+            continue;
+        }
+
+        // An offset that spells out EOF would end the patch early, so start the record a byte earlier
+        let start = if index == FOOTER_OFFSET { index - 1 } else { index };
+        let mut end = index + 1;
+        let mut unchanged = 0;
+        while end < modified.len() && end - start < MAX_RECORD_SIZE && unchanged <= MERGE_DISTANCE {
+            if original.get(end) == Some(&modified[end]) {
+                unchanged += 1;
+            } else {
+                unchanged = 0;
+            }
+
+            end += 1;
+        }
+
+        end -= unchanged;
+        patch.extend_from_slice(&[(start >> 16) as u8, (start >> 8) as u8, start as u8]);
+        patch.extend_from_slice(&[((end - start) >> 8) as u8, (end - start) as u8]);
+        patch.extend_from_slice(&modified[start..end]);
+        index = end;
+    }
+
+    patch.extend_from_slice(FOOTER);
+    patch
+}
+
+pub fn is_empty(patch: &[u8]) -> bool {
+    patch.len() == HEADER.len() + FOOTER.len()
+}
+
+fn read_u24(bytes: &[u8], index: usize) -> usize {
+    ((bytes[index] as usize) << 16) | ((bytes[index + 1] as usize) << 8) | (bytes[index + 2] as usize)
+}
+
+fn read_u16(bytes: &[u8], index: usize) -> usize {
+    ((bytes[index] as usize) << 8) | (bytes[index + 1] as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_records() {
+        let mut data = vec![0; 8];
+        let patch = b"PATCH\x00\x00\x02\x00\x02\xAA\xBB\x00\x00\x05\x00\x00\x00\x03\xCCEOF";
+        apply(&mut data, patch);
+        assert_eq!(data, [0, 0, 0xAA, 0xBB, 0, 0xCC, 0xCC, 0xCC]);
+    }
+
+    #[test]
+    fn extends_and_truncates() {
+        let mut data = vec![0; 4];
+        apply(&mut data, b"PATCH\x00\x00\x05\x00\x01\x11EOF");
+        assert_eq!(data, [0, 0, 0, 0, 0, 0x11]);
+
+        apply(&mut data, b"PATCHEOF\x00\x00\x02");
+        assert_eq!(data, [0, 0]);
+    }
+
+    #[test]
+    fn created_patch_applies() {
+        let original: Vec<u8> = (0..=255).collect();
+        let mut modified = original.clone();
+        modified[3] = 0;
+        modified[6] = 0;
+        modified[200] = 1;
+
+        let patch = create(&original, &modified);
+        let mut data = original.clone();
+        apply(&mut data, &patch);
+        assert_eq!(data, modified);
+    }
+
+    #[test]
+    fn created_patch_never_starts_a_record_at_eof() {
+        let original = vec![0; FOOTER_OFFSET + 1];
+        let mut modified = original.clone();
+        modified[FOOTER_OFFSET] = 1;
+
+        let patch = create(&original, &modified);
+        let mut data = original.clone();
+        apply(&mut data, &patch);
+        assert_eq!(data, modified);
+    }
+
+    #[test]
+    fn unchanged_file_makes_empty_patch() {
+        let data = vec![1, 2, 3];
+        assert!(is_empty(&create(&data, &data)));
+    }
+}
@@ -0,0 +1,91 @@
+pub mod bps;
+pub mod ips;
+pub mod ups;
+
+use std::fs;
+use std::path::Path;
+use crate::crc32;
+
+/// BPS and UPS patches both end with 3 CRC-32s
+const FOOTER_SIZE: usize = 12;
+const EXTENSIONS: [&str; 3] = ["ips", "bps", "ups"];
+
+/// Patches with the same name as the ROM, like game.ips for game.nes, in IPS, BPS, UPS order
+pub fn find_patches(file_path: &str) -> Vec<String> {
+    let path = Path::new(file_path);
+    EXTENSIONS.iter()
+        .map(|extension| path.with_extension(extension))
+        .filter(|patch_path| patch_path.exists())
+        .map(|patch_path| patch_path.to_str().expect("Error converting patch path to string").to_owned())
+        .collect()
+}
+
+/// Applies each patch in turn, so later patches are applied on top of the earlier ones
+pub fn apply_all(data: &mut Vec<u8>, patch_paths: &[String]) {
+    for patch_path in patch_paths {
+        let patch = fs::read(patch_path).expect("Cannot find patch");
+        let extension = Path::new(patch_path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match extension.to_lowercase().as_str() {
+            "ips" => ips::apply(data, &patch),
+            "bps" => bps::apply(data, &patch),
+            "ups" => ups::apply(data, &patch),
+            _ => panic!("Unrecognized patch extension {}", extension)
+        }
+    }
+}
+
+/// Numbers are stored 7 bits at a time, lowest first, with the top bit set on the last byte.
+/// Each byte after the first also adds one more of its place value so there's only one way to write each number.
+fn read_number(patch: &[u8], index: &mut usize) -> usize {
+    let mut number = 0;
+    let mut shift = 1;
+    loop {
+        let byte = patch[*index];
+        *index += 1;
+        number += ((byte & 0x7F) as usize) * shift;
+        if byte & 0x80 != 0 {
+            return number;
+        }
+
+        shift <<= 7;
+        number += shift;
+    }
+}
+
+fn read_u32(bytes: &[u8], index: usize) -> u32 {
+    u32::from_le_bytes([bytes[index], bytes[index + 1], bytes[index + 2], bytes[index + 3]])
+}
+
+fn verify_checksums(format: &str, source: &[u8], target: &[u8], source_crc: u32, target_crc: u32) {
+    if crc32::checksum(source) != source_crc {
+        panic!("{} patch is for a different ROM, the source checksum doesn't match", format);
+    }
+
+    if crc32::checksum(target) != target_crc {
+        panic!("{} patch didn't apply correctly, the target checksum doesn't match", format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BPS and UPS patches end with the CRC-32 of the source, the target and everything before the last one
+    pub fn add_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        patch.extend_from_slice(&crc32::checksum(source).to_le_bytes());
+        patch.extend_from_slice(&crc32::checksum(target).to_le_bytes());
+        let patch_crc = crc32::checksum(patch);
+        patch.extend_from_slice(&patch_crc.to_le_bytes());
+    }
+
+    #[test]
+    fn reads_numbers() {
+        let patch = [0x80, 0x85, 0x00, 0x80, 0x7F, 0x80];
+        let mut index = 0;
+        assert_eq!(read_number(&patch, &mut index), 0);
+        assert_eq!(read_number(&patch, &mut index), 5);
+        assert_eq!(read_number(&patch, &mut index), 128);
+        assert_eq!(read_number(&patch, &mut index), 255);
+        assert_eq!(index, patch.len());
+    }
+}
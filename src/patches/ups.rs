@@ -0,0 +1,90 @@
+use crate::crc32;
+use super::{read_number, read_u32, verify_checksums, FOOTER_SIZE};
+
+const HEADER: &[u8] = b"UPS1";
+
+/// A UPS patch is "UPS1", the source and target sizes and then a list of hunks, each one a number of bytes to skip
+/// followed by bytes to XOR with the source, ending in a 0 which also skips a byte.
+/// It ends with the CRC-32 of the source, the target and the patch itself.
+pub fn apply(data: &mut Vec<u8>, patch: &[u8]) {
+    if !patch.starts_with(HEADER) || patch.len() < HEADER.len() + FOOTER_SIZE {
+        panic!("Invalid UPS patch, missing UPS1 header");
+    }
+
+    let footer_index = patch.len() - FOOTER_SIZE;
+    let source_crc = read_u32(patch, footer_index);
+    let target_crc = read_u32(patch, footer_index + 4);
+    let patch_crc = read_u32(patch, footer_index + 8);
+    if crc32::checksum(&patch[..(footer_index + 8)]) != patch_crc {
+        panic!("UPS patch is corrupt, its checksum doesn't match");
+    }
+
+    let mut index = HEADER.len();
+    let source_size = read_number(patch, &mut index);
+    let target_size = read_number(patch, &mut index);
+    if data.len() != source_size {
+        panic!("UPS patch is for a {} byte file but the ROM is {} bytes", source_size, data.len());
+    }
+
+    // Anything past the end of the source is treated as 0
+    let mut target = data.to_owned();
+    target.resize(target_size.max(source_size), 0);
+
+    let mut position = 0;
+    while index < footer_index {
+        position += read_number(patch, &mut index);
+        while index < footer_index && patch[index] != 0 {
+            target[position] ^= patch[index];
+            position += 1;
+            index += 1;
+        }
+
+        position += 1;
+        index += 1;
+    }
+
+    target.truncate(target_size);
+    verify_checksums("UPS", data, &target, source_crc, target_crc);
+    *data = target;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::add_footer;
+
+    /// Skips the bytes that are the same and XORs the rest, the source and target have to be the same size
+    fn create_patch(source: &[u8], target: &[u8], skip: u8) -> Vec<u8> {
+        let mut patch = HEADER.to_vec();
+        patch.extend_from_slice(&[0x80 | source.len() as u8, 0x80 | target.len() as u8, 0x80 | skip]);
+        let mut padded = source.to_vec();
+        padded.resize(target.len(), 0);
+        patch.extend(padded.iter().zip(target).skip(skip as usize).map(|(a, b)| a ^ b));
+        patch.push(0);
+        add_footer(&mut patch, source, target);
+        patch
+    }
+
+    #[test]
+    fn applies_hunk() {
+        let mut data = b"hello world".to_vec();
+        let patch = create_patch(&data, b"hello there", 6);
+        apply(&mut data, &patch);
+        assert_eq!(data, b"hello there");
+    }
+
+    #[test]
+    fn extends_past_the_source() {
+        let mut data = b"hello".to_vec();
+        let patch = create_patch(&data, b"hello!!", 5);
+        apply(&mut data, &patch);
+        assert_eq!(data, b"hello!!");
+    }
+
+    #[test]
+    #[should_panic(expected = "different ROM")]
+    fn rejects_wrong_rom() {
+        let patch = create_patch(b"hello world", b"hello there", 6);
+        apply(&mut b"HELLO world".to_vec(), &patch);
+    }
+}
@@ -1,4 +1,11 @@
 pub type Color = (u8, u8, u8);
+pub type Emphasis = (bool, bool, bool);
+
+/// The 6 bit colour index, with the red, green and blue emphasis bits above it
+pub type Pixel = u16;
+
+/// How much the channels that aren't emphasized get darkened by
+const EMPHASIS_ATTENUATION: f32 = 0.816_328;
 
 pub const COLOR_RAM: [Color; 64] = [
     (84, 84, 84),
@@ -68,4 +75,25 @@ pub const COLOR_RAM: [Color; 64] = [
     (160, 162, 160),
     (0, 0, 0),
     (0, 0, 0)
-];
\ No newline at end of file
+];
+
+/// Packs the 6 bit colour index and the emphasis bits into a Pixel
+pub fn to_pixel(color_index: u8, emphasis: Emphasis) -> Pixel {
+    let (red, green, blue) = emphasis;
+    ((color_index & 0x3F) as Pixel) | ((red as Pixel) << 6) | ((green as Pixel) << 7) | ((blue as Pixel) << 8)
+}
+
+/// Emphasizing a color darkens the other two channels
+pub fn emphasize(color: Color, emphasis: Emphasis) -> Color {
+    let (red, green, blue) = color;
+    let (emphasize_red, emphasize_green, emphasize_blue) = emphasis;
+    if !(emphasize_red || emphasize_green || emphasize_blue) {
+        return color;
+    }
+
+    let attenuate = |channel: u8, emphasized: bool| {
+        if emphasized { channel } else { ((channel as f32) * EMPHASIS_ATTENUATION) as u8 }
+    };
+
+    (attenuate(red, emphasize_red), attenuate(green, emphasize_green), attenuate(blue, emphasize_blue))
+}
@@ -2,20 +2,60 @@ use serde::{Serialize, Deserialize};
 use std::time::{Duration, Instant};
 use crate::instant::InstantWrapper;
 
+/// The speeds that - and = step through, as a percentage of the console's speed
+pub const SPEEDS: [u16; 8] = [25, 50, 75, 100, 150, 200, 300, 400];
+pub const NORMAL_SPEED: u16 = 100;
+
+/// How fast to run compared to the console, turbo runs as fast as possible
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    Percent(u16),
+    Turbo
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::Percent(NORMAL_SPEED)
+    }
+}
+
+impl Speed {
+    pub fn is_normal(&self) -> bool {
+        *self == Speed::Percent(NORMAL_SPEED)
+    }
+
+    /// The next speed up or down the list, staying at the ends
+    pub fn step(&self, faster: bool) -> Self {
+        let percent = match self {
+            Speed::Percent(percent) => *percent,
+            Speed::Turbo => NORMAL_SPEED
+        };
+
+        let index = SPEEDS.iter().position(|s| *s == percent).unwrap_or(3);
+        let index = if faster { (index + 1).min(SPEEDS.len() - 1) } else { index.saturating_sub(1) };
+        Speed::Percent(SPEEDS[index])
+    }
+}
+
+/// Frames are timed against a running deadline rather than from the end of the last frame,
+/// so time spent outside of the sleep doesn't add up and the frame rate stays exact
 #[derive(Serialize, Deserialize)]
 pub struct FpsLimiter {
-    fps: u8,
+    fps: f64,
     frames: u64,
     #[serde(skip_serializing, skip_deserializing)]
-    fps_timer: InstantWrapper
+    fps_timer: InstantWrapper,
+    #[serde(skip_serializing, skip_deserializing)]
+    next_frame: InstantWrapper
 }
 
 impl FpsLimiter {
-    pub fn new(fps: u8) -> Self {
+    pub fn new(fps: f64) -> Self {
         FpsLimiter {
             fps,
             frames: 0,
-            fps_timer: Default::default()
+            fps_timer: Default::default(),
+            next_frame: Default::default()
         }
     }
     
@@ -27,12 +67,24 @@ impl FpsLimiter {
         }
     }
 
-    pub fn limit(&mut self, timer: &InstantWrapper) {
+    pub fn limit(&mut self, speed: Speed) {
         self.frames += 1;
         let now = Instant::now();
-        let milliseconds: u64 = (1000 as u64) / (self.fps as u64);
-        if now < timer.instant + Duration::from_millis(milliseconds) {
-            std::thread::sleep(timer.instant + Duration::from_millis(milliseconds) - now);
+        let percent = match speed {
+            Speed::Percent(percent) => percent,
+            Speed::Turbo => {
+                self.next_frame.instant = now;
+                return;
+            }
+        };
+
+        let frame_time = Duration::from_secs_f64((NORMAL_SPEED as f64) / (self.fps * (percent as f64)));
+        self.next_frame.instant += frame_time;
+        if now < self.next_frame.instant {
+            std::thread::sleep(self.next_frame.instant - now);
+        } else if now > self.next_frame.instant + frame_time {
+            // Fell more than a frame behind, like after being paused, so start timing again from now instead of rushing to catch up
+            self.next_frame.instant = now;
         }
     }
 }
\ No newline at end of file
@@ -18,12 +18,13 @@ use std::cell::RefCell;
 use crate::addresses::cpu::*;
 use crate::addresses::ppu::*;
 use crate::cartridge;
+use crate::region::Region;
 
-const IRQ_CLOCK_CYCLE: u16 = 260;
 const MAX_CLOCK_CYCLE: u16 = 341;
-const MAX_SCANLINE: i16 = 261;
 const MAX_VISIBLE_SCANLINE: i16 = 239;
 const MAX_VISIBLE_CLOCK_CYCLE: u16 = 257;
+const SPRITE_FETCH_END_CYCLE: u16 = 321;
+const SPRITE_FETCH_SLOTS: usize = 8;
 
 #[derive(Serialize, Deserialize)]
 pub struct Ppu2C02 {
@@ -32,6 +33,7 @@ pub struct Ppu2C02 {
     #[serde(skip_serializing, skip_deserializing)]
     pub frame: frame::Frame,
     pub oam: oam::ObjectAttributeMemory,
+    pub region: Region,
     address_latch: bool,
     background: background::Background,
     control: flags::Control,
@@ -61,6 +63,7 @@ impl Ppu2C02 {
             cycle: 0,
             frame: frame::Frame::new(),
             oam: oam::ObjectAttributeMemory::new(),
+            region: Region::Ntsc,
             status: flags::Status(0),
             control: flags::Control(0),
             mask: flags::Mask(0),
@@ -76,8 +79,8 @@ impl Ppu2C02 {
    
     pub fn clock(&mut self) -> bool {
         if self.scanline >= -1 && self.scanline <= MAX_VISIBLE_SCANLINE {
-            // Skipped on BG+odd
-            if self.scanline == 0 && self.cycle == 0 {
+            // Skipped on BG+odd, PAL PPUs don't skip a cycle
+            if self.scanline == 0 && self.cycle == 0 && self.region == Region::Ntsc {
                 self.cycle = 1;
             }
 
@@ -97,7 +100,7 @@ impl Ppu2C02 {
             // Post render scanline does nothing
         }
 
-        if self.scanline == 241 && self.scanline < 261 && self.cycle == 1 {
+        if self.scanline == self.region.get_vertical_blank_scanline() && self.cycle == 1 {
             self.status.set_vertical_blank(true);
             if self.control.generate_nmi() {
                 self.nmi = true;
@@ -110,19 +113,10 @@ impl Ppu2C02 {
 
         self.cycle += 1;
 
-        // The IRQ counter should decrement on PPU cycle 260, right after the visible part of the target scanline has ended
-        if self.mask.is_rendering_enabled() && self.cycle == IRQ_CLOCK_CYCLE && self.scanline < 240 {
-            if let Some(ref mut c) = self.cartridge {
-                if let Some(ref mut m) = c.borrow_mut().mapper {
-                    m.irq_scanline();
-                }
-            }
-        }
-
         if self.cycle >= MAX_CLOCK_CYCLE {
             self.cycle = 0;
             self.scanline += 1;
-            if self.scanline >= MAX_SCANLINE {
+            if self.scanline >= self.region.get_max_scanline() {
                 self.scanline = -1;
             }
         }
@@ -142,7 +136,10 @@ impl Ppu2C02 {
         }
 
         if ppu_address <= PATTERN_ADDRESS_UPPER {
-            data = self.pattern_table.read_data(ppu_address);
+            data = match self.get_pattern_ciram_page(ppu_address) {
+                Some(page) => self.name_table.read_page(page, ppu_address),
+                None => self.pattern_table.read_data(ppu_address)
+            };
         } else if ppu_address >= NAME_TABLE_ADDRESS_LOWER && ppu_address <= NAME_TABLE_ADDRESS_UPPER {
             data = self.name_table.read_data(ppu_address, &self.cartridge);
         } else if ppu_address >= PALETTE_ADDRESS_LOWER && ppu_address <= PALETTE_ADDRESS_UPPER {
@@ -152,6 +149,23 @@ impl Ppu2C02 {
         data
     }
 
+    /// Read from the PPU Bus as part of rendering.
+    /// The address is only put on the bus while rendering is enabled, which is what mappers watching the bus rely on.
+    fn fetch(&self, address: u16) -> u8 {
+        if self.mask.is_rendering_enabled() {
+            self.notify_address(address);
+        }
+
+        self.ppu_read(address)
+    }
+
+    /// Let the cartridge see the address on the PPU Bus, the MMC3 counts scanlines by watching A12
+    fn notify_address(&self, address: u16) {
+        if let Some(ref c) = self.cartridge {
+            c.borrow_mut().ppu_address(address & PPU_ADDRESS_END);
+        }
+    }
+
     /// Write to the PPU Bus
     fn ppu_write(&mut self, address: u16, data: u8) {
         let ppu_address = address & PPU_ADDRESS_END;
@@ -162,7 +176,10 @@ impl Ppu2C02 {
         }
 
         if ppu_address <= PATTERN_ADDRESS_UPPER {
-            self.pattern_table.write_data(ppu_address, data);
+            match self.get_pattern_ciram_page(ppu_address) {
+                Some(page) => self.name_table.write_page(page, ppu_address, data),
+                None => self.pattern_table.write_data(ppu_address, data)
+            }
         } else if ppu_address >= NAME_TABLE_ADDRESS_LOWER && ppu_address <= NAME_TABLE_ADDRESS_UPPER {
             self.name_table.write_data(ppu_address, &self.cartridge, data);
         } else if ppu_address >= PALETTE_ADDRESS_LOWER && ppu_address <= PALETTE_ADDRESS_UPPER {
@@ -170,6 +187,13 @@ impl Ppu2C02 {
         }
     }
 
+    fn get_pattern_ciram_page(&self, address: u16) -> Option<u8> {
+        match self.cartridge {
+            Some(ref c) => c.borrow().get_pattern_ciram_page(address),
+            None => None
+        }
+    }
+
     fn render_background(&mut self) {
         if (self.cycle >= 2 && self.cycle <= MAX_VISIBLE_CLOCK_CYCLE) || (self.cycle >= 321 && self.cycle < 338) {
             self.update_shifters();
@@ -178,10 +202,10 @@ impl Ppu2C02 {
             if sub_cycle == 0 {
                 self.background.load_shifters();
                 let name_table_address = self.current_vram_address.name_table_address();
-                self.background.next_tile_id = self.ppu_read(name_table_address);
+                self.background.next_tile_id = self.fetch(name_table_address);
             } else if sub_cycle == 2 {
                 let attribute_table_address = self.current_vram_address.attribute_table_address();
-                self.background.next_tile_attribute = self.ppu_read(attribute_table_address);
+                self.background.next_tile_attribute = self.fetch(attribute_table_address);
 
                 // Since there are only 4 palettes for the background tiles, we only need 2 bits to select a palette(2 bits range is 0-3)
                 // We get a byte of data we can split that byte up into 4 sets of 2 bits.
@@ -201,10 +225,10 @@ impl Ppu2C02 {
                 self.background.next_tile_attribute &= 0x03;
             } else if sub_cycle == 4 {
                 let pattern_address = self.get_pattern_address(0);
-                self.background.next_tile_lsb = self.ppu_read(pattern_address);
+                self.background.next_tile_lsb = self.fetch(pattern_address);
             } else if sub_cycle == 6 {
                 let pattern_address = self.get_pattern_address(8);
-                self.background.next_tile_msb = self.ppu_read(pattern_address);
+                self.background.next_tile_msb = self.fetch(pattern_address);
             } else if sub_cycle == 7 && self.mask.is_rendering_enabled() {
                 self.current_vram_address.increment_x();
             }
@@ -231,40 +255,71 @@ impl Ppu2C02 {
         // Useless read of the tile id at the end of the scanline
         if self.cycle == 338 || self.cycle == 340 {
             let name_table_address = self.current_vram_address.name_table_address();
-            self.background.next_tile_id = self.ppu_read(name_table_address);
+            self.background.next_tile_id = self.fetch(name_table_address);
         }
     }
 
     fn render_foreground(&mut self) {
         // This isn't exactly how the NES does foreground rendering, however it gets there most of the way
-        if self.cycle == MAX_VISIBLE_CLOCK_CYCLE && self.scanline >= 0 {
+        if self.cycle == MAX_VISIBLE_CLOCK_CYCLE {
             self.evaluate_sprites();
         }
 
-        if self.cycle == MAX_CLOCK_CYCLE - 1 {
-            for i in 0..self.sprite.count {
-                let (sprite_pattern_address_low, flip_horizontally) = self.sprite.get_pattern_address(
-                    i,
-                    self.control.sprite_size(), 
-                    self.control.sprite_table_address() as u16,
-                    self.scanline
-                );
-
-                let sprite_pattern_address_high = sprite_pattern_address_low + 8;
-                let mut sprite_pattern_bit_low = self.ppu_read(sprite_pattern_address_low);
-                let mut sprite_pattern_bit_high = self.ppu_read(sprite_pattern_address_high);
-
-                if flip_horizontally {
-                    sprite_pattern_bit_low = sprites::flip_byte_horizontally(sprite_pattern_bit_low);
-                    sprite_pattern_bit_high = sprites::flip_byte_horizontally(sprite_pattern_bit_high);
-                }
+        // The patterns for the next scanline are fetched 8 cycles per sprite during cycles 257-320,
+        // the low byte on the 5th cycle and the high byte on the 7th.
+        // All 8 slots are fetched even if there are fewer sprites, which mappers watching the bus depend on.
+        if self.cycle >= MAX_VISIBLE_CLOCK_CYCLE && self.cycle < SPRITE_FETCH_END_CYCLE {
+            let slot = ((self.cycle - MAX_VISIBLE_CLOCK_CYCLE) / 8) as usize;
+            let sub_cycle = (self.cycle - MAX_VISIBLE_CLOCK_CYCLE) % 8;
+            if sub_cycle == 4 {
+                self.fetch_sprite_pattern(slot, false);
+            } else if sub_cycle == 6 {
+                self.fetch_sprite_pattern(slot, true);
+            }
+        }
 
-                self.sprite.shifter_pattern_low[i] = sprite_pattern_bit_low;
-                self.sprite.shifter_pattern_high[i] = sprite_pattern_bit_high;
+        // Sprites past the 8th are still drawn, there's no time left for them on real hardware so they're fetched all at once
+        if self.cycle == SPRITE_FETCH_END_CYCLE - 1 {
+            for slot in SPRITE_FETCH_SLOTS..self.sprite.count {
+                let (address, flip_horizontally) = self.get_sprite_pattern_address(slot);
+                self.load_sprite_pattern(slot, self.ppu_read(address), false, flip_horizontally);
+                self.load_sprite_pattern(slot, self.ppu_read(address + 8), true, flip_horizontally);
             }
         }
     }
 
+    fn fetch_sprite_pattern(&mut self, slot: usize, high: bool) {
+        let offset = if high { 8 } else { 0 };
+        if slot >= self.sprite.count {
+            // Empty slots fetch tile $FF
+            let pattern_table = if self.control.sprite_size() { 0x1000 } else { (self.control.sprite_table_address() as u16) << 12 };
+            self.fetch(pattern_table | (0xFF << 4) | offset);
+            return;
+        }
+
+        let (address, flip_horizontally) = self.get_sprite_pattern_address(slot);
+        let data = self.fetch(address + offset);
+        self.load_sprite_pattern(slot, data, high, flip_horizontally);
+    }
+
+    fn get_sprite_pattern_address(&mut self, slot: usize) -> (u16, bool) {
+        self.sprite.get_pattern_address(
+            slot,
+            self.control.sprite_size(),
+            self.control.sprite_table_address() as u16,
+            self.scanline
+        )
+    }
+
+    fn load_sprite_pattern(&mut self, slot: usize, data: u8, high: bool, flip_horizontally: bool) {
+        let data = if flip_horizontally { sprites::flip_byte_horizontally(data) } else { data };
+        if high {
+            self.sprite.shifter_pattern_high[slot] = data;
+        } else {
+            self.sprite.shifter_pattern_low[slot] = data;
+        }
+    }
+
     /// Check to see if each sprite should be rendered on the current scanline
     /// This is done checking the y coordinates of each sprite to the current visible scanline
     /// If it's greater than 8, then set sprite overflow
@@ -325,9 +380,9 @@ impl Ppu2C02 {
             }
         }
 
-        let color = self.get_color_from_palette(palette as u16, pixel as u16);
+        let frame_pixel = self.get_pixel_from_palette(palette as u16, pixel as u16);
         if self.cycle > 0 {
-            self.frame.set_pixel((self.cycle - 1) as usize, self.scanline as usize, color);
+            self.frame.set_pixel((self.cycle - 1) as usize, self.scanline as usize, frame_pixel);
         }
     }
 
@@ -374,10 +429,21 @@ impl Ppu2C02 {
         }
     }
 
-    pub fn get_color_from_palette(&self, palette_id: u16, pixel_id: u16) -> colors::Color {
+    pub fn get_pixel_from_palette(&self, palette_id: u16, pixel_id: u16) -> colors::Pixel {
         let address = PALETTE_ADDRESS_LOWER + (palette_id * 4) + pixel_id;
         let color_index = self.ppu_read(address) & 0x3F; // Make sure we don't go out of bounds
-        colors::COLOR_RAM[color_index as usize]
+        colors::to_pixel(color_index, self.get_emphasis())
+    }
+
+    /// Red, green and blue emphasis, PAL and Dendy PPUs have the red and green bits the other way around
+    fn get_emphasis(&self) -> colors::Emphasis {
+        let (red, green) = if self.region.swaps_emphasis() {
+            (self.mask.emphasize_green(), self.mask.emphasize_red())
+        } else {
+            (self.mask.emphasize_red(), self.mask.emphasize_green())
+        };
+
+        (red, green, self.mask.emphasize_blue())
     }
 
     fn get_pattern_address(&mut self, offset: u16) -> u16 {
@@ -386,4 +452,4 @@ impl Ppu2C02 {
         (self.current_vram_address.fine_y() as u16) +
         offset
     }
-}
\ No newline at end of file
+}
@@ -48,7 +48,14 @@ impl NameTable {
                     },
                     Mirror::OneScreenHigh => {
                         self.data[(KILOBYTES_1 as usize) + name_table_address.address_offset]
-                    }
+                    },
+                    Mirror::Mapped(pages) => {
+                        match pages[name_table_address.quadrant] {
+                            0 => self.data[name_table_address.address_offset],
+                            1 => self.data[(KILOBYTES_1 as usize) + name_table_address.address_offset],
+                            _ => 0
+                        }
+                    },
                     _ => 0
                 }
             }
@@ -82,7 +89,14 @@ impl NameTable {
                     },
                     Mirror::OneScreenHigh => {
                         self.data[(KILOBYTES_1 as usize) + name_table_address.address_offset] = data
-                    }
+                    },
+                    Mirror::Mapped(pages) => {
+                        match pages[name_table_address.quadrant] {
+                            0 => self.data[name_table_address.address_offset] = data,
+                            1 => self.data[(KILOBYTES_1 as usize) + name_table_address.address_offset] = data,
+                            _ => ()
+                        }
+                    },
                     _ => ()
                 }
             },
@@ -91,9 +105,21 @@ impl NameTable {
     }
 }
 
+impl NameTable {
+    /// Reads from a CIRAM page directly, for cartridges that map it somewhere other than the name tables
+    pub fn read_page(&self, page: u8, address: u16) -> u8 {
+        self.data[(page as usize & 0b1) * (KILOBYTES_1 as usize) + (address & KILOBYTES_1_MASK) as usize]
+    }
+
+    pub fn write_page(&mut self, page: u8, address: u16, data: u8) {
+        self.data[(page as usize & 0b1) * (KILOBYTES_1 as usize) + (address & KILOBYTES_1_MASK) as usize] = data;
+    }
+}
+
 struct NameTableAddress {
     address_offset: usize,
-    masked_address: u16
+    masked_address: u16,
+    quadrant: usize
 }
 
 impl NameTableAddress {
@@ -103,7 +129,8 @@ impl NameTableAddress {
 
         NameTableAddress {
             masked_address,
-            address_offset
+            address_offset,
+            quadrant: (masked_address >> 10) as usize
         }
     }
 }
@@ -52,6 +52,7 @@ impl super::Ppu2C02 {
     fn read_ppu_data(&mut self) -> u8 {
         let address = self.current_vram_address.get();
         let mut data = self.ppu_data_buffer;
+        self.notify_address(address);
         self.ppu_data_buffer = self.ppu_read(address);
 
         /*
@@ -135,10 +136,12 @@ impl super::Ppu2C02 {
 
             self.current_vram_address.set(self.temp_vram_address.get());
             self.address_latch = false;
+            self.notify_address(self.current_vram_address.get());
         }
     }
 
     fn write_ppu_data(&mut self, data: u8) {
+        self.notify_address(self.current_vram_address.get());
         self.ppu_write(self.current_vram_address.get(), data);
         if self.mask.is_rendering_enabled() && (self.scanline < 240 || self.scanline == 261) {
             self.current_vram_address.increment_x();
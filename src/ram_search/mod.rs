@@ -0,0 +1,96 @@
+pub mod watch;
+
+use crate::addresses::mappers::{PRG_RAM_MIN_ADDRESS, PRG_RAM_MAX_ADDRESS};
+use crate::bus::Bus;
+
+const RAM_SIZE: u16 = 0x0800;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Byte,
+    Word
+}
+
+/// Compares each candidate against the value it had at the last snapshot, or against a specific value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+    Value(i32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub address: u16,
+    pub value: i32
+}
+
+/// Narrows down where a game keeps a variable by taking a snapshot of RAM and PRG RAM,
+/// then keeping only the addresses that match each filter as the game runs
+pub struct RamSearch {
+    pub size: Size,
+    pub signed: bool,
+    candidates: Vec<Candidate>
+}
+
+impl RamSearch {
+    pub fn new(bus: &Bus, size: Size, signed: bool) -> Self {
+        // RAM is mirrored up to $1FFF so only the first copy is searched
+        let addresses = (0..RAM_SIZE).chain(PRG_RAM_MIN_ADDRESS..=PRG_RAM_MAX_ADDRESS);
+        let candidates = addresses
+            .filter_map(|address| read_value(bus, address, size, signed).map(|value| Candidate { address, value }))
+            .collect();
+
+        RamSearch {
+            size,
+            signed,
+            candidates
+        }
+    }
+
+    /// Keeps the candidates that match and takes a new snapshot of their values for the next filter
+    pub fn filter(&mut self, bus: &Bus, filter: Filter) {
+        let (size, signed) = (self.size, self.signed);
+        self.candidates.retain_mut(|candidate| {
+            let value = match read_value(bus, candidate.address, size, signed) {
+                Some(value) => value,
+                None => return false
+            };
+
+            let keep = match filter {
+                Filter::Equal => value == candidate.value,
+                Filter::Changed => value != candidate.value,
+                Filter::Increased => value > candidate.value,
+                Filter::Decreased => value < candidate.value,
+                Filter::Value(v) => value == v
+            };
+
+            candidate.value = value;
+            keep
+        });
+    }
+
+    pub fn get_candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+}
+
+/// Words are little endian like everything else on the 6502
+pub fn read_value(bus: &Bus, address: u16, size: Size, signed: bool) -> Option<i32> {
+    let low = bus.peek(address)?;
+    match (size, signed) {
+        (Size::Byte, false) => Some(low as i32),
+        (Size::Byte, true) => Some(low as i8 as i32),
+        (Size::Word, _) => {
+            // The byte after the end of RAM is the start of its mirror, and after PRG RAM is ROM
+            if address == RAM_SIZE - 1 || address == PRG_RAM_MAX_ADDRESS {
+                return None;
+            }
+
+            let word = u16::from_le_bytes([low, bus.peek(address + 1)?]);
+            Some(if signed { word as i16 as i32 } else { word as i32 })
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::bus::Bus;
+use super::{Size, read_value};
+
+pub struct Watch {
+    pub address: u16,
+    pub size: Size,
+    pub signed: bool,
+    pub label: Option<String>
+}
+
+/// Addresses to keep an eye on while the game runs
+#[derive(Default)]
+pub struct WatchList {
+    watches: Vec<Watch>,
+    last_line: String
+}
+
+impl WatchList {
+    /// Watching an address again replaces the old watch
+    pub fn add(&mut self, watch: Watch) {
+        self.remove(watch.address);
+        self.watches.push(watch);
+    }
+
+    pub fn remove(&mut self, address: u16) {
+        self.watches.retain(|watch| watch.address != address);
+    }
+
+    /// Every watch and its current value on one line, like "$0075=9 lives=3"
+    pub fn format(&self, bus: &Bus) -> String {
+        let values: Vec<String> = self.watches.iter().map(|watch| {
+            let name = match watch.label {
+                Some(ref label) => label.to_owned(),
+                None => format!("${:04X}", watch.address)
+            };
+
+            match read_value(bus, watch.address, watch.size, watch.signed) {
+                Some(value) => format!("{}={}", name, value),
+                None => format!("{}=?", name)
+            }
+        }).collect();
+
+        values.join(" ")
+    }
+
+    /// Called every frame, gives back the watch line when any of the values changed since the last frame
+    pub fn update(&mut self, bus: &Bus) -> Option<String> {
+        if self.watches.is_empty() {
+            return None;
+        }
+
+        let line = self.format(bus);
+        if line == self.last_line {
+            return None;
+        }
+
+        self.last_line = line.clone();
+        Some(line)
+    }
+}
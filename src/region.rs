@@ -0,0 +1,76 @@
+use serde::{Serialize, Deserialize};
+
+const DOTS_PER_SCANLINE: f64 = 341.0;
+
+/// The console region changes how fast everything runs relative to the PPU.
+/// PAL consoles run the CPU at a 3.2:1 ratio, have 312 scanlines and 50 frames a second.
+/// Dendy famiclones are a hybrid: PAL's 312 scanlines and 50 frames a second with NTSC's 3:1 ratio and APU,
+/// with vertical blank starting 50 scanlines later so the same number of CPU cycles happen in it as on NTSC.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum Region {
+    #[default]
+    Ntsc,
+    Pal,
+    Dendy
+}
+
+impl Region {
+    pub fn from(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ntsc" => Some(Region::Ntsc),
+            "pal" => Some(Region::Pal),
+            "dendy" => Some(Region::Dendy),
+            _ => None
+        }
+    }
+
+    /// How many PPU clocks happen for a given number of CPU clocks
+    pub fn get_clock_ratio(&self) -> (u32, u32) {
+        match self {
+            Region::Pal => (16, 5),
+            _ => (3, 1)
+        }
+    }
+
+    pub fn get_cpu_frequency(&self) -> f32 {
+        match self {
+            Region::Ntsc => 1_789_773.0,
+            Region::Pal => 1_662_607.0,
+            Region::Dendy => 1_773_448.0
+        }
+    }
+
+    /// The exact frame rate, from how many PPU dots are in a frame.
+    /// NTSC skips a dot every other frame so it averages half a dot less.
+    pub fn get_fps(&self) -> f64 {
+        let (ppu_clocks, cpu_clocks) = self.get_clock_ratio();
+        let ppu_frequency = (self.get_cpu_frequency() as f64) * (ppu_clocks as f64) / (cpu_clocks as f64);
+        let dots = (DOTS_PER_SCANLINE * ((self.get_max_scanline() + 1) as f64)) - if *self == Region::Ntsc { 0.5 } else { 0.0 };
+        ppu_frequency / dots
+    }
+
+    /// The last scanline, with the pre-render scanline counted as -1
+    pub fn get_max_scanline(&self) -> i16 {
+        match self {
+            Region::Ntsc => 261,
+            _ => 311
+        }
+    }
+
+    pub fn get_vertical_blank_scanline(&self) -> i16 {
+        match self {
+            Region::Dendy => 291,
+            _ => 241
+        }
+    }
+
+    /// PAL PPUs have the red and green emphasis bits swapped
+    pub fn swaps_emphasis(&self) -> bool {
+        *self != Region::Ntsc
+    }
+
+    /// Dendy clones use NTSC's APU tables and frame counter timing
+    pub fn has_pal_apu(&self) -> bool {
+        *self == Region::Pal
+    }
+}
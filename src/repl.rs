@@ -0,0 +1,347 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use crate::audio::mixer::{Channel, CHANNELS};
+use crate::bus::Bus;
+use crate::ram_search::{Filter, RamSearch, Size};
+use crate::ram_search::watch::{Watch, WatchList};
+
+const DEFAULT_RESULTS: usize = 20;
+const HELP: &str = "\
+peek <address> [count]                  Show bytes of RAM or PRG RAM
+poke <address> <value>                  Change a byte of RAM or PRG RAM
+search new [8|16] [signed|unsigned]     Take a snapshot of every address to start a search
+search equal|changed|increased|decreased
+                                        Keep the addresses that compare that way to the last snapshot
+search value <value>                    Keep the addresses that have the value
+results [count]                         Show what's left of the search
+watch <address> [8|16] [signed|unsigned] [label]
+                                        Show the address whenever it changes
+unwatch <address>                       Stop watching the address
+mixer                                   Show the volume of each sound channel
+mute|unmute|solo <channel>              Mute, unmute or solo pulse1, pulse2, triangle, noise, dmc or expansion
+volume <channel> <percent>              Turn a sound channel down
+pan <channel> <-100 to 100>             Pan a sound channel left or right in stereo
+wait <frames>                           Let the game run before reading the next command, for scripts
+Numbers can be decimal, or hex starting with $ or 0x";
+
+/// Reads commands from stdin while the game runs so RAM can be searched and watched from a script.
+/// Commands are run between frames so the game is never stopped halfway through one.
+pub struct Repl {
+    closed: bool,
+    receiver: Receiver<String>,
+    search: Option<RamSearch>,
+    wait_frames: u32,
+    watch_list: WatchList
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let line = line.expect("Error reading from stdin");
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Repl {
+            closed: false,
+            receiver,
+            search: None,
+            wait_frames: 0,
+            watch_list: Default::default()
+        }
+    }
+
+    /// Runs the commands typed since the last frame and prints any watches that changed, called once a frame
+    pub fn update(&mut self, bus: &mut Bus) {
+        if self.wait_frames > 0 {
+            self.wait_frames -= 1;
+        }
+
+        while self.wait_frames == 0 {
+            let command = match self.receiver.try_recv() {
+                Ok(command) => command,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            };
+
+            let output = self.execute(bus, &command);
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+        }
+
+        if let Some(line) = self.watch_list.update(bus) {
+            println!("{}", line);
+        }
+    }
+
+    /// True once stdin has closed and every command from it has run
+    pub fn is_finished(&self) -> bool {
+        self.closed && self.wait_frames == 0
+    }
+
+    pub fn execute(&mut self, bus: &mut Bus, command: &str) -> String {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let result = match words.as_slice() {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_owned()),
+            ["peek", address] => peek(bus, address, "1"),
+            ["peek", address, count] => peek(bus, address, count),
+            ["poke", address, value] => poke(bus, address, value),
+            ["search", "new", options @ ..] => self.start_search(bus, options),
+            ["search", "value", value] => parse_number(value).and_then(|v| self.filter(bus, Filter::Value(v))),
+            ["search", filter] => parse_filter(filter).and_then(|f| self.filter(bus, f)),
+            ["results"] => self.results(DEFAULT_RESULTS),
+            ["results", count] => parse_number(count).and_then(|c| self.results(c as usize)),
+            ["watch", address, options @ ..] => self.watch(address, options),
+            ["unwatch", address] => parse_address(address).map(|a| {
+                self.watch_list.remove(a);
+                String::new()
+            }),
+            ["mixer"] => Ok(show_mixer(bus)),
+            ["mute", channel] => parse_channel(channel).map(|c| {
+                bus.apu.mixer.set_muted(c, true);
+                String::new()
+            }),
+            ["unmute", channel] => parse_channel(channel).map(|c| {
+                bus.apu.mixer.set_muted(c, false);
+                String::new()
+            }),
+            ["solo", channel] => parse_channel(channel).map(|c| {
+                bus.apu.mixer.toggle_solo(c);
+                String::new()
+            }),
+            ["volume", channel, percent] => parse_channel(channel).and_then(|c| {
+                let percent = parse_number(percent)?;
+                bus.apu.mixer.set_volume(c, (percent as f32) / 100.0);
+                Ok(String::new())
+            }),
+            ["pan", channel, pan] => parse_channel(channel).and_then(|c| {
+                let pan = parse_number(pan)?;
+                bus.apu.mixer.set_pan(c, (pan as f32) / 100.0);
+                Ok(String::new())
+            }),
+            ["wait", frames] => parse_count(frames).map(|f| {
+                self.wait_frames = f;
+                String::new()
+            }),
+            _ => Err(format!("Unknown command {}, type help to see the commands", command))
+        };
+
+        result.unwrap_or_else(|error| error)
+    }
+
+    fn start_search(&mut self, bus: &Bus, options: &[&str]) -> Result<String, String> {
+        let (size, signed, _) = parse_options(options)?;
+        let search = RamSearch::new(bus, size, signed);
+        let message = format!("{} candidates", search.get_candidates().len());
+        self.search = Some(search);
+        Ok(message)
+    }
+
+    fn filter(&mut self, bus: &Bus, filter: Filter) -> Result<String, String> {
+        let search = self.search.as_mut().ok_or("Start a search with search new first")?;
+        search.filter(bus, filter);
+        Ok(format!("{} candidates", search.get_candidates().len()))
+    }
+
+    fn results(&self, count: usize) -> Result<String, String> {
+        let search = self.search.as_ref().ok_or("Start a search with search new first")?;
+        let candidates = search.get_candidates();
+        let mut lines = vec![format!("{} candidates", candidates.len())];
+        for candidate in candidates.iter().take(count) {
+            lines.push(format!("${:04X} = {}", candidate.address, candidate.value));
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    fn watch(&mut self, address: &str, options: &[&str]) -> Result<String, String> {
+        let address = parse_address(address)?;
+        let (size, signed, label) = parse_options(options)?;
+        self.watch_list.add(Watch { address, size, signed, label });
+        Ok(String::new())
+    }
+}
+
+fn peek(bus: &Bus, address: &str, count: &str) -> Result<String, String> {
+    let address = parse_address(address)?;
+    let count = parse_number(count)?;
+    let bytes: Vec<String> = (0..count)
+        .map(|offset| match bus.peek(address.wrapping_add(offset as u16)) {
+            Some(data) => format!("{:02X}", data),
+            None => "??".to_owned()
+        })
+        .collect();
+
+    Ok(format!("${:04X}: {}", address, bytes.join(" ")))
+}
+
+fn poke(bus: &mut Bus, address: &str, value: &str) -> Result<String, String> {
+    let address = parse_address(address)?;
+    let value = parse_number(value)?;
+    if bus.peek(address).is_none() {
+        return Err(format!("${:04X} isn't RAM or PRG RAM", address));
+    }
+
+    bus.poke(address, value as u8);
+    Ok(String::new())
+}
+
+fn show_mixer(bus: &Bus) -> String {
+    let mixer = &bus.apu.mixer;
+    let lines: Vec<String> = CHANNELS.iter().map(|channel| {
+        let mut line = format!("{:?} {}%", channel, (mixer.get_volume(*channel) * 100.0).round());
+        if mixer.stereo {
+            line.push_str(&format!(" pan {}", (mixer.get_pan(*channel) * 100.0).round()));
+        }
+
+        if mixer.is_muted(*channel) {
+            line.push_str(" muted");
+        }
+
+        if mixer.get_solo() == Some(*channel) {
+            line.push_str(" solo");
+        }
+
+        line
+    }).collect();
+
+    lines.join("\n")
+}
+
+/// 8 or 16 bits and signed or unsigned in any order, anything else is the label
+fn parse_options(options: &[&str]) -> Result<(Size, bool, Option<String>), String> {
+    let mut size = Size::Byte;
+    let mut signed = false;
+    let mut label = None;
+    for option in options {
+        match *option {
+            "8" => size = Size::Byte,
+            "16" => size = Size::Word,
+            "signed" => signed = true,
+            "unsigned" => signed = false,
+            _ if label.is_none() => label = Some(option.to_string()),
+            _ => return Err(format!("Unknown option {}", option))
+        }
+    }
+
+    Ok((size, signed, label))
+}
+
+fn parse_channel(name: &str) -> Result<Channel, String> {
+    Channel::from(name).ok_or_else(|| format!("Unknown channel {}", name))
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    match filter {
+        "equal" => Ok(Filter::Equal),
+        "changed" => Ok(Filter::Changed),
+        "increased" => Ok(Filter::Increased),
+        "decreased" => Ok(Filter::Decreased),
+        _ => Err(format!("Unknown search filter {}", filter))
+    }
+}
+
+fn parse_address(text: &str) -> Result<u16, String> {
+    let number = parse_number(text)?;
+    if !(0..=0xFFFF).contains(&number) {
+        return Err(format!("{} isn't an address", text));
+    }
+
+    Ok(number as u16)
+}
+
+fn parse_count(text: &str) -> Result<u32, String> {
+    let number = parse_number(text)?;
+    if number < 0 {
+        return Err(format!("{} can't be negative", text));
+    }
+
+    Ok(number as u32)
+}
+
+fn parse_number(text: &str) -> Result<i32, String> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text)
+    };
+
+    let number = if let Some(hex) = digits.strip_prefix('$').or_else(|| digits.strip_prefix("0x")) {
+        i32::from_str_radix(hex, 16)
+    } else {
+        digits.parse()
+    };
+
+    match number {
+        Ok(number) if negative => Ok(-number),
+        Ok(number) => Ok(number),
+        Err(_) => Err(format!("{} isn't a number", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_and_hex() {
+        assert_eq!(parse_number("42"), Ok(42));
+        assert_eq!(parse_number("$2A"), Ok(42));
+        assert_eq!(parse_number("0x2a"), Ok(42));
+        assert_eq!(parse_number("-42"), Ok(-42));
+        assert_eq!(parse_number("-$2A"), Ok(-42));
+    }
+
+    #[test]
+    fn rejects_bad_numbers() {
+        assert!(parse_number("").is_err());
+        assert!(parse_number("$").is_err());
+        assert!(parse_number("$2G").is_err());
+        assert!(parse_number("forty").is_err());
+    }
+
+    #[test]
+    fn addresses_fit_in_16_bits() {
+        assert_eq!(parse_address("$FFFF"), Ok(0xFFFF));
+        assert!(parse_address("$10000").is_err());
+        assert!(parse_address("-1").is_err());
+    }
+
+    #[test]
+    fn counts_can_not_be_negative() {
+        assert_eq!(parse_count("60"), Ok(60));
+        assert!(parse_count("-60").is_err());
+    }
+
+    #[test]
+    fn options_default_to_unsigned_bytes() {
+        assert_eq!(parse_options(&[]), Ok((Size::Byte, false, None)));
+    }
+
+    #[test]
+    fn options_go_in_any_order() {
+        assert_eq!(parse_options(&["signed", "16", "lives"]), Ok((Size::Word, true, Some("lives".to_owned()))));
+        assert_eq!(parse_options(&["lives", "16", "signed"]), Ok((Size::Word, true, Some("lives".to_owned()))));
+        assert_eq!(parse_options(&["16", "8", "signed", "unsigned"]), Ok((Size::Byte, false, None)));
+    }
+
+    #[test]
+    fn only_one_label() {
+        assert!(parse_options(&["lives", "score"]).is_err());
+    }
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(parse_filter("decreased"), Ok(Filter::Decreased));
+        assert!(parse_filter("smaller").is_err());
+    }
+}
@@ -2,22 +2,32 @@ use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::str;
+use crate::audio::ring_buffer::RingBuffer;
 use crate::nes::Nes;
 
 pub fn quick_save(nes: &mut Nes, file_path: &str) {
-    if let Some(ref mut c) = nes.bus().cartridge {
-        c.borrow_mut().save_mapper();
-    }
-
-    let serialized = serde_json::to_string(nes).expect("Error serializing the NES");
+    let serialized = save(nes);
     let save_data_path = get_save_data_path(file_path);
     fs::write(save_data_path, serialized).expect("Error writing save data to path");
 }
 
-pub fn quick_load(file_path: &str, buffer: Arc<Mutex<Vec<f32>>>) -> Nes {
+pub fn quick_load(file_path: &str, buffer: Arc<Mutex<RingBuffer>>) -> Nes {
     let bytes = fs::read(file_path).expect("Error reading save data");
     let data = str::from_utf8(&bytes).expect("Error converting quicksave bytes to string");
-    let mut nes: Nes = serde_json::from_str(&data).expect("Error loading save data");
+    load(data, buffer)
+}
+
+/// Serializes the whole NES, including the mapper
+pub fn save(nes: &mut Nes) -> String {
+    if let Some(ref mut c) = nes.bus().cartridge {
+        c.borrow_mut().save_mapper();
+    }
+
+    serde_json::to_string(nes).expect("Error serializing the NES")
+}
+
+pub fn load(data: &str, buffer: Arc<Mutex<RingBuffer>>) -> Nes {
+    let mut nes: Nes = serde_json::from_str(data).expect("Error loading save data");
     nes.load_buffer(buffer);
 
     if let Some(ref mut c) = nes.cpu.bus.cartridge {
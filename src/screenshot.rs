@@ -0,0 +1,89 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCREENSHOTS_FOLDER: &str = "screenshots";
+
+/// A frame at the console's own resolution, 3 bytes of RGB for each pixel
+pub struct Screenshot {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>
+}
+
+impl Screenshot {
+    /// Saves it into screenshots/game next to game.nes, named after the game and the time, and returns where it went
+    pub fn save(&self, rom_path: &str) -> String {
+        let rom_path = Path::new(rom_path);
+        let name = rom_path.file_stem().expect("Error getting name of game").to_str().expect("Error converting name of game to string");
+        let mut file_path = rom_path.parent().map(PathBuf::from).unwrap_or_default();
+        file_path.push(SCREENSHOTS_FOLDER);
+        file_path.push(name);
+        fs::create_dir_all(&file_path).expect("Error creating screenshots folder");
+
+        file_path.push(format!("{} {}.png", name, get_timestamp()));
+        let file_path = file_path.to_str().expect("Error converting screenshot path to string").to_owned();
+        self.save_png(&file_path);
+        file_path
+    }
+
+    pub fn save_png(&self, file_path: &str) {
+        let file = File::create(file_path).expect("Error creating screenshot");
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().expect("Error writing screenshot header");
+        writer.write_image_data(&self.pixels).expect("Error writing screenshot");
+    }
+}
+
+/// The time in UTC as 2024-01-31 13-45-59.123, colons aren't allowed in Windows file names
+fn get_timestamp() -> String {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before 1970");
+    let seconds = elapsed.as_secs();
+    let (year, month, day) = get_date((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}-{:02}-{:02}.{:03}",
+        year, month, day, time / 3600, (time / 60) % 60, time % 60, elapsed.subsec_millis()
+    )
+}
+
+/// Turns days since 1970-01-01 into a year, month and day, using Howard Hinnant's civil_from_days
+fn get_date(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - (day_of_era / 1460) + (day_of_era / 36_524) - (day_of_era / 146_096)) / 365;
+    let day_of_year = day_of_era - ((365 * year_of_era) + (year_of_era / 4) - (year_of_era / 100));
+    let shifted_month = ((5 * day_of_year) + 2) / 153;
+    let day = day_of_year - (((153 * shifted_month) + 2) / 5) + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = (year_of_era + (era * 400)) + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_at_epoch() {
+        assert_eq!(get_date(0), (1970, 1, 1));
+        assert_eq!(get_date(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn handles_leap_years() {
+        assert_eq!(get_date(59), (1970, 3, 1));
+        assert_eq!(get_date(11_016), (2000, 2, 29));
+        assert_eq!(get_date(11_017), (2000, 3, 1));
+    }
+
+    #[test]
+    fn matches_recent_date() {
+        assert_eq!(get_date(19_753), (2024, 1, 31));
+    }
+}
@@ -0,0 +1,64 @@
+use gif::{Encoder, EncodingError, Repeat};
+use std::fs::File;
+use std::io;
+use crate::display;
+use crate::palette::Palette;
+use crate::ppu::frame::Frame;
+use crate::region::Region;
+
+/// How hard the encoder works to build each frame's palette, NES frames never have more than 256 colours so it's always exact
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Records an animated GIF, keeping one frame out of every frame_skip.
+/// GIF delays are in hundredths of a second, so they're rounded in a way that keeps the total length right.
+pub struct GifRecorder {
+    centiseconds: f64,
+    encoder: Encoder<File>,
+    frame_skip: u32,
+    frame_time: f64,
+    frames_to_skip: u32,
+    written_centiseconds: u64
+}
+
+impl GifRecorder {
+    pub fn new(file_path: &str, region: Region, frame_skip: u32) -> io::Result<Self> {
+        let file = File::create(file_path)?;
+        let mut encoder = Encoder::new(file, display::SCREEN_WIDTH as u16, display::SCREEN_HEIGHT as u16, &[]).map_err(to_io_error)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(to_io_error)?;
+
+        Ok(GifRecorder {
+            centiseconds: 0.0,
+            encoder,
+            frame_skip: frame_skip.max(1),
+            frame_time: 100.0 / region.get_fps(),
+            frames_to_skip: 0,
+            written_centiseconds: 0
+        })
+    }
+
+    pub fn add_frame(&mut self, frame: &Frame, palette: &Palette) {
+        if self.frames_to_skip > 0 {
+            self.frames_to_skip -= 1;
+            return;
+        }
+
+        self.frames_to_skip = self.frame_skip - 1;
+
+        self.centiseconds += self.frame_time * (self.frame_skip as f64);
+        let delay = (self.centiseconds.round() as u64) - self.written_centiseconds;
+        self.written_centiseconds += delay;
+
+        let pixels = display::to_rgb(frame, palette);
+        let mut gif_frame = gif::Frame::from_rgb_speed(display::SCREEN_WIDTH as u16, display::SCREEN_HEIGHT as u16, &pixels, QUANTIZE_SPEED);
+        gif_frame.delay = delay as u16;
+        self.encoder.write_frame(&gif_frame).expect("Error writing GIF frame");
+    }
+}
+
+/// Writing the header can only fail on the file itself, anything else is still passed on as an error
+fn to_io_error(error: EncodingError) -> io::Error {
+    match error {
+        EncodingError::Io(error) => error,
+        error => io::Error::other(error)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod animated_gif;
+pub mod y4m;
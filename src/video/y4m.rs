@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use crate::audio::filter::FilterChain;
+use crate::audio::resampler::Resampler;
+use crate::audio::wav::{SampleFormat, WavWriter};
+use crate::display;
+use crate::palette::Palette;
+use crate::ppu::frame::Frame;
+use crate::region::Region;
+
+/// Records every frame to a Y4M file and the sound that went with it to a WAV file next to it, game.nes.y4m.wav for game.nes.y4m.
+/// Sound is taken straight from the APU rather than what's played, so it never drifts from the video.
+/// Y4M can't hold RGB, so the colours are converted to full range YUV without any subsampling.
+pub struct Y4mRecorder {
+    audio: Vec<(Resampler, FilterChain)>,
+    file: BufWriter<File>,
+    wav: WavWriter
+}
+
+impl Y4mRecorder {
+    pub fn new(file_path: &str, region: Region, sample_rate: u32, channels: usize) -> io::Result<Self> {
+        let file = File::create(file_path)?;
+        let mut file = BufWriter::new(file);
+
+        // The frame rate isn't a whole number, so it's kept to the nearest thousandth
+        let header = format!(
+            "YUV4MPEG2 W{} H{} F{}:1000 Ip A1:1 C444 XCOLORRANGE=FULL\n",
+            display::SCREEN_WIDTH,
+            display::SCREEN_HEIGHT,
+            (region.get_fps() * 1000.0).round()
+        );
+
+        file.write_all(header.as_bytes())?;
+
+        let input_rate = (region.get_cpu_frequency() as f64) / 2.0;
+        let audio = (0..channels)
+            .map(|_| (Resampler::new(input_rate, sample_rate as f64), FilterChain::new(sample_rate as f32)))
+            .collect();
+
+        let wav_path = format!("{}.wav", file_path);
+        Ok(Y4mRecorder {
+            audio,
+            file,
+            wav: WavWriter::new(&wav_path, sample_rate, channels as u16, SampleFormat::Pcm16)?
+        })
+    }
+
+    /// Takes the frame that was just finished along with the APU's samples from it
+    pub fn add_frame(&mut self, frame: &Frame, palette: &Palette, samples: &[[f32; 2]]) {
+        self.write_frame(frame, palette);
+        self.write_samples(samples);
+    }
+
+    fn write_frame(&mut self, frame: &Frame, palette: &Palette) {
+        let pixels = display::to_rgb(frame, palette);
+        let size = display::SCREEN_WIDTH * display::SCREEN_HEIGHT;
+        let mut planes = vec![0; size * 3];
+        for (i, pixel) in pixels.chunks(display::BYTES_PER_COLOR).enumerate() {
+            let (y, u, v) = to_yuv(pixel[0], pixel[1], pixel[2]);
+            planes[i] = y;
+            planes[size + i] = u;
+            planes[(size * 2) + i] = v;
+        }
+
+        self.file.write_all(b"FRAME\n").expect("Error writing Y4M frame");
+        self.file.write_all(&planes).expect("Error writing Y4M frame");
+    }
+
+    fn write_samples(&mut self, samples: &[[f32; 2]]) {
+        for sample in samples {
+            for (channel, (resampler, _)) in self.audio.iter_mut().enumerate() {
+                resampler.add_sample(sample[channel]);
+            }
+        }
+
+        // Every channel gets the same number of samples so they can be interleaved
+        let mut channels: Vec<Vec<f32>> = vec![];
+        for (resampler, filter) in self.audio.iter_mut() {
+            let mut output = vec![];
+            resampler.read_samples(|sample| output.push(filter.filter(sample)));
+            channels.push(output);
+        }
+
+        for i in 0..channels[0].len() {
+            for channel in channels.iter() {
+                self.wav.write(channel[i]);
+            }
+        }
+    }
+}
+
+/// BT.601 with the full 0 to 255 range
+fn to_yuv(red: u8, green: u8, blue: u8) -> (u8, u8, u8) {
+    let (red, green, blue) = (red as f32, green as f32, blue as f32);
+    let y = (0.299 * red) + (0.587 * green) + (0.114 * blue);
+    let u = 128.0 - (0.168_736 * red) - (0.331_264 * green) + (0.5 * blue);
+    let v = 128.0 + (0.5 * red) - (0.418_688 * green) - (0.081_312 * blue);
+    (clamp(y), clamp(u), clamp(v))
+}
+
+fn clamp(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}